@@ -0,0 +1,149 @@
+use std::rc::Rc;
+use std::collections::{HashSet,HashMap};
+use regex::Regex;
+use serde::{Serialize,Deserialize};
+use crate::tlc::{TLC,Row,TypeRule,ForallRule,Constructors};
+use crate::scope::{Scope,ScopeId};
+use crate::term::TermId;
+use crate::typ::Type;
+use crate::kind::Kind;
+use crate::dfa::DFA;
+use crate::token::Span;
+use crate::debug::Error;
+
+/// A Cache is the state of a context after one file has been imported into it.
+/// Regexes are stored as their source strings and compiled again when the cache is read.
+#[derive(Serialize,Deserialize)]
+struct Cache {
+   version: String,
+   sources: Vec<(String,u64)>,
+   scope: ScopeId,
+   rows: Vec<Row>,
+   poly_bindings: HashMap<(String,Type),TermId>,
+   hints: HashMap<String,Vec<ForallRule>>,
+   rules: Vec<TypeRule>,
+   scopes: Vec<Scope>,
+   value_regexes: Vec<(String,String)>,
+   regexes: Vec<(Type,String)>,
+   languages: HashMap<String,Rc<DFA>>,
+   language_implies: HashMap<String,Vec<Type>>,
+   constructors: Constructors,
+   type_is_normal: HashSet<Type>,
+   kind_is_normal: HashSet<Kind>,
+   typedef_index: HashMap<String,usize>,
+   imports: HashMap<String,(ScopeId,String)>,
+}
+
+/// FNV-1a, which unlike the std hasher is stable across compiler versions.
+fn content_hash(bs: &[u8]) -> u64 {
+   let mut h = 0xcbf29ce484222325u64;
+   for b in bs.iter() {
+      h ^= *b as u64;
+      h = h.wrapping_mul(0x100000001b3);
+   }
+   h
+}
+
+impl TLC {
+   fn cache_error(path: &str, rule: String) -> Error {
+      Error::new("Cache Error", rule, Span {
+         filename: Rc::new(path.to_string()),
+         offset_start: 0,
+         offset_end: 0,
+         linecol_start: (1,1),
+         linecol_end: (1,1),
+      })
+   }
+   /// The hash of a source, read the same way that it is tokenized, builtin preludes first.
+   fn source_hash(&self, filename: &str) -> u64 {
+      match self.prelude_source(filename) {
+         Some(src) => content_hash(src.as_bytes()),
+         None => content_hash(&std::fs::read(filename).unwrap_or_default()),
+      }
+   }
+   /// Caches are named by the version, the settings, and the content of the imported file.
+   /// Files that it imports in turn are recorded inside the cache and checked when it is read.
+   pub fn cache_path(&self, dir: &str, filename: &str) -> String {
+      let key = format!("{}\n{}\n{}\n{:?}\n{}", env!("CARGO_PKG_VERSION"), filename, self.strict,
+                        self.prelude, self.source_hash(filename));
+      std::path::Path::new(dir).join(format!("{:016x}.cache", content_hash(key.as_bytes())))
+         .to_string_lossy().to_string()
+   }
+   /// Write the state of this context, into which filename has been imported as scope.
+   pub fn write_cache(&self, path: &str, filename: &str, scope: ScopeId) -> Result<(),Error> {
+      let mut sources = vec![filename.to_string()];
+      sources.extend(self.prelude.iter().cloned());
+      sources.extend(self.imports.values().map(|(_,f)| f.clone()));
+      sources.sort();
+      sources.dedup();
+      let cache = Cache {
+         version: env!("CARGO_PKG_VERSION").to_string(),
+         sources: sources.into_iter().map(|f| { let h = self.source_hash(&f); (f,h) }).collect(),
+         scope,
+         rows: self.rows.clone(),
+         poly_bindings: self.poly_bindings.clone(),
+         hints: self.hints.clone(),
+         rules: self.rules.clone(),
+         scopes: self.scopes.clone(),
+         value_regexes: self.value_regexes.iter().map(|(n,r)| (n.clone(),r.as_str().to_string())).collect(),
+         regexes: self.regexes.iter().map(|(t,r)| (t.clone(),r.as_str().to_string())).collect(),
+         languages: self.languages.clone(),
+         language_implies: self.language_implies.clone(),
+         constructors: self.constructors.clone(),
+         type_is_normal: self.type_is_normal.clone(),
+         kind_is_normal: self.kind_is_normal.clone(),
+         typedef_index: self.typedef_index.clone(),
+         imports: self.imports.clone(),
+      };
+      let bs = bincode::serialize(&cache).map_err(|e| TLC::cache_error(path, format!("Could not serialize cache: {}", e)))?;
+      //caches are written beside their final name and then renamed, so that a reader never sees part of one
+      let tmp = format!("{}.{}.{:?}", path, std::process::id(), std::thread::current().id());
+      std::fs::write(&tmp, bs).and_then(|_| std::fs::rename(&tmp, path))
+         .map_err(|e| TLC::cache_error(path, format!("Could not write cache: {}", e)))
+   }
+   /// Read a cache into this context, which must not have checked anything yet.
+   /// Returns None if the cache is missing, was written by another version, or any of its sources has changed.
+   pub fn read_cache(&mut self, path: &str) -> Option<ScopeId> {
+      if self.rows.len()>1 || !self.scopes.is_empty() { return None; }
+      let bs = std::fs::read(path).ok()?;
+      let cache: Cache = bincode::deserialize(&bs).ok()?;
+      if cache.version != env!("CARGO_PKG_VERSION") { return None; }
+      if cache.sources.iter().any(|(f,h)| self.source_hash(f) != *h) { return None; }
+      let value_regexes = cache.value_regexes.iter().map(|(n,r)| Regex::new(r).ok().map(|r| (n.clone(),r)))
+                          .collect::<Option<Vec<(String,Regex)>>>()?;
+      let regexes = cache.regexes.iter().map(|(t,r)| Regex::new(r).ok().map(|r| (t.clone(),Rc::new(r))))
+                    .collect::<Option<Vec<(Type,Rc<Regex>)>>>()?;
+      self.rows = cache.rows;
+      self.poly_bindings = cache.poly_bindings;
+      self.hints = cache.hints;
+      self.rules = cache.rules;
+      self.scopes = cache.scopes;
+      self.value_regexes = value_regexes;
+      self.regexes = regexes;
+      self.languages = cache.languages;
+      self.language_implies = cache.language_implies;
+      self.constructors = cache.constructors;
+      self.type_is_normal = cache.type_is_normal;
+      self.kind_is_normal = cache.kind_is_normal;
+      self.typedef_index = cache.typedef_index;
+      self.imports = cache.imports;
+      Some(cache.scope)
+   }
+   /// Import a file into a fresh context, reusing the cache in dir if its sources have not changed.
+   /// A missing or stale cache is replaced after the file is checked.
+   /// A context that has already checked something imports the file as usual.
+   /// Files in a loaded cache, such as the prelude, are not checked again when later files import them.
+   pub fn import_cached(&mut self, dir: &str, filename: &str) -> Result<ScopeId,Error> {
+      if self.rows.len()>1 || !self.scopes.is_empty() {
+         return self.import_file(None, filename);
+      }
+      let path = self.cache_path(dir, filename);
+      if let Some(scope) = self.read_cache(&path) {
+         return Ok(scope);
+      }
+      let scope = self.import_file(None, filename)?;
+      std::fs::create_dir_all(dir).map_err(|e| TLC::cache_error(dir, format!("Could not create cache directory: {}", e)))?;
+      self.write_cache(&path, filename, scope)?;
+      Ok(scope)
+   }
+}
@@ -1,13 +1,20 @@
+use std::collections::BTreeMap;
 use crate::tlc::TLC;
+use serde::{Serialize,Deserialize};
 
 use lambda_mountain::Rhs;
 
-#[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Hash)]
+#[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Hash,Serialize,Deserialize)]
 pub enum Constant {
    Literal(String),
    Tuple(Vec<Constant>),
+   Op(String,Vec<Constant>), //integer arithmetic over constants and variables, such as n+1 in T[n+1]
 }
 
+/// A polynomial maps each product of variables to its coefficient.
+/// The empty product is the constant term.
+type Polynomial = BTreeMap<Vec<String>,i64>;
+
 impl std::fmt::Debug for Constant {
    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
       match self {
@@ -15,6 +22,17 @@ impl std::fmt::Debug for Constant {
         Constant::Tuple(ts) => write!(f, "({})", ts.iter()
            .map(|t|format!("{:?}",t)).collect::<Vec<String>>()
            .join(",") ),
+        Constant::Op(o,ts) => {
+           //sums are parenthesized inside of products and on the right of a difference
+           for (ti,t) in ts.iter().enumerate() {
+              if ti>0 { write!(f, "{}", o)?; }
+              match t {
+                 Constant::Op(to,_) if to!="*" && (o=="*" || (o=="-" && ti>0)) => write!(f, "({:?})", t)?,
+                 _ => write!(f, "{:?}", t)?,
+              }
+           }
+           Ok(())
+        },
       }
    }
 }
@@ -23,12 +41,150 @@ impl Constant {
    pub fn parse(_tlc: &TLC, v: &str) -> Option<Constant> {
       Some(Constant::Literal(v.to_string()))
    }
-   pub fn from_value(v: Rhs) -> Constant {
+   pub fn try_from_value(v: &Rhs) -> Option<Constant> {
       match v {
-         Rhs::App(vs) => Constant::Tuple(vs.iter().map(|v| Constant::from_value(v.clone())).collect::<Vec<Constant>>()),
-         Rhs::Variable(l) => Constant::Literal(l.clone()),
-         Rhs::Literal(l) => Constant::Literal(l.clone()),
-         t => unimplemented!("Constant::from_value {}", t)
+         Rhs::App(vs) => vs.iter().map(Constant::try_from_value).collect::<Option<Vec<Constant>>>().map(Constant::Tuple),
+         Rhs::Variable(l) => Some(Constant::Literal(l.clone())),
+         Rhs::Literal(l) => Some(Constant::Literal(l.clone())),
+         _ => None,
+      }
+   }
+   pub fn op(o: &str, l: Constant, r: Constant) -> Constant {
+      Constant::Op(o.to_string(), vec![l,r])
+   }
+   fn polynomial(&self) -> Option<Polynomial> {
+      match self {
+         Constant::Literal(l) if self.is_variable() => Some(vec![(vec![l.clone()],1)].into_iter().collect()),
+         Constant::Literal(l) => str::parse::<i64>(l).ok().map(|i| vec![(Vec::new(),i)].into_iter().collect()),
+         Constant::Op(o,ts) if ts.len()==2 => {
+            let l = ts[0].polynomial()?;
+            let r = ts[1].polynomial()?;
+            let mut p = Polynomial::new();
+            match o.as_str() {
+               "+" | "-" => {
+                  let sign = if o=="+" { 1 } else { -1 };
+                  for (m,c) in l.into_iter() { let pc = p.entry(m).or_insert(0); *pc = pc.checked_add(c)?; }
+                  for (m,c) in r.into_iter() { let pc = p.entry(m).or_insert(0); *pc = pc.checked_add(c.checked_mul(sign)?)?; }
+               },
+               "*" => {
+                  for (lm,lc) in l.iter() {
+                  for (rm,rc) in r.iter() {
+                     let mut m = lm.clone();
+                     m.extend(rm.iter().cloned());
+                     m.sort();
+                     let pc = p.entry(m).or_insert(0);
+                     *pc = pc.checked_add(lc.checked_mul(*rc)?)?;
+                  }}
+               },
+               _ => { return None; },
+            }
+            p.retain(|_,c| *c!=0);
+            Some(p)
+         },
+         _ => None,
+      }
+   }
+   fn from_polynomial(p: &Polynomial) -> Constant {
+      let mut acc: Option<Constant> = None;
+      //terms are ordered by their variables, with the constant term last
+      for (m,c) in p.iter().filter(|(m,_)| !m.is_empty()).chain(p.iter().filter(|(m,_)| m.is_empty())) {
+         //the sign of every term but the first is written as its operator
+         let k = if acc.is_none() { c.to_string() } else { c.unsigned_abs().to_string() };
+         let mut t = if m.is_empty() || k!="1" { Some(Constant::Literal(k)) } else { None };
+         for v in m.iter() {
+            t = Some(match t {
+               Some(t) => Constant::op("*", t, Constant::Literal(v.clone())),
+               None => Constant::Literal(v.clone()),
+            });
+         }
+         let t = t.unwrap_or(Constant::Literal("0".to_string()));
+         acc = Some(match acc {
+            Some(acc) => Constant::op(if *c<0 { "-" } else { "+" }, acc, t),
+            None => t,
+         });
+      }
+      acc.unwrap_or(Constant::Literal("0".to_string()))
+   }
+   /// Arithmetic is evaluated as far as its variables allow, into a canonical form.
+   /// Equal expressions, such as n+m and m+n, normalize to the same constant.
+   pub fn normalize(&self) -> Constant {
+      match self {
+         Constant::Op(o,ts) => match self.polynomial() {
+            Some(p) => Constant::from_polynomial(&p),
+            None => Constant::Op(o.clone(), ts.iter().map(|t| t.normalize()).collect()),
+         },
+         Constant::Tuple(ts) => Constant::Tuple(ts.iter().map(|t| t.normalize()).collect()),
+         c => c.clone(),
+      }
+   }
+   /// The variables of a constant.
+   pub fn vars(&self) -> Vec<String> {
+      match self {
+         Constant::Literal(l) if self.is_variable() => vec![l.clone()],
+         Constant::Literal(_) => Vec::new(),
+         Constant::Tuple(ts) | Constant::Op(_,ts) => {
+            let mut vs = ts.iter().flat_map(|t| t.vars()).collect::<Vec<String>>();
+            vs.sort(); vs.dedup();
+            vs
+         },
+      }
+   }
+   pub fn substitute(&self, subs: &dyn Fn(&str) -> Option<Constant>) -> Constant {
+      match self {
+         Constant::Literal(l) if self.is_variable() => subs(l).unwrap_or(self.clone()),
+         Constant::Literal(_) => self.clone(),
+         Constant::Tuple(ts) => Constant::Tuple(ts.iter().map(|t| t.substitute(subs)).collect()),
+         Constant::Op(o,ts) => Constant::Op(o.clone(), ts.iter().map(|t| t.substitute(subs)).collect()),
+      }
+   }
+   /// Solve self = other for the single variable of self, if self is linear in it.
+   pub fn solve(&self, other: &Constant) -> Option<(String,Constant)> {
+      let vs = self.vars();
+      if vs.len()!=1 { return None; }
+      let p = self.polynomial()?;
+      if p.keys().any(|m| m.len()>1) { return None; }
+      let a = *p.get(&vs)?;
+      let b = p.get(&Vec::new()).cloned().unwrap_or(0);
+      other.polynomial()?;
+      let rest = Constant::op("-", other.clone(), Constant::Literal(b.to_string())).normalize();
+      if rest.vars().contains(&vs[0]) { return None; }
+      match (a,&rest) {
+         (1,_) => Some((vs[0].clone(), rest)),
+         (a,Constant::Literal(r)) => {
+            let r = str::parse::<i64>(r).ok()?;
+            if r.checked_rem(a)?==0 { Some((vs[0].clone(), Constant::Literal(r.checked_div(a)?.to_string()))) } else { None }
+         },
+         _ => None,
+      }
+   }
+   /// Constants in types may be variables, such as the length n in T[n].
+   /// Variables are identifiers named in lowercase, unlike any value.
+   pub fn is_variable(&self) -> bool {
+      matches!(self, Constant::Literal(l) if l.starts_with(|c: char| c.is_ascii_lowercase())
+                                          && l.chars().all(|c| c.is_ascii_alphanumeric() || c=='_'))
+   }
+   /// A length is a natural number, a named constant, a variable, or arithmetic over them.
+   /// A name in capitals, such as N, is a type variable and can not be a length.
+   pub fn is_length(&self) -> bool {
+      match self {
+         Constant::Literal(l) => self.is_variable() || str::parse::<usize>(l).is_ok()
+                              || (l.starts_with(|c: char| c.is_ascii_uppercase()) && !l.chars().all(char::is_uppercase)),
+         Constant::Op(_,ts) => ts.iter().all(|t| t.is_length()),
+         Constant::Tuple(_) => false,
+      }
+   }
+   pub fn truthy(&self) -> Constant {
+      match self {
+         Constant::Literal(l) if l=="True" => Constant::Literal("1".to_string()),
+         Constant::Literal(l) if l=="False" => Constant::Literal("0".to_string()),
+         c => c.clone(),
+      }
+   }
+   pub fn into_value(&self) -> Rhs {
+      match self {
+         Constant::Literal(l) => Rhs::Literal(l.clone()),
+         Constant::Tuple(cs) => Rhs::App(cs.iter().map(|c| c.into_value()).collect::<Vec<Rhs>>()),
+         Constant::Op(..) => Rhs::Literal(format!("{:?}",self)),
       }
    }
 }
@@ -1,14 +1,189 @@
 use crate::token::{Span};
 
+/// An Error is reported at its span.
+/// Labels mark related spans, such as the definitions involved, and notes add context to the report.
+#[derive(Clone)]
 pub struct Error {
    pub kind: String,
    pub rule: String,
    pub span: Span,
+   context: Option<Box<Context>>, //most errors have no labels or notes, so they stay small
+}
+
+#[derive(Clone,Default)]
+struct Context {
+   labels: Vec<Label>,
+   notes: Vec<String>,
 }
 
 impl std::fmt::Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "\n{}, {}, in {} --> {},{}\n", self.kind, self.rule, self.span.filename,
-               self.span.linecol_start.0, self.span.linecol_start.1)
+               self.span.linecol_start.0, self.span.linecol_start.1)?;
+        for l in self.labels().iter() {
+           writeln!(f, "{}, in {} --> {},{}", l.message, l.span.filename, l.span.linecol_start.0, l.span.linecol_start.1)?;
+        }
+        for n in self.notes().iter() {
+           writeln!(f, "{}", n)?;
+        }
+        Ok(())
     }
 }
+
+impl Error {
+   pub fn new(kind: &str, rule: String, span: Span) -> Error {
+      Error {
+         kind: kind.to_string(),
+         rule,
+         span,
+         context: None,
+      }
+   }
+   pub fn with_label(mut self, span: &Span, message: &str) -> Error {
+      self.context.get_or_insert_with(Box::default).labels.push(Label { span: span.clone(), message: message.to_string() });
+      self
+   }
+   pub fn with_note(mut self, note: &str) -> Error {
+      self.context.get_or_insert_with(Box::default).notes.push(note.to_string());
+      self
+   }
+   pub fn labels(&self) -> &[Label] {
+      self.context.as_ref().map(|c| c.labels.as_slice()).unwrap_or(&[])
+   }
+   pub fn notes(&self) -> &[String] {
+      self.context.as_ref().map(|c| c.notes.as_slice()).unwrap_or(&[])
+   }
+   pub fn code(&self) -> String {
+      match self.kind.as_str() {
+         "Tokenization Error" => "E0001",
+         "Parse Error" => "E0002",
+         "Type Error" => "E0003",
+         "Precondition" => "E0004",
+         "Runtime" => "E0005",
+         "Ambiguous Overload" => "E0006",
+         "Import Error" => "E0007",
+         "Manifest Error" => "E0008",
+         "Cache Error" => "E0009",
+         _ => "E0000",
+      }.to_string()
+   }
+   pub fn diagnostic(&self) -> Diagnostic {
+      let mut d = Diagnostic {
+         severity: Severity::Error,
+         code: self.code(),
+         message: format!("{}, {}", self.kind, self.rule),
+         primary: Label { span: self.span.clone(), message: String::new() },
+         secondary: Vec::new(),
+         notes: Vec::new(),
+      };
+      for l in self.labels().iter() {
+         d = d.with_label(&l.span, &l.message);
+      }
+      for n in self.notes().iter() {
+         d = d.with_note(n);
+      }
+      d
+   }
+}
+
+#[derive(Clone,Copy,Eq,PartialEq,Ord,PartialOrd,Hash)]
+pub enum Severity {
+   Error,
+   Warning,
+   Note,
+}
+
+impl std::fmt::Debug for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+           Severity::Error => write!(f, "error"),
+           Severity::Warning => write!(f, "warning"),
+           Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Label {
+   pub span: Span,
+   pub message: String,
+}
+
+/// A Diagnostic is a rendered report of an Error with extra context.
+/// The primary label marks the offending span, secondary labels mark related definitions.
+#[derive(Clone)]
+pub struct Diagnostic {
+   pub severity: Severity,
+   pub code: String,
+   pub message: String,
+   pub primary: Label,
+   pub secondary: Vec<Label>,
+   pub notes: Vec<String>,
+}
+
+impl std::fmt::Debug for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}[{}]: {} --> {}:{}:{}", self.severity, self.code, self.message,
+               self.primary.span.filename, self.primary.span.linecol_start.0, self.primary.span.linecol_start.1)
+    }
+}
+
+impl Diagnostic {
+   pub fn with_label(mut self, span: &Span, message: &str) -> Diagnostic {
+      self.secondary.push(Label { span: span.clone(), message: message.to_string() });
+      self
+   }
+   pub fn with_note(mut self, note: &str) -> Diagnostic {
+      self.notes.push(note.to_string());
+      self
+   }
+   /// Render the diagnostic with source excerpts.
+   /// The source is the text of the file named by the primary span.
+   pub fn render(&self, source: &str) -> String {
+      let gutter = format!("{}", std::iter::once(&self.primary).chain(self.secondary.iter())
+                             .map(|l| Diagnostic::locate(source, &l.span).0).max().unwrap_or(0)).len();
+      let (line,col) = Diagnostic::locate(source, &self.primary.span);
+      let mut buf = format!("{:?}[{}]: {}\n", self.severity, self.code, self.message);
+      buf += &format!("{} --> {}:{}:{}\n", " ".repeat(gutter), self.primary.span.filename, line, col);
+      buf += &Diagnostic::render_label(source, &self.primary, '^', gutter);
+      for l in self.secondary.iter() {
+         if l.span.filename == self.primary.span.filename {
+            buf += &Diagnostic::render_label(source, l, '-', gutter);
+         } else {
+            buf += &format!("{} ::: {}:{}:{} {}\n", " ".repeat(gutter),
+                            l.span.filename, l.span.linecol_start.0, l.span.linecol_start.1, l.message);
+         }
+      }
+      for n in self.notes.iter() {
+         buf += &format!("{} = note: {}\n", " ".repeat(gutter), n);
+      }
+      buf
+   }
+   fn locate(source: &str, span: &Span) -> (usize,usize) {
+      //line and column are recovered from the byte offset whenever the source is available
+      let start = span.offset_start;
+      if start > source.len() || !source.is_char_boundary(start) {
+         return span.linecol_start;
+      }
+      let line_start = source[..start].rfind('\n').map(|i| i+1).unwrap_or(0);
+      (source[..start].matches('\n').count()+1, source[line_start..start].chars().count()+1)
+   }
+   fn render_label(source: &str, label: &Label, underline: char, gutter: usize) -> String {
+      let start = label.span.offset_start;
+      if start > source.len() || !source.is_char_boundary(start) {
+         return String::new();
+      }
+      let line_start = source[..start].rfind('\n').map(|i| i+1).unwrap_or(0);
+      let line_end = source[start..].find('\n').map(|i| start+i).unwrap_or(source.len());
+      let end = std::cmp::max(start+1, std::cmp::min(label.span.offset_end, line_end));
+      let mut buf = format!("{} |\n", " ".repeat(gutter));
+      buf += &format!("{:>w$} | {}\n", Diagnostic::locate(source, &label.span).0, &source[line_start..line_end], w=gutter);
+      let marker = format!("{} | {}{} {}", " ".repeat(gutter),
+                      " ".repeat(source[line_start..start].chars().count()),
+                      underline.to_string().repeat(end-start),
+                      label.message);
+      buf += marker.trim_end();
+      buf += "\n";
+      buf
+   }
+}
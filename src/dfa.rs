@@ -1,5 +1,7 @@
+use std::collections::{HashMap,HashSet};
+use serde::{Serialize,Deserialize};
 
-#[derive(Clone)]
+#[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Hash,Serialize,Deserialize)]
 pub enum LiteralPattern {
    Char(char),
    String(String),
@@ -7,9 +9,689 @@ pub enum LiteralPattern {
    Variable(String),
 }
 
-#[derive(Clone)]
+impl std::fmt::Debug for LiteralPattern {
+   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      match self {
+         LiteralPattern::Char(c) => write!(f, "'{}'", c),
+         LiteralPattern::String(s) => write!(f, "\"{}\"", s),
+         LiteralPattern::Range(r) => write!(f, "[{}]", r),
+         LiteralPattern::Variable(v) => write!(f, "{}", v),
+      }
+   }
+}
+
+impl LiteralPattern {
+   /// A Range is a character class such as 0-9 or abc.
+   pub fn ranges(r: &str) -> Vec<Range> {
+      let cs = r.chars().collect::<Vec<char>>();
+      let mut rs = Vec::new();
+      let mut ci = 0;
+      while ci < cs.len() {
+         if ci+2 < cs.len() && cs[ci+1]=='-' {
+            rs.push((cs[ci],cs[ci+2]));
+            ci += 3;
+         } else {
+            rs.push((cs[ci],cs[ci]));
+            ci += 1;
+         }
+      }
+      rs
+   }
+   pub fn range_accepts(r: &str, c: char) -> bool {
+      LiteralPattern::ranges(r).iter().any(|(lo,hi)| *lo <= c && c <= *hi)
+   }
+   /// Split a textual value into one substring per pattern part.
+   /// Variables match one or more characters, and the shortest successful split is chosen from the left.
+   pub fn split(ps: &[LiteralPattern], s: &str) -> Option<Vec<String>> {
+      let Some(p) = ps.first() else {
+         return if s.is_empty() { Some(Vec::new()) } else { None };
+      };
+      let mut heads = Vec::new();
+      match p {
+         LiteralPattern::Char(c) => {
+            if s.starts_with(*c) { heads.push(c.len_utf8()); }
+         },
+         LiteralPattern::String(v) => {
+            if s.starts_with(v.as_str()) { heads.push(v.len()); }
+         },
+         LiteralPattern::Range(r) => {
+            if let Some(c) = s.chars().next() {
+            if LiteralPattern::range_accepts(r, c) {
+               heads.push(c.len_utf8());
+            }}
+         },
+         LiteralPattern::Variable(_) => {
+            heads = s.char_indices().map(|(ci,c)| ci + c.len_utf8()).collect();
+         },
+      }
+      for h in heads {
+         if let Some(mut tail) = LiteralPattern::split(&ps[1..], &s[h..]) {
+            tail.insert(0, s[..h].to_string());
+            return Some(tail);
+         }
+      }
+      None
+   }
+}
+
+/// A DFA accepts a set of strings.
+/// Each state has its own transitions, labelled with disjoint inclusive character ranges in order, and a missing transition rejects.
+#[derive(Clone,Eq,PartialEq,Hash,Serialize,Deserialize)]
 pub struct DFA {
    pub start: usize,
    pub states: Vec<bool>, //bool = is accept
-   pub transitions: Vec<(usize,char,usize)>,
+   pub transitions: Vec<Vec<(Range,usize)>>, //indexed by state
+}
+
+impl std::fmt::Debug for DFA {
+   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      write!(f, "DFA(start={}", self.start)?;
+      for (si,a) in self.states.iter().enumerate() {
+         write!(f, "; {}{}:", si, if *a { "*" } else { "" })?;
+         for ((lo,hi),t) in self.transitions[si].iter() {
+            if lo==hi { write!(f, " {:?}->{}", lo, t)?; }
+            else { write!(f, " {:?}-{:?}->{}", lo, hi, t)?; }
+         }
+      }
+      write!(f, ")")
+   }
+}
+
+/// An inclusive range of characters.
+pub type Range = (char,char);
+
+const ANY: Range = ('\0',char::MAX);
+
+/// Automata that would grow past these sizes are not constructed, so a language that is too large is unknown rather than slow.
+const NFA_LIMIT: usize = 16384;
+const STATE_LIMIT: usize = 4096;
+
+fn succ(c: char) -> Option<char> {
+   if c == '\u{D7FF}' { return Some('\u{E000}'); }
+   char::from_u32(c as u32 + 1)
+}
+
+fn pred(c: char) -> Option<char> {
+   if c == '\u{E000}' { return Some('\u{D7FF}'); }
+   (c as u32).checked_sub(1).and_then(char::from_u32)
+}
+
+/// Split the alphabet into the coarsest ranges that do not cross any boundary of the given ranges.
+fn partition(rs: &[Range]) -> Vec<Range> {
+   let mut cuts = vec!['\0'];
+   for (lo,hi) in rs.iter() {
+      cuts.push(*lo);
+      if let Some(c) = succ(*hi) { cuts.push(c); }
+   }
+   cuts.sort();
+   cuts.dedup();
+   let mut ps = Vec::new();
+   for (ci,c) in cuts.iter().enumerate() {
+      let hi = if let Some(n) = cuts.get(ci+1) { pred(*n).unwrap_or(*c) } else { char::MAX };
+      ps.push((*c,hi));
+   }
+   ps
+}
+
+/// Ranges of the alphabet that are not covered by any of the given ranges.
+fn negate(rs: &[Range]) -> Vec<Range> {
+   partition(rs).into_iter().filter(|(lo,_)| !rs.iter().any(|(l,h)| l<=lo && lo<=h)).collect()
+}
+
+enum Regex {
+   Class(Vec<Range>),
+   Concat(Vec<Regex>),
+   Alt(Vec<Regex>),
+   Repeat(Box<Regex>,usize,Option<usize>),
+}
+
+/// Parser for the regex subset used in typedefs.
+struct RegexParser {
+   cs: Vec<char>,
+   at: usize,
+}
+
+impl RegexParser {
+   fn peek(&self) -> Option<char> {
+      self.cs.get(self.at).copied()
+   }
+   fn take(&mut self) -> Option<char> {
+      let c = self.peek();
+      self.at += 1;
+      c
+   }
+   fn alt(&mut self) -> Option<Regex> {
+      let mut rs = vec![self.concat()?];
+      while self.peek() == Some('|') {
+         self.take();
+         rs.push(self.concat()?);
+      }
+      Some(if rs.len()==1 { rs.remove(0) } else { Regex::Alt(rs) })
+   }
+   fn concat(&mut self) -> Option<Regex> {
+      let mut rs = Vec::new();
+      while let Some(c) = self.peek() {
+         if c == '|' || c == ')' { break; }
+         rs.push(self.repeat()?);
+      }
+      Some(Regex::Concat(rs))
+   }
+   fn number(&mut self) -> Option<usize> {
+      let start = self.at;
+      while self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) { self.at += 1; }
+      self.cs[start..self.at].iter().collect::<String>().parse::<usize>().ok()
+   }
+   fn repeat(&mut self) -> Option<Regex> {
+      let mut r = self.atom()?;
+      loop {
+         let (lo,hi) = match self.peek() {
+            Some('*') => { self.take(); (0,None) },
+            Some('+') => { self.take(); (1,None) },
+            Some('?') => { self.take(); (0,Some(1)) },
+            Some('{') => {
+               self.take();
+               let lo = self.number()?;
+               let hi = if self.peek() == Some(',') {
+                  self.take();
+                  if self.peek() == Some('}') { None } else { Some(self.number()?) }
+               } else { Some(lo) };
+               if self.take() != Some('}') { return None; }
+               (lo,hi)
+            },
+            _ => return Some(r),
+         };
+         r = Regex::Repeat(Box::new(r), lo, hi);
+      }
+   }
+   fn escape(&mut self) -> Option<char> {
+      match self.take()? {
+         'n' => Some('\n'),
+         't' => Some('\t'),
+         'r' => Some('\r'),
+         //character class escapes are Unicode-aware in the regex crate, so they are not approximated here
+         c if c.is_alphanumeric() => None,
+         c => Some(c),
+      }
+   }
+   fn atom(&mut self) -> Option<Regex> {
+      match self.take()? {
+         '(' => {
+            if self.cs[self.at..].starts_with(&['?',':']) { self.at += 2; }
+            let r = self.alt()?;
+            if self.take() != Some(')') { return None; }
+            Some(r)
+         },
+         '[' => {
+            let negated = self.peek() == Some('^');
+            if negated { self.take(); }
+            let mut rs = Vec::new();
+            let mut first = true;
+            loop {
+               let lo = match self.take()? {
+                  ']' if !first => break,
+                  '\\' => self.escape()?,
+                  '[' => return None,
+                  c => c,
+               };
+               first = false;
+               let hi = if self.peek() == Some('-') && self.cs.get(self.at+1) != Some(&']') && self.cs.get(self.at+1).is_some() {
+                  self.take();
+                  match self.take()? {
+                     '\\' => self.escape()?,
+                     c => c,
+                  }
+               } else { lo };
+               if hi < lo { return None; }
+               rs.push((lo,hi));
+            }
+            Some(Regex::Class(if negated { negate(&rs) } else { rs }))
+         },
+         '.' => Some(Regex::Class(negate(&[('\n','\n')]))),
+         '\\' => { let c = self.escape()?; Some(Regex::Class(vec![(c,c)])) },
+         '*' | '+' | '?' | '{' | ')' | '^' | '$' => None,
+         c => Some(Regex::Class(vec![(c,c)])),
+      }
+   }
+}
+
+/// An NFA with epsilon transitions, used while constructing a DFA.
+struct Nfa {
+   edges: Vec<Vec<(Option<Range>,usize)>>,
+}
+
+impl Nfa {
+   fn state(&mut self) -> Option<usize> {
+      if self.edges.len() >= NFA_LIMIT { return None; }
+      self.edges.push(Vec::new());
+      Some(self.edges.len()-1)
+   }
+   fn edge(&mut self, s: usize, r: Option<Range>, t: usize) {
+      self.edges[s].push((r,t));
+   }
+   /// Add a fragment for the regex, returning its entry and exit states.
+   fn fragment(&mut self, r: &Regex) -> Option<(usize,usize)> {
+      let s = self.state()?;
+      let t = self.state()?;
+      match r {
+         Regex::Class(rs) => {
+            for r in rs.iter() { self.edge(s, Some(*r), t); }
+         },
+         Regex::Concat(rs) => {
+            let mut at = s;
+            for r in rs.iter() {
+               let (rs,rt) = self.fragment(r)?;
+               self.edge(at, None, rs);
+               at = rt;
+            }
+            self.edge(at, None, t);
+         },
+         Regex::Alt(rs) => {
+            for r in rs.iter() {
+               let (rs,rt) = self.fragment(r)?;
+               self.edge(s, None, rs);
+               self.edge(rt, None, t);
+            }
+         },
+         Regex::Repeat(r,lo,hi) => {
+            let mut at = s;
+            for _ in 0..*lo {
+               let (rs,rt) = self.fragment(r)?;
+               self.edge(at, None, rs);
+               at = rt;
+            }
+            if let Some(hi) = hi {
+               for _ in *lo..*hi {
+                  let (rs,rt) = self.fragment(r)?;
+                  self.edge(at, None, rs);
+                  self.edge(at, None, t);
+                  at = rt;
+               }
+            } else {
+               let (rs,rt) = self.fragment(r)?;
+               self.edge(at, None, rs);
+               self.edge(rt, None, rs);
+               self.edge(rt, None, t);
+            }
+            self.edge(at, None, t);
+         },
+      }
+      Some((s,t))
+   }
+   fn closure(&self, ss: &mut Vec<usize>) {
+      let mut seen = ss.iter().copied().collect::<HashSet<usize>>();
+      let mut stack = ss.clone();
+      while let Some(s) = stack.pop() {
+         for (r,t) in self.edges[s].iter() {
+            if r.is_none() && seen.insert(*t) {
+               ss.push(*t);
+               stack.push(*t);
+            }
+         }
+      }
+      ss.sort();
+   }
+   /// Subset construction.
+   /// Returns None if the DFA would have more than STATE_LIMIT states.
+   fn determinize(&self, start: usize, accept: usize) -> Option<DFA> {
+      let mut init = vec![start];
+      self.closure(&mut init);
+      let mut index = HashMap::new();
+      index.insert(init.clone(), 0);
+      let mut sets = vec![init];
+      let mut dfa = DFA { start: 0, states: Vec::new(), transitions: Vec::new() };
+      let mut si = 0;
+      while si < sets.len() {
+         let set = sets[si].clone();
+         dfa.states.push(set.binary_search(&accept).is_ok());
+         let out = set.iter().flat_map(|s| self.edges[*s].iter().filter_map(|(r,t)| r.map(|r| (r,*t))))
+                      .collect::<Vec<(Range,usize)>>();
+         let rs = out.iter().map(|(r,_)| *r).collect::<Vec<Range>>();
+         let mut ts = Vec::new();
+         for (lo,hi) in partition(&rs) {
+            let mut next = out.iter().filter(|((l,h),_)| *l<=lo && lo<=*h).map(|(_,t)| *t).collect::<Vec<usize>>();
+            if next.is_empty() { continue; }
+            next.sort();
+            next.dedup();
+            self.closure(&mut next);
+            let ti = if let Some(ti) = index.get(&next) { *ti } else {
+               if sets.len() >= STATE_LIMIT { return None; }
+               index.insert(next.clone(), sets.len());
+               sets.push(next);
+               sets.len()-1
+            };
+            ts.push(((lo,hi),ti));
+         }
+         dfa.transitions.push(ts);
+         si += 1;
+      }
+      dfa.merge_ranges();
+      Some(dfa)
+   }
+}
+
+impl DFA {
+   /// Construct a DFA from a regular expression as written in a typedef, with or without the slashes.
+   /// Returns None if the expression uses syntax outside of the supported subset, or if its automaton is too large.
+   pub fn from_regex(pattern: &str) -> Option<DFA> {
+      let pattern = pattern.strip_prefix('/').and_then(|p| p.strip_suffix('/')).unwrap_or(pattern);
+      let (anchor_start, pattern) = match pattern.strip_prefix('^') { Some(p) => (true,p), None => (false,pattern) };
+      let (anchor_end, pattern) = match pattern.strip_suffix('$') {
+         Some(p) if !p.ends_with('\\') => (true,p),
+         _ => (false,pattern),
+      };
+      let mut parser = RegexParser { cs: pattern.chars().collect(), at: 0 };
+      let r = parser.alt()?;
+      if parser.at < parser.cs.len() { return None; }
+      let mut rs = Vec::new();
+      //an unanchored expression may match anywhere in the string
+      if !anchor_start { rs.push(Regex::Repeat(Box::new(Regex::Class(vec![ANY])), 0, None)); }
+      rs.push(r);
+      if !anchor_end { rs.push(Regex::Repeat(Box::new(Regex::Class(vec![ANY])), 0, None)); }
+      let mut nfa = Nfa { edges: Vec::new() };
+      let (s,t) = nfa.fragment(&Regex::Concat(rs))?;
+      Some(nfa.determinize(s, t)?.minimize())
+   }
+   /// Construct a DFA that accepts the same strings as a literal pattern.
+   /// Variables match one or more characters.
+   /// Returns None if the pattern is too long to construct.
+   pub fn from_literal(ps: &[LiteralPattern]) -> Option<DFA> {
+      let mut rs = Vec::new();
+      for p in ps.iter() {
+         match p {
+            LiteralPattern::Char(c) => { rs.push(Regex::Class(vec![(*c,*c)])); },
+            LiteralPattern::String(v) => {
+               for c in v.chars() { rs.push(Regex::Class(vec![(c,c)])); }
+            },
+            LiteralPattern::Range(r) => { rs.push(Regex::Class(LiteralPattern::ranges(r))); },
+            LiteralPattern::Variable(_) => {
+               rs.push(Regex::Repeat(Box::new(Regex::Class(vec![ANY])), 1, None));
+            },
+         }
+      }
+      let mut nfa = Nfa { edges: Vec::new() };
+      let (s,t) = nfa.fragment(&Regex::Concat(rs))?;
+      Some(nfa.determinize(s, t)?.minimize())
+   }
+   /// The DFA that accepts no strings.
+   pub fn empty() -> DFA {
+      DFA { start: 0, states: vec![false], transitions: vec![Vec::new()] }
+   }
+   pub fn next(&self, s: usize, c: char) -> Option<usize> {
+      let ts = &self.transitions[s];
+      let ti = ts.partition_point(|((lo,_),_)| *lo<=c);
+      if ti==0 { return None; }
+      let ((_,hi),t) = ts[ti-1];
+      if c<=hi { Some(t) } else { None }
+   }
+   pub fn accepts(&self, s: &str) -> bool {
+      let mut at = self.start;
+      for c in s.chars() {
+         match self.next(at, c) {
+            Some(t) => { at = t; },
+            None => { return false; },
+         }
+      }
+      self.states[at]
+   }
+   /// True if no string is accepted.
+   pub fn is_empty(&self) -> bool {
+      !self.reachable(self.start).iter().any(|s| self.states[*s])
+   }
+   /// One of the shortest accepted strings, if any string is accepted.
+   pub fn example(&self) -> Option<String> {
+      let mut seen = vec![false; self.states.len()];
+      seen[self.start] = true;
+      let mut paths = vec![(self.start,String::new())];
+      let mut pi = 0;
+      while pi < paths.len() {
+         let (s,w) = paths[pi].clone();
+         if self.states[s] { return Some(w); }
+         for ((lo,_),t) in self.transitions[s].iter() {
+            if !seen[*t] {
+               seen[*t] = true;
+               paths.push((*t,format!("{}{}",w,lo)));
+            }
+         }
+         pi += 1;
+      }
+      None
+   }
+   /// Up to limit accepted strings, shortest first.
+   /// Each range contributes at most a few characters, printable ones when it has any.
+   pub fn enumerate(&self, limit: usize) -> Vec<String> {
+      let mut found = Vec::new();
+      let mut paths = std::collections::VecDeque::from(vec![(self.start,String::new())]);
+      while let Some((s,w)) = paths.pop_front() {
+         if found.len() >= limit { break; }
+         if self.states[s] { found.push(w.clone()); }
+         for ((lo,hi),t) in self.transitions[s].iter() {
+            let mut cs = (*lo..=*hi).filter(|c| c.is_ascii_graphic() && *c!='\\').take(10).collect::<Vec<char>>();
+            if cs.is_empty() { cs.push(*lo); }
+            for c in cs {
+               paths.push_back((*t,format!("{}{}",w,c)));
+            }
+         }
+         //the frontier only has to be long enough to fill the remaining results
+         if paths.len() > limit*16 { paths.truncate(limit*16); }
+      }
+      found
+   }
+   fn reachable(&self, s: usize) -> Vec<usize> {
+      let mut seen = vec![false; self.states.len()];
+      seen[s] = true;
+      let mut order = vec![s];
+      let mut si = 0;
+      while si < order.len() {
+         for (_,t) in self.transitions[order[si]].iter() {
+            if !seen[*t] {
+               seen[*t] = true;
+               order.push(*t);
+            }
+         }
+         si += 1;
+      }
+      order
+   }
+   /// Order the ranges of each state and join adjacent ranges that lead to the same state.
+   fn merge_ranges(&mut self) {
+      for ts in self.transitions.iter_mut() {
+         ts.sort_by_key(|((lo,_),_)| *lo);
+         let mut merged: Vec<(Range,usize)> = Vec::new();
+         for ((lo,hi),t) in std::mem::take(ts) {
+            if let Some(((_,lhi),lt)) = merged.last_mut() {
+               if *lt==t && succ(*lhi)==Some(lo) {
+                  *lhi = hi;
+                  continue;
+               }
+            }
+            merged.push(((lo,hi),t));
+         }
+         *ts = merged;
+      }
+   }
+   /// Add a rejecting sink state so that every state has a transition on every character.
+   pub fn complete(&self) -> DFA {
+      let mut dfa = self.clone();
+      let sink = dfa.states.len();
+      let mut used = false;
+      for (s,ts) in self.transitions.iter().enumerate() {
+         let rs = ts.iter().map(|(r,_)| *r).collect::<Vec<Range>>();
+         for r in negate(&rs) {
+            dfa.transitions[s].push((r,sink));
+            used = true;
+         }
+      }
+      if used {
+         dfa.states.push(false);
+         dfa.transitions.push(vec![(ANY,sink)]);
+      }
+      dfa.merge_ranges();
+      dfa
+   }
+   /// Accept exactly the strings that are rejected.
+   pub fn complement(&self) -> DFA {
+      let mut dfa = self.complete();
+      for a in dfa.states.iter_mut() { *a = !*a; }
+      dfa.minimize()
+   }
+   /// Run both automata in lockstep, accepting when op accepts the pair of results.
+   /// Returns None if the product would have more than STATE_LIMIT states.
+   pub fn product(&self, other: &DFA, op: fn(bool,bool) -> bool) -> Option<DFA> {
+      let l = self.complete();
+      let r = other.complete();
+      let mut index = HashMap::new();
+      index.insert((l.start,r.start), 0);
+      let mut pairs = vec![(l.start,r.start)];
+      let mut dfa = DFA { start: 0, states: Vec::new(), transitions: Vec::new() };
+      let mut pi = 0;
+      while pi < pairs.len() {
+         let (ls,rs) = pairs[pi];
+         dfa.states.push(op(l.states[ls], r.states[rs]));
+         let ranges = l.transitions[ls].iter().chain(r.transitions[rs].iter()).map(|(r,_)| *r).collect::<Vec<Range>>();
+         let mut ts = Vec::new();
+         for (lo,hi) in partition(&ranges) {
+            let (Some(lt),Some(rt)) = (l.next(ls,lo), r.next(rs,lo)) else { continue };
+            let ti = if let Some(ti) = index.get(&(lt,rt)) { *ti } else {
+               if pairs.len() >= STATE_LIMIT { return None; }
+               index.insert((lt,rt), pairs.len());
+               pairs.push((lt,rt));
+               pairs.len()-1
+            };
+            ts.push(((lo,hi),ti));
+         }
+         dfa.transitions.push(ts);
+         pi += 1;
+      }
+      Some(dfa.minimize())
+   }
+   pub fn intersection(&self, other: &DFA) -> Option<DFA> {
+      self.product(other, |l,r| l && r)
+   }
+   pub fn union(&self, other: &DFA) -> Option<DFA> {
+      self.product(other, |l,r| l || r)
+   }
+   /// True if every string accepted by self is also accepted by other.
+   /// Returns None if the automata are too large to compare.
+   pub fn is_subset(&self, other: &DFA) -> Option<bool> {
+      Some(self.intersection(&other.complement())?.is_empty())
+   }
+   /// Accept a string accepted by self followed by a string accepted by other.
+   pub fn concat(&self, other: &DFA) -> Option<DFA> {
+      let mut nfa = Nfa { edges: Vec::new() };
+      let offset = self.states.len();
+      for _ in 0..(self.states.len()+other.states.len()+1) { nfa.state()?; }
+      let accept = offset + other.states.len();
+      for (s,ts) in self.transitions.iter().enumerate() {
+         for (r,t) in ts.iter() { nfa.edge(s, Some(*r), *t); }
+      }
+      for (s,ts) in other.transitions.iter().enumerate() {
+         for (r,t) in ts.iter() { nfa.edge(offset+s, Some(*r), offset+*t); }
+      }
+      for (s,a) in self.states.iter().enumerate() {
+         if *a { nfa.edge(s, None, offset+other.start); }
+      }
+      for (s,a) in other.states.iter().enumerate() {
+         if *a { nfa.edge(offset+s, None, accept); }
+      }
+      Some(nfa.determinize(self.start, accept)?.minimize())
+   }
+   /// Accept the strings w such that c followed by w is accepted.
+   pub fn strip_prefix(&self, c: char) -> DFA {
+      match self.next(self.start, c) {
+         Some(s) => DFA { start: s, states: self.states.clone(), transitions: self.transitions.clone() }.minimize(),
+         None => DFA::empty(),
+      }
+   }
+   /// Accept the strings w such that w followed by c is accepted.
+   pub fn strip_suffix(&self, c: char) -> DFA {
+      let states = (0..self.states.len()).map(|s| self.next(s, c).map(|t| self.states[t]).unwrap_or(false)).collect();
+      DFA { start: self.start, states, transitions: self.transitions.clone() }.minimize()
+   }
+   /// Merge equivalent states and drop states that can never accept.
+   pub fn minimize(&self) -> DFA {
+      let dfa = self.complete();
+      let live = dfa.reachable(dfa.start);
+      //states are refined into classes until every member of a class behaves the same on every character
+      //the characters are split into symbols that every live state treats uniformly
+      let symbols = partition(&live.iter().flat_map(|s| dfa.transitions[*s].iter().map(|(r,_)| *r)).collect::<Vec<Range>>());
+      let mut preds = vec![vec![Vec::new(); dfa.states.len()]; symbols.len()];
+      for s in live.iter() {
+         for (k,(lo,_)) in symbols.iter().enumerate() {
+            if let Some(t) = dfa.next(*s, *lo) { preds[k][t].push(*s); }
+         }
+      }
+      let mut class = vec![0; dfa.states.len()];
+      let mut members: Vec<Vec<usize>> = Vec::new();
+      for accepting in [true,false] {
+         let ms = live.iter().copied().filter(|s| dfa.states[*s]==accepting).collect::<Vec<usize>>();
+         if ms.is_empty() { continue; }
+         for s in ms.iter() { class[*s] = members.len(); }
+         members.push(ms);
+      }
+      //Hopcroft's algorithm: each class is used to split the others on each symbol
+      let mut pending = (0..members.len()).flat_map(|c| (0..symbols.len()).map(move |k| (c,k))).collect::<HashSet<(usize,usize)>>();
+      let mut work = pending.iter().copied().collect::<Vec<(usize,usize)>>();
+      while let Some((c,k)) = work.pop() {
+         pending.remove(&(c,k));
+         let mut split: HashMap<usize,Vec<usize>> = HashMap::new();
+         for t in members[c].iter() {
+            for s in preds[k][*t].iter() { split.entry(class[*s]).or_default().push(*s); }
+         }
+         for (y,xs) in split.into_iter() {
+            if xs.len() == members[y].len() { continue; }
+            let n = members.len();
+            for s in xs.iter() { class[*s] = n; }
+            members[y].retain(|s| class[*s]==y);
+            members.push(xs);
+            for k in 0..symbols.len() {
+               let add = if pending.contains(&(y,k)) || members[n].len() <= members[y].len() { n } else { y };
+               if pending.insert((add,k)) { work.push((add,k)); }
+            }
+         }
+      }
+      let classes = members.len();
+      //every member of a class behaves the same, so the first member represents the class
+      let mut accept = vec![false; classes];
+      let mut edges: Vec<Option<&Vec<(Range,usize)>>> = vec![None; classes];
+      for s in live.iter() {
+         accept[class[*s]] = dfa.states[*s];
+         edges[class[*s]].get_or_insert(&dfa.transitions[*s]);
+      }
+      let edges = edges.into_iter().map(|ts| ts.map(|ts| ts.iter().map(|(r,t)| (*r,class[*t])).collect()).unwrap_or_default())
+                       .collect::<Vec<Vec<(Range,usize)>>>();
+      //classes that can not reach an accepting state are left out
+      let mut preds = vec![Vec::new(); classes];
+      for (c,ts) in edges.iter().enumerate() {
+         for (_,t) in ts.iter() { preds[*t].push(c); }
+      }
+      let mut alive = accept.clone();
+      let mut stack = (0..classes).filter(|c| accept[*c]).collect::<Vec<usize>>();
+      while let Some(c) = stack.pop() {
+         for p in preds[c].iter() {
+            if !alive[*p] { alive[*p] = true; stack.push(*p); }
+         }
+      }
+      //classes are renumbered in order of their discovery from the start state
+      let mut position = vec![None; classes];
+      position[class[dfa.start]] = Some(0);
+      let mut order = vec![class[dfa.start]];
+      let mut oi = 0;
+      while oi < order.len() {
+         for (_,t) in edges[order[oi]].iter() {
+            if alive[*t] && position[*t].is_none() {
+               position[*t] = Some(order.len());
+               order.push(*t);
+            }
+         }
+         oi += 1;
+      }
+      let mut min = DFA {
+         start: 0,
+         states: order.iter().map(|c| accept[*c]).collect(),
+         transitions: order.iter().map(|c| edges[*c].iter()
+                         .filter_map(|(r,t)| if alive[*t] { position[*t].map(|ti| (*r,ti)) } else { None }).collect()).collect(),
+      };
+      min.merge_ranges();
+      min
+   }
 }
@@ -1,6 +1,7 @@
+use serde::{Serialize,Deserialize};
 
 /// All Kinds are Named Strings with optional Parameters.
-#[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Hash)]
+#[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Hash,Serialize,Deserialize)]
 pub enum Kind {
    Nil,
    Named(String,Vec<Kind>),
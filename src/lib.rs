@@ -37,3 +37,15 @@ pub mod dfa;
 
 /// General quality-of-life services in Rust
 pub mod util;
+
+/// The Manifest module reads lsts.toml package descriptions
+pub mod manifest;
+
+/// The Cache module saves checked contexts to disk so that preludes are not checked again
+pub mod cache;
+
+/// The REPL module defines an interactive read-eval-print loop over one context
+pub mod repl;
+
+/// The LSP module serves diagnostics, hover, and go-to-definition over the Language Server Protocol
+pub mod lsp;
@@ -1,12 +1,13 @@
 use std::collections::{HashMap};
 use crate::term::{Term,TermId,LetTerm};
 use crate::debug::{Error};
-use crate::token::{Symbol,TokenReader,span_of,tokenize_file};
+use crate::token::{Symbol,Span,TokenReader,span_of,span_since,tokenize_file};
 use crate::scope::{ScopeId,Scope};
 use crate::tlc::{TLC,TypeRule,Invariant,TypedefRule,TypedefBranch};
 use crate::constant::{Constant};
 use crate::typ::{Type};
 use crate::kind::{Kind};
+use crate::dfa::LiteralPattern;
 
 fn peek_is_regex(tokens: &mut TokenReader) -> bool {
    if let Ok(Some(t)) = tokens.peek() {
@@ -31,20 +32,14 @@ fn pop_is(rule: &str, tokens: &mut TokenReader, is: &Vec<Symbol>) -> Result<Symb
    match tokens.take()? {
       Some(t) => {
          if !is.contains(&t.symbol) {
-            Err(Error {
-               kind: "Parse Error".to_string(),
-               rule: format!("unexpected Symbol {:?} in rule {}, expected one of {}", &t.symbol, rule,
+            Err(Error::new("Parse Error",
+               format!("unexpected Symbol {:?} in rule {}, expected one of {}", &t.symbol, rule,
                is.iter().map(|s|format!("{:?}",s)).collect::<Vec<String>>().join(" or ") ),
-               span: span_of(tokens),
-            })
+               span_of(tokens)))
          } else { Ok(t.symbol.clone()) }
       },
       None => {
-         Err(Error {
-            kind: "Parse Error".to_string(),
-            rule: format!("unexpected End-Of-File in rule {}", rule),
-            span: span_of(tokens),
-         })
+         Err(Error::new("Parse Error", format!("unexpected End-Of-File in rule {}", rule), span_of(tokens)))
       },
    }
 }
@@ -252,11 +247,7 @@ pub fn ll1_type_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
    }
 
    if normal && &kinds == &tlc.term_kind {
-      return Err(Error {
-         kind: "Parse Error".to_string(),
-         rule: format!("Term type {} cannot be normal", &t),
-         span: span.clone(),
-      })
+      return Err(Error::new("Parse Error", format!("Term type {} cannot be normal", &t), span.clone()))
    }
    if normal {
       if constructors.len()==0 {
@@ -385,6 +376,7 @@ pub fn ll1_let_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
    };
    if dot { ident = format!(".{}", ident); };
    let mut pars: Vec<Vec<(String,Type,Kind)>> = Vec::new();
+   let mut pspans = Vec::new();
    let mut rt = tlc.nil_type.clone();
    let mut rk = tlc.term_kind.clone();
    let mut t: Option<TermId> = None;
@@ -399,7 +391,10 @@ pub fn ll1_let_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
          let mut ident = "_".to_string();
          let mut typ = tlc.nil_type.clone();
          let mut kind = tlc.term_kind.clone();
+         //a parameter is defined at its name, or at the let if it has no name
+         let mut ispan = span.clone();
          if let Some(Symbol::Ident(id)) = tokens.peek_symbol()? {
+            ispan = span_of(tokens);
             tokens.take_symbol()?;
             ident = id.clone();
          }
@@ -417,6 +412,7 @@ pub fn ll1_let_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
             kind = tlc.constant_kind.clone();
          };
          itks.push((ident,typ,kind));
+         pspans.push(ispan);
       }
       pop_is("let-stmt", tokens, &vec![Symbol::RightParen])?;
       pars.push(itks);
@@ -439,17 +435,15 @@ pub fn ll1_let_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
    if rt.is_constant() {
       rk = tlc.constant_kind.clone();
    }
-   for itks in pars.iter() {
-      for (i,t,k) in itks.iter() {
-         let t = t.normalize();
-         let mut ks = HashMap::new(); ks.insert(t.clone(),k.clone());
-         let vn = i.clone();
-         let vt = tlc.push_term(Term::Ident(vn.clone()),&span);
-         tlc.untyped(vt);
-         tlc.scopes[inner_scope.id].children.push(
-            (vn.clone(), ks, t.clone(), Some(vt))
-         );
-      }
+   for ((i,t,k),ispan) in std::iter::zip(pars.iter().flatten(), pspans.iter()) {
+      let t = t.normalize();
+      let mut ks = HashMap::new(); ks.insert(t.clone(),k.clone());
+      let vn = i.clone();
+      let vt = tlc.push_term(Term::Ident(vn.clone()),ispan);
+      tlc.untyped(vt);
+      tlc.scopes[inner_scope.id].children.push(
+         (vn.clone(), ks, t.clone(), Some(vt))
+      );
    }
    let mut ft = rt.clone();
    let mut fkts = HashMap::new();
@@ -475,11 +469,7 @@ pub fn ll1_let_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
    }), &span);
    tlc.scopes[scope.id].children.push(( ident.clone(), fkts, ft, Some(vt) ));
    if tlc.strict && t.is_none() {
-      return Err(Error {
-         kind: "Type Error".to_string(),
-         rule: format!("in strict mode functions must have bodies"),
-         span: span_of(tokens),
-      })
+      return Err(Error::new("Type Error", format!("in strict mode functions must have bodies"), span_of(tokens)))
    }
    Ok(vt)
 }
@@ -566,7 +556,7 @@ pub fn ll1_for_term(tlc: &mut TLC, mut scope: ScopeId, tokens: &mut TokenReader)
       if peek_is(tokens, &vec![Symbol::For]) {
          pop_is("for-term", tokens, &vec![Symbol::For])?;
          let lhs = ll1_ascript_term(tlc, scope, tokens)?;
-         scope = Term::scope_of_lhs(tlc, Some(scope), lhs);
+         scope = Term::scope_of_lhs(tlc, Some(scope), lhs)?;
          pop_is("for-term", tokens, &vec![Symbol::In])?;
          let iterable = ll1_expr_term(tlc, scope, tokens)?;
          loop_stack.push(Comb::CFor(scope,lhs,iterable));
@@ -590,9 +580,10 @@ pub fn ll1_for_term(tlc: &mut TLC, mut scope: ScopeId, tokens: &mut TokenReader)
    match s {
       Comb::CFor(sc,lhs,iterable) => {
          let arr_scope = tlc.new_scope(Some(*sc));
-         let arr = tlc.push_term(Term::Arrow(arr_scope, *lhs, None, rhs ),&span);
+         let arr_lhs = tlc.push_term(Term::Tuple(vec![*lhs]),&span);
+         let arr = tlc.push_term(Term::Arrow(arr_scope, arr_lhs, None, rhs ),&span);
          let mut children = tlc.scopes[arr_scope.id].children.clone();
-         Term::scope_of_lhs_impl(tlc, &mut children, *lhs);
+         Term::scope_of_lhs_impl(tlc, &mut children, *lhs)?;
          tlc.scopes[arr_scope.id].children = children;
 
          let t = Term::App(
@@ -622,11 +613,13 @@ pub fn ll1_logical_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
    let span = span_of(tokens);
    let mut term = ll1_compare_term(tlc, scope, tokens)?;
    while peek_is(tokens, &vec![Symbol::And,Symbol::Or]) {
+      let ospan = span_of(tokens);
       let op = pop_is("logical-term", tokens, &vec![Symbol::And,Symbol::Or])?;
       let op = format!("{:?}", op);
       let term2 = ll1_compare_term(tlc, scope, tokens)?;
+      let span = span_since(tokens, &span);
       let t = Term::App(
-         tlc.push_term(Term::Ident(op),&span),
+         tlc.push_term(Term::Ident(op),&ospan),
          tlc.push_term(Term::Tuple(vec![term,term2]),&span),
       );
       term = tlc.push_term(t,&span);
@@ -638,11 +631,13 @@ pub fn ll1_compare_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
    let span = span_of(tokens);
    let mut term = ll1_addsub_term(tlc, scope, tokens)?;
    while peek_is(tokens, &vec![Symbol::Equal,Symbol::NotEqual,Symbol::GreaterThan,Symbol::GreaterThanOrEqual,Symbol::LessThan,Symbol::LessThanOrEqual]) {
+      let ospan = span_of(tokens);
       let op = pop_is("compare-term", tokens, &vec![Symbol::Equal,Symbol::NotEqual,Symbol::GreaterThan,Symbol::GreaterThanOrEqual,Symbol::LessThan,Symbol::LessThanOrEqual])?;
       let op = format!("{:?}", op);
       let term2 = ll1_addsub_term(tlc, scope, tokens)?;
+      let span = span_since(tokens, &span);
       let t = Term::App(
-         tlc.push_term(Term::Ident(op),&span),
+         tlc.push_term(Term::Ident(op),&ospan),
          tlc.push_term(Term::Tuple(vec![term,term2]),&span),
       );
       term = tlc.push_term(t,&span);
@@ -654,11 +649,13 @@ pub fn ll1_addsub_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
    let span = span_of(tokens);
    let mut term = ll1_divmul_term(tlc, scope, tokens)?;
    while peek_is(tokens, &vec![Symbol::Plus,Symbol::Minus]) {
+      let ospan = span_of(tokens);
       let op = pop_is("addsub-term", tokens, &vec![Symbol::Plus,Symbol::Minus])?;
       let op = format!("{:?}", op);
       let term2 = ll1_divmul_term(tlc, scope, tokens)?;
+      let span = span_since(tokens, &span);
       let t = Term::App(
-         tlc.push_term(Term::Ident(op),&span),
+         tlc.push_term(Term::Ident(op),&ospan),
          tlc.push_term(Term::Tuple(vec![term,term2]),&span),
       );
       term = tlc.push_term(t,&span);
@@ -670,11 +667,13 @@ pub fn ll1_divmul_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
    let span = span_of(tokens);
    let mut term = ll1_power_term(tlc, scope, tokens)?;
    while peek_is(tokens, &vec![Symbol::Div,Symbol::Mul,Symbol::Mod]) {
+      let ospan = span_of(tokens);
       let op = pop_is("divmul-term", tokens, &vec![Symbol::Div,Symbol::Mul,Symbol::Mod])?;
       let op = format!("{:?}", op);
       let term2 = ll1_power_term(tlc, scope, tokens)?;
+      let span = span_since(tokens, &span);
       let t = Term::App(
-         tlc.push_term(Term::Ident(op),&span),
+         tlc.push_term(Term::Ident(op),&ospan),
          tlc.push_term(Term::Tuple(vec![term,term2]),&span),
       );
       term = tlc.push_term(t,&span);
@@ -686,11 +685,13 @@ pub fn ll1_power_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -
    let span = span_of(tokens);
    let mut term = ll1_infix_term(tlc, scope, tokens)?;
    while peek_is(tokens, &vec![Symbol::Pow]) {
+      let ospan = span_of(tokens);
       let op = pop_is("power-term", tokens, &vec![Symbol::Pow])?;
       let op = format!("{:?}", op);
       let term2 = ll1_infix_term(tlc, scope, tokens)?;
+      let span = span_since(tokens, &span);
       let t = Term::App(
-         tlc.push_term(Term::Ident(op),&span),
+         tlc.push_term(Term::Ident(op),&ospan),
          tlc.push_term(Term::Tuple(vec![term,term2]),&span),
       );
       term = tlc.push_term(t,&span);
@@ -706,12 +707,14 @@ pub fn ll1_infix_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -
    while peek_is(tokens, &vec![Symbol::BackQuote]) {
       pop_is("infix-term", tokens, &vec![Symbol::BackQuote])?;
 
+      let ospan = span_of(tokens);
       if let Some(Symbol::Ident(op)) = tokens.take_symbol()? {
          pop_is("infix-term", tokens, &vec![Symbol::BackQuote])?;
          
          let term2 = ll1_prefix_term(tlc, scope, tokens)?;
+         let span = span_since(tokens, &span);
          let t = Term::App(
-            tlc.push_term(Term::Ident(op),&span),
+            tlc.push_term(Term::Ident(op),&ospan),
             tlc.push_term(Term::Tuple(vec![term,term2]),&span),
          );
          term = tlc.push_term(t,&span);
@@ -733,6 +736,7 @@ pub fn ll1_prefix_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
       ops.push(op);
    }
    let mut term = ll1_atom_term(tlc, scope, tokens)?;
+   let span = span_since(tokens, &span);
    while ops.len()>0 {
       let topop = if ops.pop()==Some(Symbol::Plus) { "pos".to_string() } else { "neg".to_string() };
       let t = Term::App(
@@ -773,7 +777,7 @@ pub fn ll1_tuple_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -
       if !comma_ok && ts.len()==1 {
          Ok(ts[0])
       } else {
-         Ok(tlc.push_term(Term::Tuple(ts),&span))
+         Ok(tlc.push_term(Term::Tuple(ts),&span_since(tokens, &span)))
       }
    }
 }
@@ -792,7 +796,7 @@ pub fn ll1_args_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
       }
    }
    pop_is("args-term", tokens, &vec![Symbol::RightParen])?;
-   Ok(tlc.push_term(Term::Tuple(ts),&span))
+   Ok(tlc.push_term(Term::Tuple(ts),&span_since(tokens, &span)))
 }
 
 pub fn ll1_value_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<TermId,Error> {
@@ -846,6 +850,37 @@ pub fn ll1_value_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -
             let joined = tlc.push_term(Term::App(join,ps),&span);
             return Ok(joined)
          }
+      } else if let Symbol::LiteralPattern = sym {
+         pop_is("value-term", tokens, &vec![Symbol::LiteralPattern])?;
+         //a name written directly after a part, without space, binds the text matched by that part
+         let mut ps: Vec<(LiteralPattern,Option<String>)> = Vec::new();
+         let mut last_end = None;
+         while let Some(t) = tokens.peek()? {
+            let adjacent = last_end == Some(t.span.offset_start);
+            let p = match t.symbol {
+               Symbol::LiteralC(c) => LiteralPattern::Char(c),
+               Symbol::LiteralR(r) => LiteralPattern::Range(r),
+               Symbol::Value(v) if v.len()>=2 && v.starts_with('"') && v.ends_with('"') => {
+                  LiteralPattern::String(v[1..v.len()-1].to_string())
+               },
+               Symbol::Value(v) => LiteralPattern::String(v),
+               Symbol::Ident(v) if adjacent && ps.last().map(|(_,b)| b.is_none()).unwrap_or(false) => {
+                  tokens.take()?;
+                  if let Some((_,b)) = ps.last_mut() { *b = Some(v); }
+                  last_end = None;
+                  continue;
+               },
+               Symbol::Ident(v) => LiteralPattern::Variable(v),
+               _ => break,
+            };
+            tokens.take()?;
+            last_end = Some(t.span.offset_end);
+            ps.push((p,None));
+         }
+         if ps.is_empty() {
+            pop_is("value-term", tokens, &vec![Symbol::LiteralC('c'), Symbol::LiteralR("0-9".to_string()), Symbol::Ident("x".to_string())])?;
+         }
+         return Ok(tlc.push_term(Term::Literal(ps), &span))
       } else if let Symbol::Typename(cname) = sym {
          tokens.take_symbol()?;
          let mut kvs = Vec::new();
@@ -942,6 +977,7 @@ pub fn ll1_atom_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
       if peek_is(tokens, &vec![Symbol::Dot]) {
          let field = ll1_field_term(tlc, scope, tokens)?;
          if !peek_is(tokens, &vec![Symbol::LeftParen]) {
+            let span = span_since(tokens, &span);
             if let Term::Ident(_) = &tlc.rows[field.id].term {
                term = tlc.push_term(Term::Tuple(vec![term]),&span);
                term = tlc.push_term(Term::App(field, term),&span);
@@ -961,6 +997,7 @@ pub fn ll1_atom_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
                }
             }
             pop_is("atom-term", tokens, &vec![Symbol::RightParen])?;
+            let span = span_since(tokens, &span);
             if ts.len()==0 { //x.f()
                let fargs = tlc.push_term(Term::Tuple(ts),&span);
                term = tlc.push_term(Term::App(field, fargs),&span);
@@ -972,8 +1009,9 @@ pub fn ll1_atom_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
             }
          }
       } else if peek_is(tokens, &vec![Symbol::LeftBracket]) {
-         let prj = tlc.push_term(Term::Ident("[]".to_string()),&span);
+         let prj = tlc.push_term(Term::Ident("[]".to_string()),&span_of(tokens));
          let index = ll1_index_term(tlc, scope, tokens)?;
+         let span = span_since(tokens, &span);
          let fargs = tlc.push_term(Term::Tuple(vec![term,index]),&span);
          term = tlc.push_term(Term::App(prj, fargs),&span);
       } else {
@@ -982,7 +1020,7 @@ pub fn ll1_atom_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
             term,
             args
          );
-         term = tlc.push_term(t,&span);
+         term = tlc.push_term(t,&span_since(tokens, &span));
       }
    }
    Ok(term)
@@ -1074,9 +1112,63 @@ pub fn ll1_constant(tlc: &mut TLC, _scope: ScopeId, tokens: &mut TokenReader) ->
    unreachable!("constant-term expected Ident, Typename, or Value")
 }
 
+pub fn ll1_constant_atom(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<Constant,Error> {
+   if peek_is(tokens, &vec![Symbol::LeftParen]) {
+      pop_is("constant-expression", tokens, &vec![Symbol::LeftParen])?;
+      let c = ll1_constant_sum(tlc, scope, tokens)?;
+      pop_is("constant-expression", tokens, &vec![Symbol::RightParen])?;
+      Ok(c)
+   } else if let Some(Symbol::Ident(v)) = tokens.peek_symbol()? {
+      //a variable
+      let span = span_of(tokens);
+      tokens.take_symbol()?;
+      let c = Constant::Literal(v);
+      if !c.is_variable() { return Err(Error::new("Parse Error",
+         format!("expected a variable named in lowercase, found {:?}", c), span)) }
+      Ok(c)
+   } else {
+      ll1_constant(tlc, scope, tokens)
+   }
+}
+
+pub fn ll1_constant_product(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader, first: Option<Constant>) -> Result<Constant,Error> {
+   let mut c = match first {
+      Some(c) => c,
+      None => ll1_constant_atom(tlc, scope, tokens)?,
+   };
+   while peek_is(tokens, &vec![Symbol::Mul]) {
+      pop_is("constant-expression", tokens, &vec![Symbol::Mul])?;
+      c = Constant::op("*", c, ll1_constant_atom(tlc, scope, tokens)?);
+   }
+   Ok(c)
+}
+
+pub fn ll1_constant_sum(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<Constant,Error> {
+   let mut c = ll1_constant_product(tlc, scope, tokens, None)?;
+   loop {
+      if peek_is(tokens, &vec![Symbol::Plus,Symbol::Minus]) {
+         let o = if peek_is(tokens, &vec![Symbol::Plus]) { "+" } else { "-" };
+         tokens.take_symbol()?;
+         c = Constant::op(o, c, ll1_constant_product(tlc, scope, tokens, None)?);
+      } else if let Some(Symbol::Value(v)) = tokens.peek_symbol()? {
+         //n-1 is tokenized as n followed by the value -1
+         if !v.starts_with('-') && !v.starts_with('+') { break; }
+         tokens.take_symbol()?;
+         let first = Constant::Literal(v[1..].to_string());
+         c = Constant::op(&v[..1], c, ll1_constant_product(tlc, scope, tokens, Some(first))?);
+      } else { break; }
+   }
+   Ok(c)
+}
+
+/// Constants in types may be arithmetic over integers and variables, such as [n+1].
+pub fn ll1_constant_expr(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<Constant,Error> {
+   Ok(ll1_constant_sum(tlc, scope, tokens)?.normalize())
+}
+
 pub fn ll1_dep_type(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<Type,Error> {
    pop_is("dependent-type", tokens, &vec![Symbol::LeftBracket])?;
-   let cv = ll1_constant(tlc, scope, tokens)?;
+   let cv = ll1_constant_expr(tlc, scope, tokens)?;
    pop_is("dependent-type", tokens, &vec![Symbol::RightBracket])?;
    Ok(Type::Constant(cv))
 }
@@ -1108,7 +1200,12 @@ pub fn ll1_suffix_type(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
          if peek_is(tokens, &vec![Symbol::RightBracket]) {
             ts.push( Constant::Tuple(Vec::new()) );
          } else {
-            ts.push( ll1_constant(tlc, scope, tokens)? );
+            //a length may be a variable, or arithmetic over variables
+            let span = span_of(tokens);
+            let c = ll1_constant_expr(tlc, scope, tokens)?;
+            if !c.is_length() { return Err(Error::new("Parse Error",
+               format!("expected a natural number or a lowercase variable as a length, found {:?}", c), span)) }
+            ts.push(c);
          }
          pop_is("suffix-type", tokens, &vec![Symbol::RightBracket])?;
       } else {
@@ -1121,11 +1218,32 @@ pub fn ll1_suffix_type(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
    Ok(base)
 }
 
+pub fn ll1_power_type(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<Type,Error> {
+   let base = ll1_suffix_type(tlc, scope, tokens)?;
+   if !peek_is(tokens, &vec![Symbol::Pow]) {
+      return Ok(base);
+   }
+   pop_is("power-type", tokens, &vec![Symbol::Pow])?;
+   let sign = if peek_is(tokens, &vec![Symbol::Minus]) {
+      pop_is("power-type", tokens, &vec![Symbol::Minus])?;
+      -1
+   } else { 1 };
+   let span = span_of(tokens);
+   let e = match tokens.peek_symbol()? {
+      Some(Symbol::Value(v)) => str::parse::<i64>(&v).ok(),
+      _ => None,
+   };
+   let Some(e) = e else { return Err(Error::new("Parse Error", "expected an integer exponent".to_string(), span)) };
+   tokens.take_symbol()?;
+   e.checked_mul(sign).and_then(|e| base.pow(e)).ok_or_else(|| Error::new("Parse Error",
+      format!("exponent overflows in {:?}^{}{}", base, if sign<0 { "-" } else { "" }, e), span))
+}
+
 pub fn ll1_product_type(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<Type,Error> {
-   let mut types = vec![ ll1_suffix_type(tlc, scope, tokens)? ];
+   let mut types = vec![ ll1_power_type(tlc, scope, tokens)? ];
    while peek_is(tokens, &vec![Symbol::Mul]) {
       pop_is("product-type", tokens, &vec![Symbol::Mul])?;
-      types.push( ll1_suffix_type(tlc, scope, tokens)? );
+      types.push( ll1_power_type(tlc, scope, tokens)? );
    }
    if types.len()==1 {
       Ok(types[0].clone())
@@ -1135,12 +1253,17 @@ pub fn ll1_product_type(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
 }
 
 pub fn ll1_ratio_type(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<Type,Error> {
+   let span = span_of(tokens);
    let mut typ = ll1_product_type(tlc, scope, tokens)?;
    if peek_is(tokens, &vec![Symbol::Div]) {
       pop_is("ratio-type", tokens, &vec![Symbol::Div])?;
       let typ2 = ll1_product_type(tlc, scope, tokens)?;
       typ = Type::Ratio(Box::new(typ), Box::new(typ2));
    }
+   //exponents are summed when the type is normalized, so they must not overflow
+   if matches!(typ, Type::Product(_) | Type::Ratio(_,_)) && typ.exponents().is_none() {
+      return Err(Error::new("Parse Error", format!("exponent overflows in {:?}", typ), span));
+   }
    Ok(typ)
 }
 
@@ -1182,7 +1305,7 @@ pub fn ll1_ascript_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
             term,
             at
          );
-         term = tlc.push_term(t, &span);
+         term = tlc.push_term(t, &span_since(tokens, &span));
       } else if peek_is(tokens, &vec![Symbol::At]) {
          pop_is("ascript-term", tokens, &vec![Symbol::At])?;
          let mut at = "".to_string();
@@ -1196,7 +1319,7 @@ pub fn ll1_ascript_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader)
             term,
             at
          );
-         term = tlc.push_term(t, &span);
+         term = tlc.push_term(t, &span_since(tokens, &span));
       }
    }
    Ok(term)
@@ -1212,7 +1335,7 @@ pub fn ll1_as_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> R
          term,
          at
       );
-      term = tlc.push_term(t, &span);
+      term = tlc.push_term(t, &span_since(tokens, &span));
    }
    Ok(term)
 }
@@ -1232,23 +1355,31 @@ pub fn ll1_asif_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) ->
 }
 
 pub fn ll1_arrow_term(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<TermId,Error> {
-   let _span = span_of(tokens);
+   let span = span_of(tokens);
    if peek_is(tokens, &vec![Symbol::Fn]) {
       pop_is("arrow-term", tokens, &vec![Symbol::Fn])?;
-      unimplemented!("Parse Term::Arrow");
-      /*
-      let mut children = Vec::new();
-      let inner_scope = tlc.push_scope(Scope {
-         parent: Some(scope),
-         children: children,
-      });
-
-      while peek_is(tokens, &vec![Symbol::Arrow]) {
-         let bterm = ll1_asif_term(tlc, scope, tokens)?;
-         let t = Term::Arrow(term, bterm);
-         term = tlc.push_term(t, &span);
+      pop_is("arrow-term", tokens, &vec![Symbol::LeftParen])?;
+      let mut ps = Vec::new();
+      while !peek_is(tokens, &vec![Symbol::RightParen]) {
+         if peek_is(tokens, &vec![Symbol::Comma]) {
+            pop_is("arrow-term", tokens, &vec![Symbol::Comma])?;
+            continue;
+         }
+         ps.push( ll1_ascript_term(tlc, scope, tokens)? );
+      }
+      pop_is("arrow-term", tokens, &vec![Symbol::RightParen])?;
+      let mut rt = None;
+      if peek_is(tokens, &vec![Symbol::Ascript]) {
+         pop_is("arrow-term", tokens, &vec![Symbol::Ascript])?;
+         rt = Some( ll1_type(tlc, scope, tokens)? );
       }
-      */
+      pop_is("arrow-term", tokens, &vec![Symbol::Is])?;
+
+      //fn(lhs): rt = rhs
+      let lhs = tlc.push_term(Term::Tuple(ps),&span);
+      let arr_scope = Term::scope_of_lhs(tlc, Some(scope), lhs)?;
+      let rhs = ll1_term(tlc, arr_scope, tokens)?;
+      Ok(tlc.push_term(Term::Arrow(arr_scope, lhs, rt, rhs),&span))
    } else {
       ll1_asif_term(tlc, scope, tokens)
    }
@@ -1277,20 +1408,61 @@ pub fn ll1_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Resu
 }
 
 pub fn ll1_import_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<TermId,Error> {
+   let span = span_of(tokens);
    pop_is("import", tokens, &vec![Symbol::Import])?;
    if let Some(Symbol::Ident(fp)) = tokens.peek_symbol()? {
       tokens.take_symbol()?;
-      let mut tks = tokenize_file(tlc, &fp)?;
-      ll1_file_impl(tlc, scope, &mut tks, false)
+      let Some(filename) = tlc.resolve_import(span.filename.as_str(), &fp) else { return Err(Error::new("Import Error",
+         format!("Could not find import {} from {} or the search path", fp, span.filename), span)) };
+      ll1_import_file(tlc, scope, span, filename)
    } else {
-      Err(Error {
-         kind: "Parse Error".to_string(),
-         rule: format!("Expected identifier in import statement"),
-         span: span_of(tokens),
-      })
+      Err(Error::new("Parse Error", format!("Expected identifier in import statement"), span_of(tokens)))
    }
 }
 
+/// Import a file into a scope, for an import statement or for the implicit prelude.
+fn ll1_import_file(tlc: &mut TLC, scope: ScopeId, span: Span, filename: String) -> Result<TermId,Error> {
+   let key = TLC::import_key(&filename);
+
+   //each file is imported at most once, later imports only expose its names again
+   if let Some((module,mfilename)) = tlc.imports.get(&key).cloned() {
+      ll1_expose(tlc, module, scope, &mfilename);
+      return Ok(TermId { id:0 });
+   }
+   if tlc.importing.is_empty() {
+      tlc.importing.push((TLC::import_key(span.filename.as_str()), span.filename.to_string()));
+   }
+   if tlc.importing.iter().any(|(k,_)| k==&key) {
+      let chain = tlc.importing.iter().map(|(_,f)| f.clone()).chain(std::iter::once(filename)).collect::<Vec<String>>();
+      tlc.importing.clear();
+      return Err(Error::new("Import Error", format!("Circular import: {}", chain.join(" -> ")), span));
+   }
+
+   //an imported file is checked in a scope of its own
+   //only the names that the file itself defines are exposed to the importing scope
+   let module = tlc.push_scope(Scope {
+      parent: None,
+      children: Vec::new(),
+   });
+   tlc.importing.push((key.clone(), filename.clone()));
+   let t = tokenize_file(tlc, &filename).and_then(|mut tks| ll1_file(tlc, module, &mut tks));
+   tlc.importing.pop();
+   if tlc.importing.len()==1 { tlc.importing.clear(); }
+   let t = t?;
+   tlc.imports.insert(key, (module,filename.clone()));
+   ll1_expose(tlc, module, scope, &filename);
+   Ok(t)
+}
+
+fn ll1_expose(tlc: &mut TLC, module: ScopeId, scope: ScopeId, filename: &str) {
+   let exports = tlc.scopes[module.id].children.iter().filter(|(_,_,_,b)| match b {
+      Some(b) => tlc.rows[b.id].span.filename.as_str() == filename,
+      None => false,
+   }).filter(|(_,_,_,b)| !tlc.scopes[scope.id].children.iter().any(|(_,_,_,sb)| sb==b))
+     .cloned().collect::<Vec<_>>();
+   tlc.scopes[scope.id].children.extend(exports);
+}
+
 pub fn ll1_block_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<TermId,Error> {
    let scope = tlc.push_scope(Scope {
       parent: Some(scope),
@@ -1311,17 +1483,14 @@ pub fn ll1_block_stmt(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -
 }
 
 pub fn ll1_file(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader) -> Result<TermId,Error> {
-   ll1_file_impl(tlc, scope, tokens, true)
-}
-
-fn ll1_file_impl(tlc: &mut TLC, scope: ScopeId, tokens: &mut TokenReader, user: bool) -> Result<TermId,Error> {
    let mut es = Vec::new();
 
-   //default to L1 prelude if none specified
-   if user && !peek_is(tokens, &vec![Symbol::Import]) {
-      let mut tks = tokenize_file(tlc, "preludes/l1.tlc")?;
-      es.push( ll1_file_impl(tlc, scope, &mut tks, false)? );
-   }
+   //default to the implicit prelude, L1 unless configured otherwise, if none specified
+   //the prelude is imported like any other file, so it is checked once and its names are exposed to each file
+   if !peek_is(tokens, &vec![Symbol::Import]) {
+   if let Some(prelude) = tlc.prelude.clone().filter(|p| p.as_str() != span_of(tokens).filename.as_str()) {
+      es.push( ll1_import_file(tlc, scope, span_of(tokens), prelude)? );
+   }}
 
    while !peek_is(tokens, &vec![Symbol::EOF]) {
       es.push( ll1_stmt(tlc, scope, tokens)? );
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use std::io::{BufRead,Write};
+use serde_json::{json,Value};
+use crate::tlc::{TLC,TypeRule,Snapshot};
+use crate::term::{Term,TermId};
+use crate::scope::{Scope,ScopeId};
+use crate::token::{Span,tokenize_string};
+use crate::debug::{Diagnostic,Severity};
+
+/// A Document is the latest text of an open file and the diagnostics of its last check.
+/// It is checked under the path of its URI, so that its imports are relative to that file.
+pub struct Document {
+   pub text: String,
+   pub path: String,
+   pub diagnostics: Vec<Diagnostic>,
+}
+
+/// The path of a file URI, or the URI itself if it does not name a file.
+pub fn uri_path(uri: &str) -> String {
+   let Some(p) = uri.strip_prefix("file://") else { return uri.to_string() };
+   let bs = p.as_bytes();
+   let mut path = Vec::new();
+   let mut i = 0;
+   while i < bs.len() {
+      let hex = if bs[i]==b'%' { p.get(i+1..i+3).and_then(|h| u8::from_str_radix(h, 16).ok()) } else { None };
+      if let Some(b) = hex {
+         path.push(b);
+         i += 3;
+      } else {
+         path.push(bs[i]);
+         i += 1;
+      }
+   }
+   String::from_utf8_lossy(&path).to_string()
+}
+
+impl Document {
+   pub fn new(uri: &str, text: &str) -> Document {
+      Document {
+         text: text.to_string(),
+         path: uri_path(uri),
+         diagnostics: Vec::new(),
+      }
+   }
+   /// Check the text in a context that has been restored to the prelude.
+   /// Returns the scope of the file and its term, if it could be parsed.
+   pub fn check(&mut self, tlc: &mut TLC, globals: Option<ScopeId>) -> (ScopeId,Option<TermId>) {
+      let file = globals.unwrap_or_else(|| tlc.push_scope(Scope { parent: None, children: Vec::new() }));
+      let ast = tokenize_string(tlc, &self.path, &self.text).and_then(|mut tks| tlc.parse_toks(Some(file), &mut tks));
+      let r = match ast {
+         Ok(ast) => tlc.check_ast(Some(file), ast),
+         Err(ref e) => Err(e.clone()),
+      };
+      if let Err(e) = r {
+         if tlc.diagnostics.is_empty() {
+            tlc.diagnostics.push(e.diagnostic());
+         }
+      }
+      self.diagnostics = std::mem::take(&mut tlc.diagnostics);
+      (file, ast.ok())
+   }
+   fn is_local(&self, span: &Span) -> bool {
+      span.filename.as_str() == self.path
+   }
+   /// Convert an LSP position into a byte offset into the text.
+   /// LSP characters are counted in UTF-16 code units.
+   pub fn offset(&self, line: usize, character: usize) -> usize {
+      let mut offset = 0;
+      for (li,l) in self.text.split_inclusive('\n').enumerate() {
+         if li == line {
+            let mut units = 0;
+            for (ci,c) in l.char_indices() {
+               if units >= character || c == '\n' { return offset + ci; }
+               units += c.len_utf16();
+            }
+            return offset + l.len();
+         }
+         offset += l.len();
+      }
+      offset
+   }
+   /// Convert a byte offset into the text into an LSP position.
+   pub fn position(&self, offset: usize) -> Value {
+      let offset = std::cmp::min(offset, self.text.len());
+      let before = &self.text[..offset];
+      let line = before.matches('\n').count();
+      let line_start = before.rfind('\n').map(|i| i+1).unwrap_or(0);
+      let character = before[line_start..].chars().map(|c| c.len_utf16()).sum::<usize>();
+      json!({"line": line, "character": character})
+   }
+   pub fn range(&self, span: &Span) -> Value {
+      if self.is_local(span) {
+         json!({"start": self.position(span.offset_start), "end": self.position(span.offset_end)})
+      } else {
+         json!({"start": self.position(0), "end": self.position(0)})
+      }
+   }
+   pub fn diagnostics(&self) -> Vec<Value> {
+      self.diagnostics.iter().map(|d: &Diagnostic| {
+         let mut message = d.message.clone();
+         for n in d.notes.iter() {
+            message += "\n";
+            message += n;
+         }
+         json!({
+            "range": self.range(&d.primary.span),
+            "severity": match d.severity { Severity::Error => 1, Severity::Warning => 2, Severity::Note => 3 },
+            "code": d.code,
+            "source": "lsts",
+            "message": message,
+         })
+      }).collect()
+   }
+   /// The identifier under the cursor, including a leading dot for methods and fields.
+   pub fn word_at(&self, offset: usize) -> Option<String> {
+      let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+      let offset = std::cmp::min(offset, self.text.len());
+      let start = self.text[..offset].rfind(|c: char| !is_word(c)).map(|i| i+1).unwrap_or(0);
+      let end = self.text[offset..].find(|c: char| !is_word(c)).map(|i| offset+i).unwrap_or(self.text.len());
+      let word = self.text[start..end].trim_end_matches('.');
+      if word.is_empty() { None } else { Some(word.to_string()) }
+   }
+   /// The narrowest term of this document under the cursor that is accepted by a filter.
+   fn term_at(&self, tlc: &TLC, offset: usize, accept: &dyn Fn(&Term) -> bool) -> Option<usize> {
+      let mut best: Option<usize> = None;
+      for (ri,r) in tlc.rows.iter().enumerate() {
+         if !self.is_local(&r.span) || !accept(&r.term) { continue; }
+         if r.span.offset_start > offset || offset >= r.span.offset_end { continue; }
+         if best.map(|bi| {
+            let b = &tlc.rows[bi].span;
+            r.span.offset_end - r.span.offset_start < b.offset_end - b.offset_start
+         }).unwrap_or(true) {
+            best = Some(ri);
+         }
+      }
+      best
+   }
+   /// Hover shows the inferred type of the narrowest term under the cursor.
+   pub fn hover(&self, tlc: &TLC, offset: usize) -> Value {
+      let Some(bi) = self.term_at(tlc, offset, &|_| true) else { return Value::Null };
+      let row = &tlc.rows[bi];
+      let value = match &row.term {
+         Term::Ident(n) => format!("{} : {:?}", n, row.typ),
+         _ => format!("{:?}", row.typ),
+      };
+      json!({
+         "contents": {"kind": "plaintext", "value": value},
+         "range": self.range(&row.span),
+      })
+   }
+   /// The innermost scope that a term is checked in, searching from a term checked in scope.
+   fn scope_of(tlc: &TLC, t: TermId, target: TermId, scope: ScopeId) -> Option<ScopeId> {
+      if t.id == target.id { return Some(scope); }
+      let within = |ts: &[TermId], sc: ScopeId| ts.iter().find_map(|ct| Document::scope_of(tlc, *ct, target, sc));
+      match &tlc.rows[t.id].term {
+         Term::Block(sc,es) => within(es, *sc),
+         Term::Arrow(sc,_p,_rt,b) => within(&[*b], *sc),
+         Term::Let(lt) => within(&lt.body.into_iter().collect::<Vec<TermId>>(), lt.scope),
+         Term::App(g,x) => within(&[*g,*x], scope),
+         Term::Tuple(es) => within(es, scope),
+         Term::Ascript(x,_) | Term::As(x,_) | Term::RuleApplication(x,_) => within(&[*x], scope),
+         Term::Constructor(_,kvs) => within(&kvs.iter().map(|(_,v)| *v).collect::<Vec<TermId>>(), scope),
+         Term::Match(d,arms) => within(&[*d], scope).or_else(|| arms.iter().find_map(|(sc,l,r)| within(&[*l,*r], *sc))),
+         _ => None,
+      }
+   }
+   /// Definitions are the nearest bindings in scope at the cursor, typedefs, and named hints.
+   pub fn definition(&self, tlc: &TLC, uri: &str, file: ScopeId, ast: Option<TermId>, offset: usize) -> Value {
+      let Some(word) = self.word_at(offset) else { return Value::Null };
+      let hint = word.trim_start_matches('@');
+      let mut spans = Vec::new();
+      //an identifier is resolved through the scopes that enclose it, the nearest scope that binds it wins
+      let ident = self.term_at(tlc, offset, &|t| matches!(t, Term::Ident(n) if n==&word));
+      let mut scope = match (ident,ast) {
+         (Some(i),Some(ast)) => Document::scope_of(tlc, ast, TermId { id: i }, file),
+         _ => None,
+      };
+      while let Some(sc) = scope {
+         for (cn,_ck,_ct,cb) in tlc.scopes[sc.id].children.iter() {
+            if cn != &word { continue; }
+            if let Some(cb) = cb {
+               spans.push(tlc.rows[cb.id].span.clone());
+            }
+         }
+         if !spans.is_empty() { break; }
+         scope = tlc.scopes[sc.id].parent;
+      }
+      for r in tlc.rules.iter() { match r {
+         TypeRule::Typedef(tr) if tr.name == word => { spans.push(tr.span.clone()); },
+         TypeRule::Forall(fr) if fr.name.as_deref() == Some(hint) => { spans.push(fr.span.clone()); },
+         _ => {},
+      }}
+      let locations = spans.iter().filter(|s| self.is_local(s)).map(|s| json!({
+         "uri": uri,
+         "range": self.range(s),
+      })).collect::<Vec<Value>>();
+      if locations.is_empty() { Value::Null } else { Value::Array(locations) }
+   }
+}
+
+/// The Server answers Language Server Protocol requests for .tlc files.
+/// The prelude is checked once, and documents are checked one at a time after restoring to that point.
+pub struct Server {
+   pub documents: HashMap<String,Document>,
+   pub tlc: TLC,
+   globals: Option<ScopeId>,
+   prelude: Snapshot,
+   checked: Option<(String,ScopeId,Option<TermId>)>, //the document whose check is in tlc, with its scope and term
+   pub shutdown: bool,
+   pub exit: bool,
+}
+
+impl Server {
+   pub fn new() -> Server {
+      let mut tlc = TLC::new().accumulate();
+      let globals = tlc.prelude.clone().and_then(|p| tlc.import_file(None, &p).ok());
+      tlc.diagnostics.clear();
+      let prelude = tlc.snapshot();
+      Server {
+         documents: HashMap::new(),
+         tlc,
+         globals,
+         prelude,
+         checked: None,
+         shutdown: false,
+         exit: false,
+      }
+   }
+   /// Check a document unless its check is already in the context.
+   fn check(&mut self, uri: &str) -> Option<(ScopeId,Option<TermId>)> {
+      if let Some((cu,sc,ast)) = &self.checked {
+         if cu == uri { return Some((*sc,*ast)); }
+      }
+      let doc = self.documents.get_mut(uri)?;
+      self.tlc.restore(self.prelude.clone());
+      let (sc,ast) = doc.check(&mut self.tlc, self.globals);
+      self.checked = Some((uri.to_string(),sc,ast));
+      Some((sc,ast))
+   }
+   fn update(&mut self, uri: &str, text: &str) {
+      self.documents.insert(uri.to_string(), Document::new(uri, text));
+      self.checked = None;
+      self.check(uri);
+   }
+   fn response(id: &Value, result: Value) -> Value {
+      json!({"jsonrpc": "2.0", "id": id, "result": result})
+   }
+   fn publish(&self, uri: &str) -> Value {
+      let diagnostics = self.documents.get(uri).map(|d| d.diagnostics()).unwrap_or_default();
+      json!({
+         "jsonrpc": "2.0",
+         "method": "textDocument/publishDiagnostics",
+         "params": {"uri": uri, "diagnostics": diagnostics},
+      })
+   }
+   /// The document of a position, checked, with the position as an offset into its text.
+   fn located(&mut self, params: &Value) -> Option<(String,ScopeId,Option<TermId>,usize)> {
+      let uri = params["textDocument"]["uri"].as_str()?;
+      let line = params["position"]["line"].as_u64()? as usize;
+      let character = params["position"]["character"].as_u64()? as usize;
+      let (sc,ast) = self.check(uri)?;
+      let offset = self.documents.get(uri)?.offset(line, character);
+      Some((uri.to_string(), sc, ast, offset))
+   }
+   /// Handle one message, returning the responses and notifications to send back.
+   pub fn handle(&mut self, msg: &Value) -> Vec<Value> {
+      let id = &msg["id"];
+      let params = &msg["params"];
+      match msg["method"].as_str().unwrap_or("") {
+         "initialize" => vec![Server::response(id, json!({
+            "capabilities": {
+               "textDocumentSync": 1,
+               "hoverProvider": true,
+               "definitionProvider": true,
+            },
+            "serverInfo": {"name": "lsts-lsp", "version": env!("CARGO_PKG_VERSION")},
+         }))],
+         "textDocument/didOpen" => {
+            let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+            let text = params["textDocument"]["text"].as_str().unwrap_or("");
+            self.update(&uri, text);
+            vec![self.publish(&uri)]
+         },
+         "textDocument/didChange" => {
+            //documents are synchronized in full, so the last change is the whole text
+            let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+            let Some(text) = params["contentChanges"].as_array()
+                             .and_then(|cs| cs.last()).and_then(|c| c["text"].as_str())
+            else { return Vec::new() };
+            self.update(&uri, text);
+            vec![self.publish(&uri)]
+         },
+         "textDocument/didClose" => {
+            let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+            self.documents.remove(&uri);
+            if self.checked.as_ref().map(|(cu,_,_)| cu==&uri).unwrap_or(false) { self.checked = None; }
+            vec![json!({
+               "jsonrpc": "2.0",
+               "method": "textDocument/publishDiagnostics",
+               "params": {"uri": uri, "diagnostics": []},
+            })]
+         },
+         "textDocument/hover" => {
+            let result = self.located(params).map(|(uri,_sc,_ast,offset)| self.documents[&uri].hover(&self.tlc, offset))
+                             .unwrap_or(Value::Null);
+            vec![Server::response(id, result)]
+         },
+         "textDocument/definition" => {
+            let result = self.located(params).map(|(uri,sc,ast,offset)| self.documents[&uri].definition(&self.tlc, &uri, sc, ast, offset))
+                             .unwrap_or(Value::Null);
+            vec![Server::response(id, result)]
+         },
+         "shutdown" => {
+            self.shutdown = true;
+            vec![Server::response(id, Value::Null)]
+         },
+         "exit" => {
+            self.exit = true;
+            Vec::new()
+         },
+         method => {
+            if id.is_null() { Vec::new() } //notifications that are not understood are ignored
+            else { vec![json!({
+               "jsonrpc": "2.0", "id": id,
+               "error": {"code": -32601, "message": format!("method not found: {}", method)},
+            })] }
+         },
+      }
+   }
+   /// Serve messages until the client sends exit or closes the input.
+   pub fn serve<R: BufRead, W: Write>(&mut self, input: &mut R, output: &mut W) -> std::io::Result<()> {
+      while !self.exit {
+         let msg = match read_message(input) {
+            Ok(Some(msg)) => msg,
+            Ok(None) => break,
+            //a message that can not be parsed has no id to answer, so the error is sent with a null id
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+               write_message(output, &json!({
+                  "jsonrpc": "2.0", "id": null,
+                  "error": {"code": -32700, "message": format!("parse error: {}", e)},
+               }))?;
+               continue;
+            },
+            Err(e) => { return Err(e); },
+         };
+         //a request that panics is answered with an error, and the context is checked again before it is used
+         let replies = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.handle(&msg))) {
+            Ok(replies) => replies,
+            Err(_) => {
+               self.checked = None;
+               if msg["id"].is_null() { Vec::new() }
+               else { vec![json!({
+                  "jsonrpc": "2.0", "id": msg["id"],
+                  "error": {"code": -32603, "message": format!("internal error in {}", msg["method"].as_str().unwrap_or(""))},
+               })] }
+            },
+         };
+         for reply in replies {
+            write_message(output, &reply)?;
+         }
+      }
+      Ok(())
+   }
+}
+
+impl Default for Server {
+   fn default() -> Self {
+      Server::new()
+   }
+}
+
+/// The longest message body that is read, in bytes.
+pub const MAX_CONTENT_LENGTH: usize = 1 << 26;
+
+/// Read one Content-Length framed JSON-RPC message.
+/// A body that is too long or is not JSON is an InvalidData error, after which the next message can still be read.
+pub fn read_message<R: BufRead>(input: &mut R) -> std::io::Result<Option<Value>> {
+   let mut length = None;
+   loop {
+      let mut header = String::new();
+      if input.read_line(&mut header)? == 0 { return Ok(None); }
+      let header = header.trim_end();
+      if header.is_empty() { if length.is_some() { break; } else { continue; } }
+      if let Some(l) = header.strip_prefix("Content-Length:") {
+         length = l.trim().parse::<usize>().ok();
+      }
+   }
+   let length = length.unwrap_or(0);
+   if length > MAX_CONTENT_LENGTH {
+      //the body is skipped without being stored
+      std::io::copy(&mut std::io::Read::take(&mut *input, length as u64), &mut std::io::sink())?;
+      return Err(std::io::Error::new(std::io::ErrorKind::InvalidData,
+         format!("message of {} bytes is longer than {} bytes", length, MAX_CONTENT_LENGTH)));
+   }
+   let mut body = vec![0; length];
+   input.read_exact(&mut body)?;
+   serde_json::from_slice(&body).map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Write one Content-Length framed JSON-RPC message.
+pub fn write_message<W: Write>(output: &mut W, msg: &Value) -> std::io::Result<()> {
+   let body = msg.to_string();
+   write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+   output.flush()
+}
@@ -0,0 +1,12 @@
+use lsts::lsp::Server;
+
+fn main() {
+   let stdin = std::io::stdin();
+   let stdout = std::io::stdout();
+   let mut server = Server::new();
+   if let Err(e) = server.serve(&mut stdin.lock(), &mut stdout.lock()) {
+      eprintln!("lsts-lsp: {}", e);
+      std::process::exit(1);
+   }
+   std::process::exit(if server.shutdown { 0 } else { 1 });
+}
@@ -1,18 +1,71 @@
 use std::env;
-use lsts::tlc::TLC;
+use lsts::tlc::{TLC,TypeRule};
+use lsts::repl::Repl;
+use lsts::manifest::Manifest;
 use lsts::token::{tokenize_file,Symbol};
 use gag::Gag;
 
+/// Directories listed in LSTS_PATH are searched for imports.
+fn lsts_path() -> Vec<String> {
+   match env::var_os("LSTS_PATH") {
+      Some(p) => env::split_paths(&p).map(|d| d.to_string_lossy().to_string()).collect(),
+      None => Vec::new(),
+   }
+}
+
 fn main() {
-   let mut tlc = TLC::new();
-   let mut command = "help".to_string();
+   let mut tlc = TLC::new().search_path(lsts_path());
+   let mut command = None;
    let mut args = Vec::new();
-   for (argi,argument) in env::args().enumerate() {
-      if argi==0 { continue; }
-      if argi==1 { command = argument.clone(); continue; }
-      args.push(argument.clone());
+   let mut arguments = env::args().skip(1);
+   while let Some(argument) = arguments.next() {
+      if argument=="--no-prelude" { tlc = tlc.no_prelude(); continue; }
+      if argument=="--prelude" {
+         if let Some(p) = arguments.next() { tlc = tlc.prelude(&p); }
+         continue;
+      }
+      if command.is_none() { command = Some(argument); continue; }
+      args.push(argument);
    }
-   if command=="build" {
+   let command = command.unwrap_or("help".to_string());
+   //a package is named by its lsts.toml or directory, or found in the current directory when no files are given
+   let package = match args.as_slice() {
+      [] => Manifest::find("."),
+      [fp] => Manifest::find(fp),
+      _ => None,
+   };
+   let package = if ["build","check","run"].contains(&command.as_str()) { package } else { None };
+   let package = package.map(|p| Manifest::load(&p).unwrap_or_else(|e| {
+      eprintln!("{:?}", e);
+      std::process::exit(1);
+   }));
+   if let (Some(m),"build") = (&package,command.as_str()) {
+      for p in m.build_order() {
+         println!("Compiling: {}", p.name);
+      }
+      tlc.import_package(m).unwrap();
+   } else if let (Some(m),"run") = (&package,command.as_str()) {
+      let r = {
+         let _gag_order = Gag::stdout().unwrap();
+         tlc.reduce_package(m)
+      };
+      match r {
+         Ok(v) => { println!("{:?}", v); },
+         Err(msg) => { eprintln!("{:?}", msg); },
+      }
+   } else if let (Some(m),"check") = (&package,command.as_str()) {
+      let mut tlc = tlc.accumulate();
+      for p in m.build_order() {
+         println!("Typechecking: {}", p.name);
+      }
+      if tlc.import_package(m).is_err() {
+         for d in tlc.diagnostics.drain(..) {
+            let source = std::fs::read_to_string(d.primary.span.filename.as_str()).unwrap_or_default();
+            eprintln!("{}", d.render(&source));
+         }
+         std::process::exit(1);
+      }
+   } else if command=="build" {
       let mut env = None;
       for fp in args.iter() {
          println!("Compiling: {}", fp);
@@ -56,17 +109,64 @@ fn main() {
          tlc.parse_file(None, fp).unwrap();
       }
    } else if command=="check" {
+      let mut tlc = tlc.accumulate();
       let mut env = None;
       for fp in args.iter() {
          println!("Typechecking: {}", fp);
+         match tlc.import_file(env, fp) {
+            Ok(sc) => { env = Some(sc); },
+            Err(_) => {
+               for d in tlc.diagnostics.drain(..) {
+                  let source = std::fs::read_to_string(d.primary.span.filename.as_str()).unwrap_or_default();
+                  eprintln!("{}", d.render(&source));
+               }
+               std::process::exit(1);
+            }
+         }
+      }
+   } else if command=="quickcheck" {
+      let mut env = None;
+      for fp in args.iter() {
          env = Some(tlc.import_file(env, fp).unwrap());
       }
+      //only statements from the provided files are checked, not those of the prelude
+      let mut failed = false;
+      for rule in tlc.rules.clone().iter() {
+         let TypeRule::Forall(fr) = rule else { continue };
+         if !args.iter().any(|fp| fp==fr.span.filename.as_str()) { continue; }
+         match tlc.falsify(fr) {
+            Ok(None) => {},
+            Ok(Some(ce)) => {
+               println!("Counterexample: {:?}, {:?} in {} --> {},{}", rule, ce,
+                        fr.span.filename, fr.span.linecol_start.0, fr.span.linecol_start.1);
+               failed = true;
+            },
+            Err(e) => { eprintln!("{:?}", e); failed = true; },
+         }
+      }
+      if failed { std::process::exit(1); }
+   } else if command=="repl" {
+      let stdin = std::io::stdin();
+      let stdout = std::io::stdout();
+      let mut repl = Repl::with_tlc(tlc);
+      for fp in args.iter() {
+         if let Err(e) = repl.command(&format!(":load {}", fp)) {
+            eprintln!("{:?}", e);
+         }
+      }
+      repl.run(&mut stdin.lock(), &mut stdout.lock()).unwrap();
    } else {
       println!("lsts help");
       println!("     parse [filenames] -- parse files but nothing more");
       println!("     check [filenames] -- parse and typecheck files");
       println!("     build [filenames] -- compile provided files as a program");
       println!("     run   [filenames] -- execute provided files as a program");
-      println!("");
+      println!("     check, build or run [lsts.toml or package directory] -- operate on a package");
+      println!("           with no filenames, the package in the current directory is used");
+      println!("     quickcheck [filenames] -- search for counterexamples to forall statements");
+      println!("     repl  [filenames] -- load provided files and read statements interactively");
+      println!("     --prelude file -- import file into files that do not start with an import, instead of L1");
+      println!("     --no-prelude   -- do not import a prelude into files that do not start with an import");
+      println!();
    }
 }
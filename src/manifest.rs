@@ -0,0 +1,163 @@
+use std::rc::Rc;
+use std::path::{Path,PathBuf};
+use crate::debug::Error;
+use crate::token::Span;
+
+/// A Manifest describes a package, read from an lsts.toml file.
+///
+/// ```toml
+/// [package]
+/// name = "app"
+/// roots = ["src"]            #every .tlc file under a root belongs to the package
+/// entry = "src/main.tlc"     #the file that is run, checked last
+/// prelude = "preludes/l1.tlc" #or false for no implicit prelude
+/// strict = false
+///
+/// [dependencies]
+/// mathlib = { path = "../mathlib" }
+/// ```
+#[derive(Clone)]
+pub struct Manifest {
+   pub name: String,
+   pub path: String,
+   pub dir: PathBuf,
+   pub roots: Vec<PathBuf>,
+   pub entry: Option<PathBuf>,
+   pub prelude: Option<String>,
+   pub strict: bool,
+   pub dependencies: Vec<Manifest>,
+}
+
+impl Manifest {
+   fn error(path: &str, src: &str, offset: usize, rule: String) -> Error {
+      let offset = std::cmp::min(offset, src.len());
+      let line = src[..offset].matches('\n').count() + 1;
+      let column = offset - src[..offset].rfind('\n').map(|i| i+1).unwrap_or(0) + 1;
+      Error::new("Manifest Error", rule, Span {
+         filename: Rc::new(path.to_string()),
+         offset_start: offset,
+         offset_end: offset,
+         linecol_start: (line,column),
+         linecol_end: (line,column),
+      })
+   }
+   /// The manifest of a package, given either the lsts.toml file or the directory that contains it.
+   pub fn find(path: &str) -> Option<String> {
+      let p = Path::new(path);
+      if p.is_dir() && p.join("lsts.toml").is_file() {
+         Some(p.join("lsts.toml").to_string_lossy().to_string())
+      } else if p.file_name().map(|f| f=="lsts.toml").unwrap_or(false) && p.is_file() {
+         Some(path.to_string())
+      } else { None }
+   }
+   /// Read a manifest and, recursively, the manifests of its path dependencies.
+   pub fn load(path: &str) -> Result<Manifest,Error> {
+      Manifest::load_impl(path, &mut Vec::new())
+   }
+   fn load_impl(path: &str, loading: &mut Vec<(PathBuf,String)>) -> Result<Manifest,Error> {
+      let src = std::fs::read_to_string(path)
+                .map_err(|e| Manifest::error(path, "", 0, format!("Could not read manifest: {}", e)))?;
+      let table = src.parse::<toml::Table>()
+                  .map_err(|e| Manifest::error(path, &src, e.span().map(|s| s.start).unwrap_or(0), e.message().to_string()))?;
+      let dir = Path::new(path).parent().map(|d| d.to_path_buf()).unwrap_or_default();
+      let package = table.get("package").and_then(|p| p.as_table())
+                    .ok_or_else(|| Manifest::error(path, &src, 0, "Manifest must have a [package] table".to_string()))?;
+      let string = |key: &str| -> Result<Option<String>,Error> {
+         match package.get(key) {
+            None => Ok(None),
+            Some(toml::Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(Manifest::error(path, &src, 0, format!("package.{} must be a string", key))),
+         }
+      };
+      let name = string("name")?.ok_or_else(|| Manifest::error(path, &src, 0, "package.name is required".to_string()))?;
+      let entry = string("entry")?.map(|e| dir.join(e));
+      let roots = match package.get("roots") {
+         None => vec![dir.clone()],
+         Some(toml::Value::Array(rs)) => rs.iter().map(|r| match r {
+            toml::Value::String(r) => Ok(dir.join(r)),
+            _ => Err(Manifest::error(path, &src, 0, "package.roots must be a list of strings".to_string())),
+         }).collect::<Result<Vec<PathBuf>,Error>>()?,
+         Some(_) => { return Err(Manifest::error(path, &src, 0, "package.roots must be a list of strings".to_string())); },
+      };
+      //a prelude next to the manifest is read from the package, any other is resolved like an import
+      let prelude = match package.get("prelude") {
+         None => Some("preludes/l1.tlc".to_string()),
+         Some(toml::Value::Boolean(false)) => None,
+         Some(toml::Value::String(p)) if dir.join(p).is_file() => Some(dir.join(p).to_string_lossy().to_string()),
+         Some(toml::Value::String(p)) => Some(p.clone()),
+         Some(_) => { return Err(Manifest::error(path, &src, 0, "package.prelude must be a string or false".to_string())); },
+      };
+      let strict = match package.get("strict") {
+         None => false,
+         Some(toml::Value::Boolean(b)) => *b,
+         Some(_) => { return Err(Manifest::error(path, &src, 0, "package.strict must be a boolean".to_string())); },
+      };
+
+      let key = std::fs::canonicalize(&dir).unwrap_or(dir.clone());
+      if loading.iter().any(|(k,_)| k==&key) {
+         let chain = loading.iter().map(|(_,n)| n.clone()).chain(std::iter::once(name)).collect::<Vec<String>>();
+         return Err(Manifest::error(path, &src, 0, format!("Circular dependency: {}", chain.join(" -> "))));
+      }
+      loading.push((key, name.clone()));
+      let mut dependencies = Vec::new();
+      if let Some(ds) = table.get("dependencies").and_then(|d| d.as_table()) {
+         for (dn,d) in ds.iter() {
+            let Some(dp) = d.get("path").and_then(|p| p.as_str()) else {
+               return Err(Manifest::error(path, &src, 0, format!("dependency {} must have a path", dn)));
+            };
+            let Some(dm) = Manifest::find(&dir.join(dp).to_string_lossy()) else {
+               return Err(Manifest::error(path, &src, 0, format!("dependency {} has no lsts.toml at {}", dn, dp)));
+            };
+            dependencies.push(Manifest::load_impl(&dm, loading)?);
+         }
+      }
+      loading.pop();
+
+      Ok(Manifest {
+         name,
+         path: path.to_string(),
+         dir,
+         roots,
+         entry,
+         prelude,
+         strict,
+         dependencies,
+      })
+   }
+   /// The files of the package, with the entry last.
+   pub fn files(&self) -> Vec<String> {
+      fn walk(dir: &Path, fs: &mut Vec<PathBuf>) {
+         let Ok(es) = std::fs::read_dir(dir) else { return };
+         for e in es.flatten() {
+            let p = e.path();
+            if p.is_dir() { walk(&p, fs); }
+            else if p.extension().map(|x| x=="tlc").unwrap_or(false) { fs.push(p); }
+         }
+      }
+      let mut fs = Vec::new();
+      for r in self.roots.iter() { walk(r, &mut fs); }
+      fs.sort();
+      fs.dedup();
+      if let Some(ref e) = self.entry {
+         fs.retain(|f| f!=e);
+         fs.push(e.clone());
+      }
+      fs.iter().map(|f| f.to_string_lossy().to_string()).collect()
+   }
+   /// Packages in the order that they are checked, each dependency before the packages that depend on it.
+   pub fn build_order(&self) -> Vec<&Manifest> {
+      fn visit<'a>(m: &'a Manifest, order: &mut Vec<&'a Manifest>) {
+         for d in m.dependencies.iter() { visit(d, order); }
+         if !order.iter().any(|o| o.dir==m.dir) { order.push(m); }
+      }
+      let mut order = Vec::new();
+      visit(self, &mut order);
+      order
+   }
+   /// The package is checked as a file that imports each of its files, optionally leaving out the entry.
+   pub fn source(&self, entry: bool) -> String {
+      let mut fs = self.files();
+      if !entry && self.entry.is_some() { fs.pop(); }
+      fs.iter().map(|f| format!("import $\"{}\";\n", f)).collect()
+   }
+}
@@ -0,0 +1,156 @@
+use std::io::{BufRead,Write};
+use crate::tlc::TLC;
+use crate::term::Term;
+use crate::scope::{Scope,ScopeId};
+use crate::constant::Constant;
+use crate::debug::Error;
+
+/// A Repl keeps one context alive and accepts statements incrementally.
+/// Statements may span several lines and are evaluated once they end with a semicolon.
+pub struct Repl {
+   pub tlc: TLC,
+   pub globals: Option<ScopeId>,
+   pub buffer: String,
+   pub values: Vec<(String,Constant)>,
+}
+
+impl Repl {
+   pub fn new() -> Repl {
+      Repl::with_tlc(TLC::new())
+   }
+   /// Start a repl in a configured context, such as one with another prelude.
+   pub fn with_tlc(mut tlc: TLC) -> Repl {
+      let globals = match tlc.prelude.clone() {
+         Some(prelude) => tlc.import_file(None, &prelude).ok(),
+         None => Some(tlc.push_scope(Scope { parent: None, children: Vec::new() })),
+      };
+      Repl {
+         tlc,
+         globals,
+         buffer: String::new(),
+         values: Vec::new(),
+      }
+   }
+   /// True if a statement has been started but not yet terminated.
+   pub fn is_continued(&self) -> bool {
+      !self.buffer.trim().is_empty()
+   }
+   /// Feed one line of input.
+   /// Returns None while a statement is still incomplete, otherwise the output or the error.
+   pub fn feed(&mut self, line: &str) -> Option<Result<String,Error>> {
+      if !self.is_continued() && line.trim_start().starts_with(':') {
+         return Some(self.command(line.trim()));
+      }
+      self.buffer += line;
+      self.buffer += "\n";
+      if !self.buffer.trim_end().ends_with(';') {
+         return None;
+      }
+      let src = std::mem::take(&mut self.buffer);
+      Some(self.eval(&src))
+   }
+   /// Check and evaluate complete statements, keeping their definitions on success.
+   /// A failed statement is rolled back so that earlier definitions survive.
+   pub fn eval(&mut self, src: &str) -> Result<String,Error> {
+      let snapshot = self.tlc.snapshot();
+      let ast = match self.tlc.check_str(self.globals, src) {
+         Ok(ast) => ast,
+         Err(e) => { self.tlc.restore(snapshot); return Err(e); },
+      };
+      let Term::Block(_,es) = self.tlc.rows[ast.id].term.clone() else { return Ok(String::new()) };
+      let mut buf = String::new();
+      for e in es.iter() {
+         //statements from the implicit prelude and type or forall statements are not echoed
+         if self.tlc.rows[e.id].span.filename.as_str() != "[string]" { continue; }
+         match &self.tlc.rows[e.id].term {
+            Term::Let(lt) => {
+               //valued let bindings are remembered so that later statements can reduce them
+               if let (true,Some(b)) = (lt.parameters.is_empty(),lt.body) {
+                  if let Ok(v) = Term::reduce_with(&self.tlc, &self.globals, &self.values, b) {
+                     self.values.retain(|(n,_)| n != &lt.name);
+                     self.values.push((lt.name.clone(), v));
+                  }
+               }
+               buf += &format!("{} : {:?}\n", lt.name, lt.typeof_binding());
+            },
+            _ => {
+               let tt = self.tlc.rows[e.id].typ.clone();
+               if let Ok(v) = Term::reduce_with(&self.tlc, &self.globals, &self.values, *e) {
+                  buf += &format!("{:?} : {:?}\n", v, tt);
+               } else {
+                  buf += &format!("{:?}\n", tt);
+               }
+            },
+         }
+      }
+      Ok(buf)
+   }
+   /// Meta-commands start with a colon.
+   pub fn command(&mut self, line: &str) -> Result<String,Error> {
+      let (cmd,arg) = line.split_once(char::is_whitespace).unwrap_or((line,""));
+      let arg = arg.trim();
+      match cmd {
+         ":type" => {
+            let src = format!("{};", arg.trim_end_matches(';'));
+            let snapshot = self.tlc.snapshot();
+            let r = self.tlc.check_str(self.globals, &src).map(|ast| match &self.tlc.rows[ast.id].term {
+               Term::Block(_,es) => es.last().map(|e| format!("{:?}\n", self.tlc.rows[e.id].typ)).unwrap_or_default(),
+               _ => String::new(),
+            });
+            self.tlc.restore(snapshot);
+            r
+         },
+         ":scope" => {
+            Ok(self.globals.map(|g| self.tlc.print_scope(g)).unwrap_or_default())
+         },
+         ":rules" => {
+            Ok(self.tlc.rules.iter().map(|r| format!("{:?}\n", r)).collect::<String>())
+         },
+         ":load" => {
+            //a file that fails to check leaves nothing behind, like a statement that fails to check
+            let snapshot = self.tlc.snapshot();
+            match self.tlc.import_file(self.globals, arg) {
+               Ok(g) => {
+                  self.globals = Some(g);
+                  Ok(format!("loaded {}\n", arg))
+               },
+               Err(e) => {
+                  self.tlc.restore(snapshot);
+                  Err(e)
+               },
+            }
+         },
+         _ => {
+            Ok("commands: :type expr, :scope, :rules, :load file.tlc, :quit\n".to_string())
+         },
+      }
+   }
+   /// Read statements from input until it is closed or :quit is entered.
+   pub fn run<R: BufRead, W: Write>(&mut self, input: &mut R, output: &mut W) -> std::io::Result<()> {
+      loop {
+         write!(output, "{}", if self.is_continued() { "....> " } else { "lsts> " })?;
+         output.flush()?;
+         let mut line = String::new();
+         if input.read_line(&mut line)? == 0 { break; }
+         let line = line.trim_end_matches('\n');
+         if !self.is_continued() && line.trim() == ":quit" { break; }
+         let source = format!("{}{}\n", self.buffer, line);
+         match self.feed(line) {
+            None => {},
+            Some(Ok(out)) => { write!(output, "{}", out)?; },
+            Some(Err(e)) => {
+               let source = if e.span.filename.as_str() == "[string]" { source }
+                            else { std::fs::read_to_string(e.span.filename.as_str()).unwrap_or_default() };
+               write!(output, "{}", e.diagnostic().render(&source))?;
+            },
+         }
+      }
+      Ok(())
+   }
+}
+
+impl Default for Repl {
+   fn default() -> Self {
+      Repl::new()
+   }
+}
@@ -3,21 +3,25 @@ use crate::typ::Type;
 use crate::kind::Kind;
 use crate::term::TermId;
 use crate::tlc::TLC;
+use crate::debug::Error;
+use crate::token::{Span};
+use serde::{Serialize,Deserialize};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct ScopeId {
    pub id: usize,
 }
 
-#[derive(Clone)]
+#[derive(Clone,Serialize,Deserialize)]
 pub struct Scope {
    pub parent: Option<ScopeId>,
    pub children: Vec<(String,HashMap<Type,Kind>,Type,Option<TermId>)>,
 }
 
 impl Scope {
-   pub fn lookup_term(tlc: &TLC, scope: ScopeId, v: &str, t: &Type) -> Option<TermId> {
+   pub fn lookup_term(tlc: &TLC, scope: ScopeId, v: &str, t: &Type, span: &Span) -> Result<Option<TermId>,Error> {
       let mut candidates = Vec::new();
+      let implies = |subs: &mut Vec<(Type,Type)>, lt: &Type, rt: &Type| Type::subs_implies(tlc, subs, lt, rt);
       for (cv,_ck,ct,cb) in tlc.scopes[scope.id].children.iter() {
          // NO  neg:(Integer)->(Integer) => (Whole)->(Integer)
          // YES neg:(Integer)->(Integer) => (Integer)->(Integer)
@@ -27,51 +31,88 @@ impl Scope {
          if cv == v {
          if let Some(cb) = cb {
          match (t,ct) {
-            (Type::Arrow(td,tr),Type::Arrow(ctd,ctr)) => { if
-               !Type::implies(tlc, td, ctd).is_bottom() &&
-               !Type::implies(tlc, tr, ctr).is_bottom() {
-               candidates.push((ct.clone(), *cb));
-            }}, _ => { if !Type::implies(tlc, t, ct).is_bottom() {
+            (Type::Arrow(td,tr),Type::Arrow(ctd,ctr)) => {
+               //constant parameters such as lengths are shared between domain and range
+               let mut subs = Vec::new();
+               if !implies(&mut subs, td, ctd).is_bottom() {
+                  subs.retain(|(sv,_)| matches!(sv, Type::Constant(_)));
+                  if !implies(&mut subs, tr, ctr).is_bottom() {
+                     candidates.push((ct.clone(), *cb));
+                  }
+               }
+            }, _ => { if !implies(&mut Vec::new(), t, ct).is_bottom() {
                candidates.push((ct.clone(), *cb));
             }},
          }}}
       }
       if candidates.len() == 0 {
          if let Some(psc) = tlc.scopes[scope.id].parent {
-            return Scope::lookup_term(tlc, psc, v, t);
+            return Scope::lookup_term(tlc, psc, v, t, span);
          } else {
-            return None;
+            return Ok(None);
          }
       } else if candidates.len() == 1 {
-         return Some(candidates[0].1);
+         return Ok(Some(candidates[0].1));
       } else {
-         //careful specialization can be made sound
-         //symbol .binary : {(Integer)->(SignedBinary)+({Integer+Whole})->({Binary+SignedBinary})}
-         // .binary : (Whole)->(Binary)
-         // .binary : (Integer)->(SignedBinary)', src/scope.rs:57:10
-         //choose (Whole)->(Binary) because
-         // domain(Whole -> Binary) => domain(Integer -> SignedBinary)
-         // range(Whole -> Binary) => range(Integer -> SignedBinary)
-         for (xi,(xt,xb)) in candidates.iter().enumerate() {
-            let mut all_accept = true;
-            for (yi,(yt,_yb)) in candidates.iter().enumerate() {
-               if xi==yi { continue; }
-               match (xt,yt) {
-                  (Type::Arrow(xd,xr),Type::Arrow(yd,yr)) => {
-                  if Type::implies(tlc, xd, yd).is_bottom()
-                  || Type::implies(tlc, xr, yr).is_bottom() {
-                     all_accept = false;
-                  }},
-                  _ => { all_accept = false; }
-               }
+         if let Some(cb) = Scope::disambiguate(tlc, &candidates) {
+            return Ok(Some(cb));
+         }
+         //each candidate is labelled at its definition
+         let mut e = Error::new("Ambiguous Overload", format!("no candidate for {} : {:?} is most specific", v, t), span.clone());
+         for (ct,cb) in candidates.iter() {
+            e = e.with_label(&tlc.rows[cb.id].span, &format!("candidate {} : {:?}", v, ct));
+         }
+         if let Some(n) = Scope::suggestion(v, &candidates) {
+            e = e.with_note(&n);
+         }
+         Err(e)
+      }
+   }
+   /// The most specific candidate, if any.
+   /// Candidates are compared with the same implication that matched them, including inclusion between regex languages.
+   fn disambiguate(tlc: &TLC, candidates: &[(Type,TermId)]) -> Option<TermId> {
+      //careful specialization can be made sound
+      //symbol .binary : {(Integer)->(SignedBinary)+({Integer+Whole})->({Binary+SignedBinary})}
+      // .binary : (Whole)->(Binary)
+      // .binary : (Integer)->(SignedBinary)', src/scope.rs:57:10
+      //choose (Whole)->(Binary) because
+      // domain(Whole -> Binary) => domain(Integer -> SignedBinary)
+      // range(Whole -> Binary) => range(Integer -> SignedBinary)
+      //a later binding of the same type shadows an earlier one
+      for (xi,(xt,xb)) in candidates.iter().enumerate().rev() {
+         let mut all_accept = true;
+         for (yi,(yt,_yb)) in candidates.iter().enumerate() {
+            if xi==yi { continue; }
+            match (xt,yt) {
+               (Type::Arrow(xd,xr),Type::Arrow(yd,yr)) => {
+               if Type::implies(tlc, xd, yd).is_bottom()
+               || Type::implies(tlc, xr, yr).is_bottom() {
+                  all_accept = false;
+               }},
+               _ => { all_accept = false; }
             }
-            if all_accept { return Some(*xb); }
          }
-         let mut cs = "".to_string();
-         for (ct,_) in candidates.iter() {
-            cs += &format!("\n{} : {:?}", v, ct);
-         };
-         panic!("Scope::lookup_term multiple viable candidate functions found for symbol {} : {:?}{}", v, t, cs)
+         if all_accept { return Some(*xb); }
+      }
+      None
+   }
+   fn suggestion(v: &str, candidates: &[(Type,TermId)]) -> Option<String> {
+      //suggest whichever side of the arrow distinguishes every candidate
+      let distinct = |ts: &Vec<Type>| ts.iter().enumerate().all(|(i,x)| ts[..i].iter().all(|y| x!=y));
+      let mut ds = Vec::new();
+      let mut rs = Vec::new();
+      for (ct,_) in candidates.iter() {
+         if let Type::Arrow(cd,cr) = ct {
+            ds.push(*cd.clone());
+            rs.push(*cr.clone());
+         }
+      }
+      if rs.len()==candidates.len() && distinct(&rs) {
+         Some(format!("ascribe the result to choose a candidate, e.g. {}(..) : {:?}", v, rs[0]))
+      } else if ds.len()==candidates.len() && distinct(&ds) {
+         Some(format!("ascribe the arguments to choose a candidate, e.g. {}(.. : {:?})", v, ds[0]))
+      } else {
+         None
       }
    }
 }
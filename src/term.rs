@@ -5,15 +5,17 @@ use crate::tlc::TLC;
 use crate::constant::Constant;
 use crate::debug::{Error};
 use crate::token::{Span};
+use crate::dfa::LiteralPattern;
 use std::collections::HashMap;
+use serde::{Serialize,Deserialize};
 use lambda_mountain::*;
 
-#[derive(Clone,Copy,Eq,PartialEq,Ord,PartialOrd,Hash)]
+#[derive(Clone,Copy,Eq,PartialEq,Ord,PartialOrd,Hash,Serialize,Deserialize)]
 pub struct TermId {
    pub id: usize,
 }
 
-#[derive(Clone)]
+#[derive(Clone,Serialize,Deserialize)]
 pub struct LetTerm {
    pub is_extern: bool,
    pub scope: ScopeId,
@@ -41,7 +43,7 @@ impl LetTerm {
 }
 
 //does not implement Clone because terms are uniquely identified by their id
-#[derive(Clone)] //clone seems to be needed to deconflict mutable borrows :(
+#[derive(Clone,Serialize,Deserialize)] //clone seems to be needed to deconflict mutable borrows :(
 pub enum Term {
    Ident(String),
    Value(String),
@@ -60,6 +62,7 @@ pub enum Term {
       Vec<(ScopeId,TermId,TermId)>, //lhs's here don't need scopes because these bindings can't be polymorphic
    ),
    Fail, //indicates that Term does not return a Value
+   Literal(Vec<(LiteralPattern,Option<String>)>), //a textual pattern or concatenation, parts may be bound to names
 }
 
 impl Term {
@@ -87,7 +90,7 @@ impl Term {
          _ => false
       }
    }
-   pub fn scope_of_lhs_impl(tlc: &mut TLC, children: &mut Vec<(String,HashMap<Type,Kind>,Type,Option<TermId>)>, lhs: TermId) {
+   pub fn scope_of_lhs_impl(tlc: &mut TLC, children: &mut Vec<(String,HashMap<Type,Kind>,Type,Option<TermId>)>, lhs: TermId) -> Result<(),Error> {
       match &tlc.rows[lhs.id].term.clone() {
          Term::Ident(n) if n=="_" => {},
          Term::Ident(n) => {
@@ -99,19 +102,27 @@ impl Term {
          Term::Ascript(lt,ltt) => {
             tlc.rows[lt.id].typ = ltt.clone();
             tlc.rows[lhs.id].typ = ltt.clone();
-            Term::scope_of_lhs_impl(tlc, children, *lt);
+            Term::scope_of_lhs_impl(tlc, children, *lt)?;
          },
-         _ => unimplemented!("destructure lhs in Term::scope_of_lhs({})", tlc.print_term(lhs)),
+         Term::Tuple(lts) => {
+            for lt in lts.iter() {
+               Term::scope_of_lhs_impl(tlc, children, *lt)?;
+            }
+            tlc.rows[lhs.id].typ = Type::Tuple(lts.iter().map(|lt| tlc.rows[lt.id].typ.clone()).collect::<Vec<Type>>());
+         },
+         _ => return Err(Error::new("Parse Error",
+            format!("unsupported destructuring pattern: {}", tlc.print_term(lhs)), tlc.rows[lhs.id].span.clone())),
       }
+      Ok(())
    }
-   pub fn scope_of_lhs(tlc: &mut TLC, scope: Option<ScopeId>, lhs: TermId) -> ScopeId {
+   pub fn scope_of_lhs(tlc: &mut TLC, scope: Option<ScopeId>, lhs: TermId) -> Result<ScopeId,Error> {
       let mut children = Vec::new();
-      Term::scope_of_lhs_impl(tlc, &mut children, lhs);
+      Term::scope_of_lhs_impl(tlc, &mut children, lhs)?;
       let sid = tlc.push_scope(Scope {
          parent: scope,
          children: children,
       });
-      sid
+      Ok(sid)
    }
    pub fn compile_lhs(tlc: &TLC, scope: ScopeId, term: TermId) -> Result<Rhs,Error> {
       match &tlc.rows[term.id].term {
@@ -124,6 +135,13 @@ impl Term {
          Term::Ascript(t,_tt) => {
             Term::compile_lhs(tlc, scope, *t)
          },
+         Term::Tuple(ts) => {
+            let mut tas = Vec::new();
+            for t in ts.iter() {
+               tas.push(Term::compile_lhs(tlc, scope, *t)?);
+            }
+            Ok(Rhs::App(tas))
+         },
          Term::Constructor(cname,cts) if cname=="False" && cts.len()==0 => {
             Ok(Rhs::Literal("0".to_string()))
          },
@@ -140,7 +158,63 @@ impl Term {
             }
             Ok(Rhs::App(cas))
          },
-         _ => unimplemented!("compile_lhs: {}", tlc.print_term(term))
+         _ => Err(Error::new("Runtime",
+            format!("pattern can not be compiled: {}", tlc.print_term(term)), tlc.rows[term.id].span.clone())),
+      }
+   }
+   pub fn has_literal(tlc: &TLC, term: TermId) -> bool {
+      match &tlc.rows[term.id].term {
+         Term::Literal(_) => true,
+         Term::Ascript(t,_tt) => Term::has_literal(tlc, *t),
+         Term::Tuple(ts) => ts.iter().any(|t| Term::has_literal(tlc, *t)),
+         _ => false,
+      }
+   }
+   /// Literal patterns are matched at runtime by the literal:split extern.
+   /// The pattern is encoded as a value and the names that receive each captured substring are returned alongside.
+   pub fn compile_literal_lhs(tlc: &TLC, scope: ScopeId, term: TermId, binds: &mut Vec<Rhs>) -> Result<Rhs,Error> {
+      match &tlc.rows[term.id].term {
+         Term::Literal(ps) => {
+            let mut enc = vec![Rhs::Literal("literal".to_string())];
+            for (p,b) in ps.iter() {
+               let (kind,text) = match p {
+                  LiteralPattern::Char(c) => ("string", c.to_string()),
+                  LiteralPattern::String(v) => ("string", v.clone()),
+                  LiteralPattern::Range(r) => ("range", r.clone()),
+                  LiteralPattern::Variable(v) => ("variable", v.clone()),
+               };
+               enc.push(Rhs::App(vec![Rhs::Literal(kind.to_string()), Rhs::Literal(text)]));
+               binds.push(Rhs::Variable(match (p,b) {
+                  (_,Some(b)) => b.clone(),
+                  (LiteralPattern::Variable(v),None) => v.clone(),
+                  _ => "_".to_string(),
+               }));
+            }
+            Ok(Rhs::App(enc))
+         },
+         Term::Ident(n) => {
+            binds.push(Rhs::Variable(n.clone()));
+            Ok(Rhs::App(vec![Rhs::Literal("any".to_string())]))
+         },
+         Term::Ascript(t,_tt) => {
+            Term::compile_literal_lhs(tlc, scope, *t, binds)
+         },
+         Term::Tuple(ts) => {
+            let mut enc = vec![Rhs::Literal("tuple".to_string())];
+            for t in ts.iter() {
+               enc.push(Term::compile_literal_lhs(tlc, scope, *t, binds)?);
+            }
+            Ok(Rhs::App(enc))
+         },
+         Term::Value(_) | Term::Constructor(_,_) => {
+            if let Rhs::Literal(v) = Term::compile_lhs(tlc, scope, term)? {
+               return Ok(Rhs::App(vec![Rhs::Literal("value".to_string()), Rhs::Literal(v)]));
+            }
+            Err(Error::new("Runtime",
+               format!("pattern can not be compiled alongside a literal pattern: {}", tlc.print_term(term)), tlc.rows[term.id].span.clone()))
+         },
+         _ => Err(Error::new("Runtime",
+            format!("pattern can not be compiled: {}", tlc.print_term(term)), tlc.rows[term.id].span.clone())),
       }
    }
    pub fn compile_function(tlc: &TLC, _scope: &Option<ScopeId>, funcs: &mut Vec<(String,Rhs)>, term: TermId) -> Result<String,Error> {
@@ -157,20 +231,40 @@ impl Term {
             name += ")->";
          }
          name += &format!("{:?}", lt.rtype);
+         //bindings with the same name and type may be defined in different modules
+         name += &format!("#{}", term.id);
          name
-      } else { panic!("Term::compile_function must be a Let binding") };
+      } else { return Err(Error::new("Runtime",
+         format!("compiled function must be a let binding: {}", tlc.print_term(term)), tlc.rows[term.id].span.clone())) };
       for fd in funcs.iter() {
          if fd.0 == mangled { return Ok(mangled); }
       }
+      if let Term::Let(ref lt) = tlc.rows[term.id].term {
+      if lt.parameters.is_empty() {
+         //valued let bindings, such as lambdas, are bound directly
+         funcs.push((mangled.clone(), Rhs::App(Vec::new())));
+         let mut preamble = Vec::new();
+         let ret = if let Some(body) = lt.body {
+            Term::compile_expr(tlc, &Some(lt.scope), funcs, &mut preamble, body)?
+         } else { Rhs::App(Vec::new()) };
+         for ref mut fd in funcs.iter_mut() {
+         if fd.0 == mangled {
+            fd.1 = ret;
+            break;
+         }}
+         return Ok(mangled);
+      }}
       let mut lhs = Vec::new();
       if let Term::Let(ref lt) = tlc.rows[term.id].term {
-         if lt.parameters.len()==0 { unimplemented!("Term::compile_function valued let binding") }
-         if lt.parameters.len()>1 { unimplemented!("Term::compile_function curried let binding") }
+         let span = tlc.rows[term.id].span.clone();
+         if lt.parameters.len()>1 { return Err(Error::new("Runtime",
+            format!("curried let binding can not be compiled: {}", lt.name), span)) }
          for l in lt.parameters.iter() {
             for args in l.iter() {
                let name = args.0.clone();
                let typ = args.1.clone();
-               let _term = Scope::lookup_term(tlc, lt.scope, &name, &typ).expect("Term::compile_function parameter not found in scope");
+               if Scope::lookup_term(tlc, lt.scope, &name, &typ, &span)?.is_none() { return Err(Error::new("Runtime",
+                  format!("parameter {} not found in scope of {}", name, lt.name), span)) }
                lhs.push( Rhs::Variable(name) );
             }
          }
@@ -192,27 +286,32 @@ impl Term {
    }
    pub fn apply_fn(tlc: &TLC, scope: &Option<ScopeId>, funcs: &mut Vec<(String,Rhs)>,
                    preamble: &mut Vec<Rhs>, f: &str, ps: &Vec<TermId>,
-                   ft: Type, _span: Span) -> Result<Rhs,Error> {
-      let sc = if let Some(sc) = scope { *sc } else { panic!("Term::apply_fn, function application has no scope at {}", f) };
+                   ft: Type, span: Span) -> Result<Rhs,Error> {
+      let sc = if let Some(sc) = scope { *sc } else { return Err(Error::new("Runtime",
+         format!("function application has no scope: {}", f), span)) };
       let mut args = Vec::new();
       for p in ps.iter() {
          args.push(Term::compile_expr(tlc, scope, funcs, preamble, *p)?);
       }
-      if let Some(binding) = Scope::lookup_term(tlc, sc, f, &ft) {
+      let unsupported = |rule: String| Err(Error::new("Runtime", rule, span.clone()));
+      if let Some(binding) = Scope::lookup_term(tlc, sc, f, &ft, &span)? {
          if let Term::Let(lb) = &tlc.rows[binding.id].term {
-            if lb.parameters.len() > 1 { unimplemented!("Term::reduce, beta-reduce curried functions") }
+            if lb.parameters.len() > 1 { return unsupported(format!("curried function can not be applied: {}", f)) }
             let bt = lb.typeof_binding();
             if lb.is_extern {
-               let body = lb.body.expect(&format!("extern function body must be a mangled symbol: {}", f));
-               if let Term::Ident(mangled) = &tlc.rows[body.id].term {
-                  args.insert(0, Rhs::Variable(mangled.clone()));
+               let mangled = lb.body.and_then(|body| match &tlc.rows[body.id].term {
+                  Term::Ident(mangled) => Some(mangled.clone()),
+                  _ => None,
+               });
+               if let Some(mangled) = mangled {
+                  args.insert(0, Rhs::Variable(mangled));
                   Ok(Rhs::App(args))
-               } else { unreachable!("extern function body must be a mangled symbol: {}", f) }
+               } else { unsupported(format!("extern function body must be a mangled symbol: {}", f)) }
             } else if bt.is_open() {
                let Some(lbt) = tlc.poly_bindings.get(&(lb.name.clone(),ft.clone()))
-               else { unreachable!("could not find template function {}: {:?}", lb.name, bt) };
+               else { return unsupported(format!("could not find template function {}: {:?}", lb.name, bt)) };
                let Term::Let(_lbb) = &tlc.rows[lbt.id].term
-               else { unreachable!("template function must be let binding {}: {:?}", lb.name, bt) };
+               else { return unsupported(format!("template function must be let binding {}: {:?}", lb.name, bt)) };
                let mangled = Term::compile_function(tlc, scope, funcs, *lbt)?;
                args.insert(0, Rhs::Variable(mangled.clone()));
                Ok(Rhs::App(args))
@@ -221,10 +320,14 @@ impl Term {
                args.insert(0, Rhs::Variable(mangled.clone()));
                Ok(Rhs::App(args))
             }
+         } else if let Term::Ident(_) = &tlc.rows[binding.id].term {
+            //parameters are bound to lambdas at runtime
+            args.insert(0, Rhs::Variable(f.to_string()));
+            Ok(Rhs::App(args))
          } else {
-            panic!("Term::reduce, unexpected lambda format in beta-reduction {}", tlc.print_term(binding))
+            unsupported(format!("unexpected lambda format in beta-reduction {}", tlc.print_term(binding)))
          }
-      } else { panic!("Term::reduce, failed to lookup function {}: {:?}", f, &ft) }
+      } else { unsupported(format!("failed to lookup function {}: {:?}", f, &ft)) }
    }
    pub fn mangle_fn(tlc: &TLC, scope: &Option<ScopeId>, funcs: &mut Vec<(String,Rhs)>,
                     preamble: &mut Vec<Rhs>, g: TermId, x: TermId) -> Result<Rhs,Error> {
@@ -237,7 +340,15 @@ impl Term {
             Term::apply_fn(tlc, scope, funcs, preamble, gn, &vec![x], gt, span)
          }
       } else {
-         Term::compile_expr(tlc, scope, funcs, preamble, g)
+         let mut args = vec![Term::compile_expr(tlc, scope, funcs, preamble, g)?];
+         if let Term::Tuple(ts) = &tlc.rows[x.id].term {
+            for t in ts.iter() {
+               args.push(Term::compile_expr(tlc, scope, funcs, preamble, *t)?);
+            }
+         } else {
+            args.push(Term::compile_expr(tlc, scope, funcs, preamble, x)?);
+         }
+         Ok(Rhs::App(args))
       }
    }
    pub fn compile_expr(tlc: &TLC, scope: &Option<ScopeId>, funcs: &mut Vec<(String,Rhs)>,
@@ -270,6 +381,19 @@ impl Term {
          Term::Ident(n) => {
             Ok(Rhs::Variable(n.clone()))
          },
+         Term::Literal(ps) => {
+            let mut args = vec![Rhs::Variable("literal:join".to_string())];
+            for (p,_b) in ps.iter() {
+               args.push(match p {
+                  LiteralPattern::Char(c) => Rhs::Literal(c.to_string()),
+                  LiteralPattern::String(v) => Rhs::Literal(v.clone()),
+                  LiteralPattern::Variable(v) => Rhs::Variable(v.clone()),
+                  LiteralPattern::Range(_) => return Err(Error::new("Runtime",
+                     format!("character range can not be reduced: {}", tlc.print_term(term)), span)),
+               });
+            }
+            Ok(Rhs::App(args))
+         },
          Term::Ascript(t,_tt) => {
             //TODO gradual type
             Term::compile_expr(tlc, scope, funcs, preamble, *t)
@@ -278,6 +402,10 @@ impl Term {
             let bt = tlc.rows[t.id].typ.clone();
             if !Type::implies(tlc, &bt, tt).is_bottom() {
                Term::compile_expr(tlc, scope, funcs, preamble, *t)
+            } else if tlc.is_knormal(&tlc.kind(tt).first()) {
+               //a cast between units is proven by its chain of conversions, which is not applied to values
+               Err(Error::new("Runtime",
+                  format!("unit cast can not be reduced, conversions are only checked: {}", tlc.print_term(term)), span))
             } else {
                let bts = Type::Tuple(vec![bt]);
                let gt = Type::Arrow(Box::new(bts), Box::new(tt.clone()));
@@ -298,6 +426,34 @@ impl Term {
          Term::Match(dv,lrs) => {
             //These panics are OK, because the type-checker should disprove them
             let pe = Term::compile_expr(tlc, scope, funcs, preamble, *dv)?;
+            if lrs.iter().any(|(_lrc,l,_r)| Term::has_literal(tlc, *l)) {
+               //literal patterns are tried one arm at a time, each arm falling through to the next
+               let mut rest = Rhs::App(vec![
+                  Rhs::Variable("error".to_string()),
+                  Rhs::Literal("Pattern Match Failure".to_string()),
+               ]);
+               for (lrc,l,r) in lrs.iter().rev() {
+                  let rhs = Term::compile_expr(tlc, &Some(*lrc), funcs, preamble, *r)?;
+                  let fallthrough = Rhs::Lambda(vec![Rhs::Variable("_".to_string())], vec![rest]);
+                  rest = if Term::has_literal(tlc, *l) {
+                     let mut binds = vec![Rhs::Literal("1".to_string())];
+                     let enc = Term::compile_literal_lhs(tlc, *lrc, *l, &mut binds)?;
+                     Rhs::App(vec![
+                        Rhs::Variable("match".to_string()),
+                        Rhs::App(vec![Rhs::Variable("literal:split".to_string()), pe.clone(), enc]),
+                        Rhs::App(vec![Rhs::Lambda(vec![Rhs::App(binds)],vec![rhs]), fallthrough]),
+                     ])
+                  } else {
+                     let lhs = Term::compile_lhs(tlc, *lrc, *l)?;
+                     Rhs::App(vec![
+                        Rhs::Variable("match".to_string()),
+                        pe.clone(),
+                        Rhs::App(vec![Rhs::Lambda(vec![lhs],vec![rhs]), fallthrough]),
+                     ])
+                  };
+               }
+               return Ok(rest);
+            }
             let mut plrs = Vec::new();
             for (lrc,l,r) in lrs.iter() {
                let lhs = Term::compile_lhs(tlc, *lrc, *l)?;
@@ -311,11 +467,24 @@ impl Term {
             ]))
          },
          Term::Arrow(sc,lhs,_lt,rhs) => {
-            let lhs = Term::compile_lhs(tlc, *sc, *lhs)?;
+            let lhs = if let Term::Tuple(lts) = &tlc.rows[lhs.id].term {
+               let mut lhs = Vec::new();
+               for lt in lts.iter() {
+                  lhs.push(Term::compile_lhs(tlc, *sc, *lt)?);
+               }
+               lhs
+            } else {
+               vec![Term::compile_lhs(tlc, *sc, *lhs)?]
+            };
             let rhs = Term::compile_expr(tlc, &Some(*sc), funcs, preamble, *rhs)?;
-            Ok(Rhs::Lambda(vec![lhs], vec![rhs]))
+            Ok(Rhs::Lambda(lhs, vec![rhs]))
          },
          Term::App(gt,xt) => {
+            //the length of a tuple is known statically even if the tuple is not
+            if let (Term::Ident(gn),Some(Constant::Literal(n))) = (&tlc.rows[gt.id].term,tlc.rows[term.id].typ.constant()) {
+            if gn==".length" && str::parse::<u64>(&n).is_ok() {
+               return Ok(Rhs::Literal(n));
+            }}
             let x = Term::compile_expr(tlc, scope, funcs, preamble, *xt)?;
 
             if let Term::Project(Constant::Literal(cv)) = &tlc.rows[gt.id].term {
@@ -325,69 +494,71 @@ impl Term {
                   x
                ]));
             }
-            Term::mangle_fn(tlc, scope, funcs, preamble, *gt, *xt)
-            /*
-            match (&tlc.rows[g.id].term,&tlc.rows[x.id].term) {
-               (Term::Ident(gv),Term::Tuple(ps)) if gv==".flatmap" && ps.len()==2 => {
-                  let Term::Arrow(asc,lhs,_att,rhs) = tlc.rows[ps[1].id].term.clone()
-                  else { panic!(".flatmap second argument must be an arrow: {}", tlc.print_term(ps[1])) };
-                  if let Term::Match(mcond,mlrs) = &tlc.rows[rhs.id].term {
-                  if mlrs.len()==2 {
-                  if let Term::Constructor(tname,_) = &tlc.rows[mlrs[0].1.id].term {
-                  if tname == "True" {
-                  if let Term::Constructor(fname,_) = &tlc.rows[mlrs[1].1.id].term {
-                  if fname == "False" {
-                  if let Term::Tuple(fvalue) = &tlc.rows[mlrs[1].2.id].term {
-                  if fvalue.len()==0 {
-                     let (_true_scope,_true_lhs,true_rhs) = mlrs[0];
-                     let map_iterable = Term::compile_expr(tlc, &Some(sc), funcs, preamble, ps[0])?;
-                     let map_lhs = Term::compile_lhs(tlc, asc, lhs)?;
-                     let map_yield = Term::compile_expr(tlc, &Some(asc), funcs, preamble, true_rhs)?;
-                     let map_guard = Term::compile_expr(tlc, &Some(asc), funcs, preamble, *mcond)?;
-                     let map_ti = TIPart::expression(Expression::pattern(
-                        map_guard, vec![(
-                           LHSPart::literal("1"),
-                           map_yield.typed("Value")
-                        )]
-                     ,span.clone()).typed("Value"));
-                     let map = Expression::map(
-                        map_lhs,
-                        map_iterable.typed("Value"),
-                        map_ti,
-                        span.clone(),
-                     ).typed("Value");
-                     let flatmap = Expression::apply(".flatten:(Tuple)->Tuple", vec![map], span).typed("Value");
-                     return Ok(flatmap)
-                  }} }} }} }}
-                  let map_iterable = Term::compile_expr(tlc, &Some(sc), funcs, preamble, ps[0])?;
-                  let map_lhs = Term::compile_lhs(tlc, asc, lhs)?;
-                  let map_yield = Term::compile_expr(tlc, &Some(asc), funcs, preamble, rhs)?;
-                  let map = Expression::map(
-                     map_lhs,
-                     map_iterable.typed("Value"),
-                     TIPart::expression(map_yield.typed("Value")),
-                     span.clone(),
-                  ).typed("Value");
-                  let flatmap = Expression::apply(".flatten:(Tuple)->Tuple", vec![map], span).typed("Value");
-                  Ok(flatmap)
-               },
-               _ => unimplemented!("Term::reduce, implement Call-by-Value function call: {}({})", tlc.print_term(*g), tlc.print_term(*x))
+            if let Some((is_while,cond,body)) = tlc.loop_construct(*gt, *xt) {
+               let cond = Term::compile_expr(tlc, scope, funcs, preamble, cond)?;
+               let body = Term::compile_expr(tlc, scope, funcs, preamble, body)?;
+               return Ok(if is_while {
+                  //the body of a while loop is not evaluated unless the loop is entered
+                  Rhs::App(vec![Rhs::Variable("while".to_string()), cond, Rhs::Lambda(Vec::new(), vec![body])])
+               } else {
+                  Rhs::App(vec![Rhs::Variable("loop".to_string()), body, cond])
+               });
             }
-            */
+            Term::mangle_fn(tlc, scope, funcs, preamble, *gt, *xt)
          },
-         _ => unimplemented!("Term::compile_expr {}", tlc.print_term(term)),
+         _ => Err(Error::new("Runtime", format!("term can not be reduced: {}", tlc.print_term(term)), span)),
       }
    }
    pub fn reduce(tlc: &TLC, scope: &Option<ScopeId>, term: TermId) -> Result<Constant,Error> {
+      Term::reduce_with(tlc, scope, &[], term)
+   }
+   pub fn reduce_with(tlc: &TLC, scope: &Option<ScopeId>, bindings: &[(String,Constant)], term: TermId) -> Result<Constant,Error> {
+      Term::compile_reduction(tlc, scope, term)?.reduce_with(bindings)
+   }
+   /// Apply a builtin extern to constant arguments, if it computes a result for them.
+   pub fn apply_extern(mangled: &str, args: &[Constant], span: &Span) -> Option<Constant> {
+      let policy = Term::builtin_policy();
+      if !policy.externs.contains_key(mangled) { return None; }
+      let mut app = vec![Rhs::Variable(mangled.to_string())];
+      app.extend(args.iter().map(|a| a.into_value()));
+      Reduction {
+         context: Context::new(&policy),
+         preamble: vec![Rhs::App(app)],
+         span: span.clone(),
+      }.reduce_with(&[]).ok()
+   }
+   /// Compile a term and the policy that evaluates it, so that it can be reduced under many bindings.
+   pub fn compile_reduction(tlc: &TLC, scope: &Option<ScopeId>, term: TermId) -> Result<Reduction,Error> {
       let span = tlc.rows[term.id].span.clone();
+      let mut policy = Term::builtin_policy();
 
+      let mut preamble = Vec::new();
+      let mut funcs = Vec::new();
+      let pe = Term::compile_expr(tlc, scope, &mut funcs, &mut preamble, term)?;
+      preamble.push(pe);
+
+      for (k,v) in funcs.iter() {
+         policy.bind(k, v.clone());
+      }
+
+      Ok(Reduction {
+         context: Context::new(&policy),
+         preamble,
+         span,
+      })
+   }
+   fn builtin_policy() -> Policy {
       let mut policy = Policy::new();
       policy.bind_extern("π", &pi);
       policy.bind_extern("[]:(Tuple,U64)->Value", &get_index);
       policy.bind_extern(".length:(Tuple)->U64", &dot_length);
+      policy.bind_extern(".concat:(Tuple,Tuple)->Tuple", &dot_concat);
 
       policy.bind_extern("range:(I64,I64,I64)->I64[]", &range);
 
+      policy.bind_extern("literal:split", &literal_split);
+      policy.bind_extern("literal:join", &literal_join);
+
       policy.bind_extern(".join:(String[])->String", &string_join);
       policy.bind_extern(".join:(String[],String)->String", &string_join2);
 
@@ -435,40 +606,75 @@ impl Term {
       policy.bind_extern("pos:(F64)->F64", &pos_f64);
       policy.bind_extern("neg:(F64)->F64", &neg_f64);
 
-      let mut preamble = Vec::new();
-      let mut funcs = Vec::new();
-      let pe = Term::compile_expr(tlc, scope, &mut funcs, &mut preamble, term)?;
-      preamble.push(pe);
+      policy.bind_extern("+:(Tensor,Tensor)->Tensor", &add_tensor);
+      policy.bind_extern("-:(Tensor,Tensor)->Tensor", &sub_tensor);
+      policy.bind_extern("*:(Tensor,Tensor)->Tensor", &mul_tensor);
+      policy.bind_extern("dot:(F64[],F64[])->F64", &dot_tensor);
+      policy.bind_extern("matmul:(F64[][],F64[][])->F64[][]", &matmul_tensor);
+      policy.bind_extern("transpose:(F64[][])->F64[][]", &transpose_tensor);
 
-      for (k,v) in funcs.iter() {
-         policy.bind(k, v.clone());
-      }
+      //Terms are immutable, so a loop condition that holds once will hold forever
+      //while(False){body} = ()
+      //loop{body}while(False) = body
+      //otherwise the loop does not terminate, which is reported as an error
+      let diverges = Rhs::App(vec![
+         Rhs::Variable("error".to_string()),
+         Rhs::Literal("loop condition is always true, so the loop does not terminate".to_string()),
+      ]);
+      policy.bind("while", Rhs::Lambda(vec![Rhs::Literal("0".to_string()), Rhs::Variable("_".to_string())], vec![Rhs::App(Vec::new())]));
+      policy.bind("while", Rhs::Lambda(vec![Rhs::Variable("_".to_string()), Rhs::Variable("_".to_string())], vec![diverges.clone()]));
+      policy.bind("loop", Rhs::Lambda(vec![Rhs::Variable("body".to_string()), Rhs::Literal("0".to_string())], vec![Rhs::Variable("body".to_string())]));
+      policy.bind("loop", Rhs::Lambda(vec![Rhs::Variable("_".to_string()), Rhs::Variable("_".to_string())], vec![diverges]));
+      policy
+   }
+}
+
+/// A Reduction is a compiled term together with the policy that evaluates it.
+pub struct Reduction {
+   context: Context,
+   preamble: Vec<Rhs>,
+   span: Span,
+}
 
-      let context = Context::new(&policy);
+impl Reduction {
+   fn stuck(&self, v: &Rhs) -> Option<Rhs> {
+      let Rhs::App(vs) = v else { return None };
+      match vs.first() {
+         Some(Rhs::Literal(f)) if self.context.externs.contains_key(f) => Some(v.clone()),
+         _ => vs.iter().find_map(|v| self.stuck(v)),
+      }
+   }
+   pub fn reduce_with(&self, bindings: &[(String,Constant)]) -> Result<Constant,Error> {
+      let span = self.span.clone();
+      let mut context = self.context.clone();
+      for (k,v) in bindings.iter() {
+         context = context.bind(k.clone(), v.into_value());
+      }
       let mut last_e = Rhs::App(Vec::new());
-      for pe in preamble {
-         match eval_rhs(context.clone(), &[pe]) {
+      for pe in self.preamble.iter() {
+         match eval_rhs(context.clone(), std::slice::from_ref(pe)) {
             Err(e) => {
-               return Err(Error {
-                  kind: "Runtime".to_string(),
-                  rule: format!("reduce: {}", e),
-                  span: span.clone(),
-               });
+               return Err(Error::new("Runtime", format!("reduce: {}", e), span.clone()));
             }, Ok(e) => {
                last_e = e.clone();
             },
          }
       }
 
-      Ok(Constant::from_value(last_e))
+      //an extern that is left applied could not compute its result, such as on overflow
+      if let Some(stuck) = self.stuck(&last_e) {
+         return Err(Error::new("Runtime", format!("reduce: could not evaluate {}", stuck), span));
+      }
+      Constant::try_from_value(&last_e).ok_or_else(|| Error::new("Runtime",
+         format!("reduced value is not a constant: {}", last_e), span))
    }
 }
 
 fn pi(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(i), Rhs::App(ts)] = args {
-      let i = i.parse::<usize>().unwrap();
-      return ts[i].clone();
-   }
+   if let Some(t) = i.parse::<usize>().ok().and_then(|i| ts.get(i)) {
+      return t.clone();
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("π".to_string()));
    Rhs::App(args)
@@ -476,43 +682,105 @@ fn pi(args: &[Rhs]) -> Rhs {
 
 fn range(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y),Rhs::Literal(z)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
-      let z = z.parse::<usize>().unwrap();
+   if let (Ok(x),Ok(y),Ok(z)) = (x.parse::<i64>(),y.parse::<i64>(),z.parse::<usize>()) {
+   if z > 0 {
       let mut cs = Vec::new();
       for i in (x..y).step_by(z) {
          cs.push(Rhs::Literal(format!("{}",i)))
       }
       return Rhs::App(cs);
-   }
+   }}}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("range:(I64,I64,I64)->I64[]".to_string()));
    Rhs::App(args)
 }
 
-fn string_join(args: &[Rhs]) -> Rhs {
-   if let [Rhs::App(ts)] = args {
-      let mut s = String::new();
-      for t in ts {
-         s.push_str(&t.to_string());
+fn literal_text(v: &Rhs) -> Option<String> {
+   match v {
+      Rhs::Literal(l) if l.len()>=2 && l.starts_with('"') && l.ends_with('"') => Some(l[1..l.len()-1].to_string()),
+      Rhs::Literal(l) => Some(l.clone()),
+      _ => None,
+   }
+}
+fn literal_captures(v: &Rhs, pat: &Rhs, captures: &mut Vec<Rhs>) -> bool {
+   let Rhs::App(pat) = pat else { return false };
+   match pat.first() {
+      Some(Rhs::Literal(k)) if k=="any" => { captures.push(v.clone()); true },
+      Some(Rhs::Literal(k)) if k=="value" => {
+         matches!((v,&pat[1..]), (Rhs::Literal(l),[Rhs::Literal(r)]) if l==r)
+      },
+      Some(Rhs::Literal(k)) if k=="tuple" => {
+         let Rhs::App(vs) = v else { return false };
+         vs.len()+1==pat.len() && std::iter::zip(vs,&pat[1..]).all(|(v,p)| literal_captures(v, p, captures))
+      },
+      Some(Rhs::Literal(k)) if k=="literal" => {
+         let Some(text) = literal_text(v) else { return false };
+         let mut ps = Vec::new();
+         for p in pat[1..].iter() {
+            let Rhs::App(p) = p else { return false };
+            ps.push(match p.as_slice() {
+               [Rhs::Literal(k),Rhs::Literal(t)] if k=="string" => LiteralPattern::String(t.clone()),
+               [Rhs::Literal(k),Rhs::Literal(t)] if k=="range" => LiteralPattern::Range(t.clone()),
+               [Rhs::Literal(k),Rhs::Literal(t)] if k=="variable" => LiteralPattern::Variable(t.clone()),
+               _ => return false,
+            });
+         }
+         let Some(parts) = LiteralPattern::split(&ps, &text) else { return false };
+         captures.extend(parts.into_iter().map(Rhs::Literal));
+         true
+      },
+      _ => false,
+   }
+}
+fn literal_split(args: &[Rhs]) -> Rhs {
+   if let [v,pat] = args {
+      let mut captures = vec![Rhs::Literal("1".to_string())];
+      if literal_captures(v, pat, &mut captures) {
+         return Rhs::App(captures);
       }
-      return Rhs::Literal(s)
    }
+   Rhs::Literal("0".to_string())
+}
+fn literal_join(args: &[Rhs]) -> Rhs {
+   let mut s = String::new();
+   for a in args.iter() {
+      if let Some(t) = literal_text(a) {
+         s.push_str(&t);
+      } else {
+         let mut args = args.to_vec();
+         args.insert(0, Rhs::Literal("literal:join".to_string()));
+         return Rhs::App(args);
+      }
+   }
+   Rhs::Literal(s)
+}
+
+/// Strings are joined by their text, so that the result is one quoted string.
+/// This matches the language that a concatenation is typed with.
+fn join_text(ts: &[Rhs], sep: &str) -> Option<Rhs> {
+   let mut s = String::new();
+   for (ti,t) in ts.iter().enumerate() {
+      if ti>0 {
+         s.push_str(sep);
+      }
+      s.push_str(&literal_text(t)?);
+   }
+   Some(Rhs::Literal(format!("\"{}\"", s)))
+}
+fn string_join(args: &[Rhs]) -> Rhs {
+   if let [Rhs::App(ts)] = args {
+   if let Some(s) = join_text(ts, "") {
+      return s;
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal(".join:(String[])->String".to_string()));
    Rhs::App(args)
 }
 fn string_join2(args: &[Rhs]) -> Rhs {
-   if let [Rhs::App(ts),Rhs::Literal(sep)] = args {
-      let mut s = String::new();
-      for (ti,t) in ts.iter().enumerate() {
-         if ti>0 {
-            s.push_str(sep);
-         }
-         s.push_str(&t.to_string());
-      }
-      return Rhs::Literal(s)
-   }
+   if let [Rhs::App(ts),sep] = args {
+   if let Some(s) = literal_text(sep).and_then(|sep| join_text(ts, &sep)) {
+      return s;
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal(".join:(String[],String)->String".to_string()));
    Rhs::App(args)
@@ -520,9 +788,9 @@ fn string_join2(args: &[Rhs]) -> Rhs {
 
 fn get_index(args: &[Rhs]) -> Rhs {
    if let [Rhs::App(ts), Rhs::Literal(i)] = args {
-      let i = i.parse::<usize>().unwrap();
-      return ts[i].clone();
-   }
+   if let Some(t) = i.parse::<usize>().ok().and_then(|i| ts.get(i)) {
+      return t.clone();
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("[]:(Tuple,U64)->Value".to_string()));
    Rhs::App(args)
@@ -537,112 +805,115 @@ fn dot_length(args: &[Rhs]) -> Rhs {
    Rhs::App(args)
 }
 
+fn dot_concat(args: &[Rhs]) -> Rhs {
+   if let [Rhs::App(xs),Rhs::App(ys)] = args {
+      return Rhs::App(xs.iter().chain(ys.iter()).cloned().collect());
+   }
+   let mut args = args.to_vec();
+   args.insert(0, Rhs::Literal(".concat:(Tuple,Tuple)->Tuple".to_string()));
+   Rhs::App(args)
+}
+
 fn add_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
-      return Rhs::Literal(format!("{}",x+y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
+   if let Some(z) = x.checked_add(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("+:(U64,U64)->U64".to_string()));
    Rhs::App(args)
 }
 fn sub_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
-      return Rhs::Literal(format!("{}",x-y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
+   if let Some(z) = x.checked_sub(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("-:(U64,U64)->U64".to_string()));
    Rhs::App(args)
 }
 fn mul_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
-      return Rhs::Literal(format!("{}",x*y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
+   if let Some(z) = x.checked_mul(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("*:(U64,U64)->U64".to_string()));
    Rhs::App(args)
 }
 fn div_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
-      return Rhs::Literal(format!("{}",x/y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
+   if let Some(z) = x.checked_div(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("/:(U64,U64)->U64".to_string()));
    Rhs::App(args)
 }
 fn mod_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
-      return Rhs::Literal(format!("{}",x%y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
+   if let Some(z) = x.checked_rem(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("%:(U64,U64)->U64".to_string()));
    Rhs::App(args)
 }
 fn eq_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
       return Rhs::Literal(format!("{}",(x==y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("==:(U64,U64)->U8".to_string()));
    Rhs::App(args)
 }
 fn ne_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
       return Rhs::Literal(format!("{}",(x != y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("!=:(U64,U64)->U8".to_string()));
    Rhs::App(args)
 }
 fn lt_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
       return Rhs::Literal(format!("{}",(x<y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("<:(U64,U64)->U8".to_string()));
    Rhs::App(args)
 }
 fn lte_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
       return Rhs::Literal(format!("{}",(x<=y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("<=:(U64,U64)->U8".to_string()));
    Rhs::App(args)
 }
 fn gt_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
       return Rhs::Literal(format!("{}",(x>y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal(">:(U64,U64)->U8".to_string()));
    Rhs::App(args)
 }
 fn gte_u64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<u64>().unwrap();
-      let y = y.parse::<u64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<u64>(),y.parse::<u64>()) {
       return Rhs::Literal(format!("{}",(x>=y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal(">=:(U64,U64)->U8".to_string()));
    Rhs::App(args)
@@ -650,167 +921,159 @@ fn gte_u64(args: &[Rhs]) -> Rhs {
 
 fn add_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
-      return Rhs::Literal(format!("{}",x+y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
+   if let Some(z) = x.checked_add(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("+:(i64,i64)->i64".to_string()));
+   args.insert(0, Rhs::Literal("+:(I64,I64)->I64".to_string()));
    Rhs::App(args)
 }
 fn sub_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
-      return Rhs::Literal(format!("{}",x-y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
+   if let Some(z) = x.checked_sub(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("-:(i64,i64)->i64".to_string()));
+   args.insert(0, Rhs::Literal("-:(I64,I64)->I64".to_string()));
    Rhs::App(args)
 }
 fn mul_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
-      return Rhs::Literal(format!("{}",x*y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
+   if let Some(z) = x.checked_mul(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("*:(i64,i64)->i64".to_string()));
+   args.insert(0, Rhs::Literal("*:(I64,I64)->I64".to_string()));
    Rhs::App(args)
 }
 fn div_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
-      return Rhs::Literal(format!("{}",x/y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
+   if let Some(z) = x.checked_div(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("/:(i64,i64)->i64".to_string()));
+   args.insert(0, Rhs::Literal("/:(I64,I64)->I64".to_string()));
    Rhs::App(args)
 }
 fn mod_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
-      return Rhs::Literal(format!("{}",x%y));
-   }
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
+   if let Some(z) = x.checked_rem(y) {
+      return Rhs::Literal(format!("{}",z));
+   }}}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("%:(i64,i64)->i64".to_string()));
+   args.insert(0, Rhs::Literal("%:(I64,I64)->I64".to_string()));
    Rhs::App(args)
 }
 fn eq_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
       return Rhs::Literal(format!("{}",(x==y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("==:(i64,i64)->U8".to_string()));
+   args.insert(0, Rhs::Literal("==:(I64,I64)->U8".to_string()));
    Rhs::App(args)
 }
 fn ne_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
       return Rhs::Literal(format!("{}",(x != y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("!=:(i64,i64)->U8".to_string()));
+   args.insert(0, Rhs::Literal("!=:(I64,I64)->U8".to_string()));
    Rhs::App(args)
 }
 fn lt_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
       return Rhs::Literal(format!("{}",(x<y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("<:(i64,i64)->U8".to_string()));
+   args.insert(0, Rhs::Literal("<:(I64,I64)->U8".to_string()));
    Rhs::App(args)
 }
 fn lte_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
       return Rhs::Literal(format!("{}",(x<=y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("<=:(i64,i64)->U8".to_string()));
+   args.insert(0, Rhs::Literal("<=:(I64,I64)->U8".to_string()));
    Rhs::App(args)
 }
 fn gt_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
       return Rhs::Literal(format!("{}",(x>y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal(">:(i64,i64)->U8".to_string()));
+   args.insert(0, Rhs::Literal(">:(I64,I64)->U8".to_string()));
    Rhs::App(args)
 }
 fn gte_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<i64>().unwrap();
-      let y = y.parse::<i64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<i64>(),y.parse::<i64>()) {
       return Rhs::Literal(format!("{}",(x>=y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal(">=:(i64,i64)->U8".to_string()));
+   args.insert(0, Rhs::Literal(">=:(I64,I64)->U8".to_string()));
    Rhs::App(args)
 }
 fn pos_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x)] = args {
-      let x = x.parse::<i64>().unwrap();
+   if let Ok(x) = x.parse::<i64>() {
       return Rhs::Literal(format!("{}",x));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("pos:(I64)->I64".to_string()));
    Rhs::App(args)
 }
 fn neg_i64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x)] = args {
-      let x = x.parse::<i64>().unwrap();
-      return Rhs::Literal(format!("{}",-x));
-   }
+   if let Some(z) = x.parse::<i64>().ok().and_then(|x| x.checked_neg()) {
+      return Rhs::Literal(format!("{}",z));
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("neg:(I64)->I64".to_string()));
    Rhs::App(args)
 }
 
-fn bool_as_u8(x: &str) -> u8 {
+fn bool_as_u8(x: &str) -> Option<u8> {
    if x == "True" {
-      1
+      Some(1)
    } else if x == "False" {
-      0
+      Some(0)
    } else {
-      x.parse::<u8>().unwrap()
+      x.parse::<u8>().ok()
    }
 }
 fn not_u8(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x)] = args {
-      let x = bool_as_u8(x);
+   if let Some(x) = bool_as_u8(x) {
       return Rhs::Literal(format!("{}",if x==0 {1} else {0}));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("not:(U8)->U8".to_string()));
    Rhs::App(args)
 }
 fn and_u8(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = bool_as_u8(x);
-      let y = bool_as_u8(y);
+   if let (Some(x),Some(y)) = (bool_as_u8(x),bool_as_u8(y)) {
       return Rhs::Literal(format!("{}",x & y));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("&&:(U8,U8)->U8".to_string()));
    Rhs::App(args)
 }
 fn or_u8(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = bool_as_u8(x);
-      let y = bool_as_u8(y);
+   if let (Some(x),Some(y)) = (bool_as_u8(x),bool_as_u8(y)) {
       return Rhs::Literal(format!("{}",x | y));
-   }
+   }}
    let mut args = args.to_vec();
    args.insert(0, Rhs::Literal("||:(U8,U8)->U8".to_string()));
    Rhs::App(args)
@@ -818,129 +1081,216 @@ fn or_u8(args: &[Rhs]) -> Rhs {
 
 fn add_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",x+y));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("+:(f64,f64)->f64".to_string()));
+   args.insert(0, Rhs::Literal("+:(F64,F64)->F64".to_string()));
    Rhs::App(args)
 }
 fn sub_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",x-y));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("-:(f64,f64)->f64".to_string()));
+   args.insert(0, Rhs::Literal("-:(F64,F64)->F64".to_string()));
    Rhs::App(args)
 }
 fn mul_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",x*y));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("*:(f64,f64)->f64".to_string()));
+   args.insert(0, Rhs::Literal("*:(F64,F64)->F64".to_string()));
    Rhs::App(args)
 }
 fn div_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",x/y));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("/:(f64,f64)->f64".to_string()));
+   args.insert(0, Rhs::Literal("/:(F64,F64)->F64".to_string()));
    Rhs::App(args)
 }
 fn mod_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",x%y));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("%:(f64,f64)->f64".to_string()));
+   args.insert(0, Rhs::Literal("%:(F64,F64)->F64".to_string()));
    Rhs::App(args)
 }
 fn eq_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",(x==y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("==:(f64,f64)->U8".to_string()));
+   args.insert(0, Rhs::Literal("==:(F64,F64)->U8".to_string()));
    Rhs::App(args)
 }
 fn ne_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",(x != y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("!=:(f64,f64)->U8".to_string()));
+   args.insert(0, Rhs::Literal("!=:(F64,F64)->U8".to_string()));
    Rhs::App(args)
 }
 fn lt_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",(x<y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("<:(f64,f64)->U8".to_string()));
+   args.insert(0, Rhs::Literal("<:(F64,F64)->U8".to_string()));
    Rhs::App(args)
 }
 fn lte_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",(x<=y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("<=:(f64,f64)->U8".to_string()));
+   args.insert(0, Rhs::Literal("<=:(F64,F64)->U8".to_string()));
    Rhs::App(args)
 }
 fn gt_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",(x>y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal(">:(f64,f64)->U8".to_string()));
+   args.insert(0, Rhs::Literal(">:(F64,F64)->U8".to_string()));
    Rhs::App(args)
 }
 fn gte_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x),Rhs::Literal(y)] = args {
-      let x = x.parse::<f64>().unwrap();
-      let y = y.parse::<f64>().unwrap();
+   if let (Ok(x),Ok(y)) = (x.parse::<f64>(),y.parse::<f64>()) {
       return Rhs::Literal(format!("{}",(x>=y) as u8));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal(">=:(f64,f64)->U8".to_string()));
+   args.insert(0, Rhs::Literal(">=:(F64,F64)->U8".to_string()));
    Rhs::App(args)
 }
 fn pos_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x)] = args {
-      let x = x.parse::<f64>().unwrap();
+   if let Ok(x) = x.parse::<f64>() {
       return Rhs::Literal(format!("{}",x));
-   }
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("pos:(f64)->f64".to_string()));
+   args.insert(0, Rhs::Literal("pos:(F64)->F64".to_string()));
    Rhs::App(args)
 }
 fn neg_f64(args: &[Rhs]) -> Rhs {
    if let [Rhs::Literal(x)] = args {
-      let x = x.parse::<f64>().unwrap();
+   if let Ok(x) = x.parse::<f64>() {
       return Rhs::Literal(format!("{}",-x));
+   }}
+   let mut args = args.to_vec();
+   args.insert(0, Rhs::Literal("neg:(F64)->F64".to_string()));
+   Rhs::App(args)
+}
+
+fn tensor_depth(v: &Rhs) -> usize {
+   match v {
+      Rhs::App(vs) => 1 + vs.first().map(tensor_depth).unwrap_or(0),
+      _ => 0,
+   }
+}
+//an operand with fewer dimensions is broadcast over each element of the other
+fn tensor_op(x: &Rhs, y: &Rhs, op: &dyn Fn(f64,f64) -> f64) -> Option<Rhs> {
+   match (x,y,tensor_depth(x).cmp(&tensor_depth(y))) {
+      (Rhs::Literal(x),Rhs::Literal(y),_) => {
+         Some(Rhs::Literal(format!("{}",op(x.parse::<f64>().ok()?, y.parse::<f64>().ok()?))))
+      },
+      (Rhs::App(xs),Rhs::App(ys),std::cmp::Ordering::Equal) if xs.len()==ys.len() => {
+         std::iter::zip(xs,ys).map(|(x,y)| tensor_op(x, y, op)).collect::<Option<Vec<Rhs>>>().map(Rhs::App)
+      },
+      (Rhs::App(xs),y,std::cmp::Ordering::Greater) => {
+         xs.iter().map(|x| tensor_op(x, y, op)).collect::<Option<Vec<Rhs>>>().map(Rhs::App)
+      },
+      (x,Rhs::App(ys),std::cmp::Ordering::Less) => {
+         ys.iter().map(|y| tensor_op(x, y, op)).collect::<Option<Vec<Rhs>>>().map(Rhs::App)
+      },
+      _ => None,
    }
+}
+fn tensor_matrix(v: &Rhs) -> Option<Vec<Vec<f64>>> {
+   let Rhs::App(rs) = v else { return None };
+   rs.iter().map(|r| match r {
+      Rhs::App(cs) => cs.iter().map(|c| match c {
+         Rhs::Literal(c) => c.parse::<f64>().ok(),
+         _ => None,
+      }).collect::<Option<Vec<f64>>>(),
+      _ => None,
+   }).collect()
+}
+fn tensor_rhs(m: Vec<Vec<f64>>) -> Rhs {
+   Rhs::App(m.into_iter().map(|r| Rhs::App(r.into_iter().map(|c| Rhs::Literal(format!("{}",c))).collect())).collect())
+}
+fn add_tensor(args: &[Rhs]) -> Rhs {
+   if let [x,y] = args {
+   if let Some(z) = tensor_op(x, y, &|x,y| x+y) {
+      return z;
+   }}
+   let mut args = args.to_vec();
+   args.insert(0, Rhs::Literal("+:(Tensor,Tensor)->Tensor".to_string()));
+   Rhs::App(args)
+}
+fn sub_tensor(args: &[Rhs]) -> Rhs {
+   if let [x,y] = args {
+   if let Some(z) = tensor_op(x, y, &|x,y| x-y) {
+      return z;
+   }}
+   let mut args = args.to_vec();
+   args.insert(0, Rhs::Literal("-:(Tensor,Tensor)->Tensor".to_string()));
+   Rhs::App(args)
+}
+fn mul_tensor(args: &[Rhs]) -> Rhs {
+   if let [x,y] = args {
+   if let Some(z) = tensor_op(x, y, &|x,y| x*y) {
+      return z;
+   }}
+   let mut args = args.to_vec();
+   args.insert(0, Rhs::Literal("*:(Tensor,Tensor)->Tensor".to_string()));
+   Rhs::App(args)
+}
+fn dot_tensor(args: &[Rhs]) -> Rhs {
+   if let [x,y] = args {
+   if let Some(Rhs::App(zs)) = tensor_op(x, y, &|x,y| x*y) {
+   if let Some(zs) = zs.iter().map(|z| match z { Rhs::Literal(z) => z.parse::<f64>().ok(), _ => None }).collect::<Option<Vec<f64>>>() {
+      return Rhs::Literal(format!("{}",zs.iter().sum::<f64>()));
+   }}}
+   let mut args = args.to_vec();
+   args.insert(0, Rhs::Literal("dot:(F64[],F64[])->F64".to_string()));
+   Rhs::App(args)
+}
+fn matmul_tensor(args: &[Rhs]) -> Rhs {
+   if let [x,y] = args {
+   if let (Some(a),Some(b)) = (tensor_matrix(x),tensor_matrix(y)) {
+   if a.iter().all(|r| r.len()==b.len()) {
+      let n = b.first().map(|r| r.len()).unwrap_or(0);
+      return tensor_rhs(a.iter().map(|r| (0..n).map(|j| {
+         r.iter().enumerate().map(|(k,c)| c*b[k][j]).sum::<f64>()
+      }).collect()).collect());
+   }}}
+   let mut args = args.to_vec();
+   args.insert(0, Rhs::Literal("matmul:(F64[][],F64[][])->F64[][]".to_string()));
+   Rhs::App(args)
+}
+fn transpose_tensor(args: &[Rhs]) -> Rhs {
+   if let [x] = args {
+   if let Some(a) = tensor_matrix(x) {
+      let n = a.first().map(|r| r.len()).unwrap_or(0);
+      return tensor_rhs((0..n).map(|j| a.iter().map(|r| r[j]).collect()).collect());
+   }}
    let mut args = args.to_vec();
-   args.insert(0, Rhs::Literal("neg:(f64)->f64".to_string()));
+   args.insert(0, Rhs::Literal("transpose:(F64[][])->F64[][]".to_string()));
    Rhs::App(args)
 }
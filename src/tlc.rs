@@ -1,17 +1,22 @@
 use std::rc::Rc;
-use std::collections::{HashSet,HashMap};
+use std::collections::{HashSet,HashMap,BTreeMap};
 use regex::Regex;
-use crate::term::{Term,TermId,LetTerm};
+use crate::term::{Term,TermId,LetTerm,Reduction};
 use crate::scope::{Scope,ScopeId};
 use crate::typ::{Type,InArrow};
 use crate::kind::Kind;
 use crate::token::{Span,TokenReader,tokenize_string,tokenize_file};
 use crate::constant::Constant;
-use crate::debug::Error;
+use crate::debug::{Error,Diagnostic};
 use crate::ll::ll1_file;
+use crate::dfa::{DFA,LiteralPattern};
+use crate::manifest::Manifest;
+use serde::{Serialize,Deserialize};
 
 pub struct TLC {
    pub strict: bool,
+   pub accumulate: bool,
+   pub diagnostics: Vec<Diagnostic>,
    pub poly_bindings: HashMap<(String,Type),TermId>,
    pub rows: Vec<Row>,
    pub hints: HashMap<String,Vec<ForallRule>>,
@@ -19,16 +24,53 @@ pub struct TLC {
    pub scopes: Vec<Scope>,
    pub value_regexes: Vec<(String,Regex)>,
    pub regexes: Vec<(Type,Rc<Regex>)>,
+   pub languages: HashMap<String,Rc<DFA>>,
+   pub language_implies: HashMap<String,Vec<Type>>,
+   pub language_supersets: HashMap<DFA,Vec<String>>,
    pub constructors: HashMap<String,(Type,Vec<Type>,Vec<(String,Type)>)>,
    pub type_is_normal: HashSet<Type>,
    pub kind_is_normal: HashSet<Kind>,
    pub typedef_index: HashMap<String,usize>,
+   pub search_path: Vec<String>,
+   pub prelude: Option<String>,
+   pub embedded: HashMap<String,Rc<String>>,
+   pub imports: HashMap<String,(ScopeId,String)>,
+   pub importing: Vec<(String,String)>,
+   pub invariant_checks: HashMap<usize,Rc<InvariantCheck>>,
    pub term_kind: Kind,
    pub constant_kind: Kind,
    pub nil_type: Type,
    pub bottom_type: Type,
 }
 
+/// A Snapshot records the state of a TLC so that it can be restored later.
+/// Rows, rules, scopes and regexes only grow, so they are recorded by length.
+/// Everything else that a check can modify is cloned.
+#[derive(Clone)]
+pub struct Snapshot {
+   strict: bool,
+   accumulate: bool,
+   diagnostics: usize,
+   rows: usize,
+   rules: usize,
+   scopes: Vec<usize>,
+   value_regexes: usize,
+   regexes: usize,
+   languages: HashMap<String,Rc<DFA>>,
+   language_implies: HashMap<String,Vec<Type>>,
+   language_supersets: HashMap<DFA,Vec<String>>,
+   poly_bindings: HashMap<(String,Type),TermId>,
+   hints: HashMap<String,Vec<ForallRule>>,
+   constructors: Constructors,
+   type_is_normal: HashSet<Type>,
+   kind_is_normal: HashSet<Kind>,
+   typedef_index: HashMap<String,usize>,
+   imports: HashMap<String,(ScopeId,String)>,
+   invariant_checks: HashMap<usize,Rc<InvariantCheck>>,
+}
+pub(crate) type Constructors = HashMap<String,(Type,Vec<Type>,Vec<(String,Type)>)>;
+
+#[derive(Clone,Serialize,Deserialize)]
 pub struct Row {
    pub term: Term,
    pub typ: Type,
@@ -37,13 +79,13 @@ pub struct Row {
    pub untyped: bool,
 }
 
-#[derive(Clone)]
+#[derive(Clone,Serialize,Deserialize)]
 pub enum TypedefBranch {
    Regex(String),
    Constructor(String,Vec<(String,Type)>),
 }
 
-#[derive(Clone)]
+#[derive(Clone,Serialize,Deserialize)]
 pub struct Invariant {
    pub scope: ScopeId,
    pub itks: Vec<(Option<String>,Option<Type>,Kind)>,
@@ -51,7 +93,19 @@ pub struct Invariant {
    pub algs: Constant,
 }
 
-#[derive(Clone)]
+/// An InvariantCheck is the scope in which an invariant binds self and its quantified variables,
+/// and the invariant and each of its disjuncts compiled in that scope.
+/// Divisors are the quantified variables that only refute the invariant when they divide self.
+/// It is built once per invariant, when the invariant is first checked.
+pub struct InvariantCheck {
+   pub scope: ScopeId,
+   pub quants: Vec<String>,
+   pub divisors: Vec<String>,
+   pub reduction: Reduction,
+   pub disjuncts: Vec<(TermId,Option<Reduction>)>,
+}
+
+#[derive(Clone,Serialize,Deserialize)]
 pub struct TypedefRule {
    pub name: String,
    pub is_normal: bool,
@@ -63,7 +117,7 @@ pub struct TypedefRule {
    pub span: Span,
 }
 
-#[derive(Clone)]
+#[derive(Clone,Serialize,Deserialize)]
 pub struct ForallRule {
    pub axiom: bool,
    pub name: Option<String>,
@@ -75,7 +129,24 @@ pub struct ForallRule {
    pub span: Span,
 }
 
+/// A Counterexample binds the parameters of a forall rule to values
+/// for which the rule's term reduces to a value outside of its inferred type.
 #[derive(Clone)]
+pub struct Counterexample {
+   pub bindings: Vec<(String,Constant)>,
+   pub value: Constant,
+}
+
+impl std::fmt::Debug for Counterexample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} yields {:?}",
+           self.bindings.iter().map(|(n,c)| format!("{}={:?}",n,c)).collect::<Vec<String>>().join(", "),
+           self.value,
+        )
+    }
+}
+
+#[derive(Clone,Serialize,Deserialize)]
 pub enum TypeRule {
    Typedef(TypedefRule),
    Forall(ForallRule),
@@ -120,6 +191,8 @@ impl TLC {
    pub fn new() -> TLC {
       TLC {
          strict: false,
+         accumulate: false,
+         diagnostics: Vec::new(),
          //the first row, index 0, is nullary
          rows: vec![Row {
             term: Term::Tuple(Vec::new()),
@@ -139,9 +212,24 @@ impl TLC {
          scopes: Vec::new(),
          value_regexes: Vec::new(),
          regexes: Vec::new(),
+         languages: HashMap::new(),
+         language_implies: HashMap::new(),
+         language_supersets: HashMap::new(),
          hints: HashMap::new(),
          constructors: HashMap::new(),
          typedef_index: HashMap::new(),
+         search_path: Vec::new(),
+         prelude: Some("preludes/l1.tlc".to_string()),
+         //the builtin preludes are part of the binary, so they can be imported from any directory
+         embedded: [
+            ("preludes/l1.tlc", include_str!("../preludes/l1.tlc")),
+            ("preludes/si.tlc", include_str!("../preludes/si.tlc")),
+            ("preludes/algebra.tlc", include_str!("../preludes/algebra.tlc")),
+            ("preludes/tensor.tlc", include_str!("../preludes/tensor.tlc")),
+         ].iter().map(|(n,src)| (n.to_string(),Rc::new(src.to_string()))).collect(),
+         imports: HashMap::new(),
+         importing: Vec::new(),
+         invariant_checks: HashMap::new(),
          type_is_normal: HashSet::new(),
          kind_is_normal: HashSet::new(),
          term_kind: Kind::Named("Term".to_string(),Vec::new()),
@@ -154,6 +242,69 @@ impl TLC {
       self.strict = true;
       self
    }
+   pub fn accumulate(mut self) -> TLC {
+      self.accumulate = true;
+      self
+   }
+   /// Directories that are searched for imports that are not found next to the importing file.
+   pub fn search_path(mut self, dirs: Vec<String>) -> TLC {
+      self.search_path = dirs;
+      self
+   }
+   pub fn snapshot(&self) -> Snapshot {
+      Snapshot {
+         strict: self.strict,
+         accumulate: self.accumulate,
+         diagnostics: self.diagnostics.len(),
+         rows: self.rows.len(),
+         rules: self.rules.len(),
+         scopes: self.scopes.iter().map(|s| s.children.len()).collect(),
+         value_regexes: self.value_regexes.len(),
+         regexes: self.regexes.len(),
+         languages: self.languages.clone(),
+         language_implies: self.language_implies.clone(),
+         language_supersets: self.language_supersets.clone(),
+         poly_bindings: self.poly_bindings.clone(),
+         hints: self.hints.clone(),
+         constructors: self.constructors.clone(),
+         type_is_normal: self.type_is_normal.clone(),
+         kind_is_normal: self.kind_is_normal.clone(),
+         typedef_index: self.typedef_index.clone(),
+         imports: self.imports.clone(),
+         invariant_checks: self.invariant_checks.clone(),
+      }
+   }
+   pub fn restore(&mut self, s: Snapshot) {
+      self.strict = s.strict;
+      self.accumulate = s.accumulate;
+      self.diagnostics.truncate(s.diagnostics);
+      self.rows.truncate(s.rows);
+      self.rules.truncate(s.rules);
+      self.scopes.truncate(s.scopes.len());
+      for (sc,cl) in self.scopes.iter_mut().zip(s.scopes.iter()) {
+         sc.children.truncate(*cl);
+      }
+      self.value_regexes.truncate(s.value_regexes);
+      self.regexes.truncate(s.regexes);
+      self.languages = s.languages;
+      self.language_implies = s.language_implies;
+      self.language_supersets = s.language_supersets;
+      self.poly_bindings = s.poly_bindings;
+      self.hints = s.hints;
+      self.constructors = s.constructors;
+      self.type_is_normal = s.type_is_normal;
+      self.kind_is_normal = s.kind_is_normal;
+      self.typedef_index = s.typedef_index;
+      self.imports = s.imports;
+      self.invariant_checks = s.invariant_checks;
+      self.importing.clear();
+   }
+   pub fn diagnose(&mut self, e: Error) -> Error {
+      if self.accumulate {
+         self.diagnostics.push(e.diagnostic());
+      }
+      e
+   }
    pub fn print_scope(&self, s: ScopeId) -> String {
       let mut buf:String = format!("#{}{{\n", s.id);
       for (cn,pks,ct,_v) in self.scopes[s.id].children.iter() {
@@ -205,6 +356,10 @@ impl TLC {
             format!("{}{{{}}}", cn, kvs.iter().map(|(k,v)|format!("{}={}",k,self.print_term(*v))).collect::<Vec<String>>().join(","))
          },
          Term::RuleApplication(t,n) => format!("{} @{}", self.print_term(*t), n),
+         Term::Literal(ps) => {
+            format!("literal {}", ps.iter().map(|(p,b)| format!("{:?}{}", p, b.clone().unwrap_or_default()))
+                                           .collect::<Vec<String>>().join(" "))
+         },
       }
    }
    pub fn fails(&self, t: TermId) -> bool {
@@ -281,15 +436,40 @@ impl TLC {
       Ok(ll1_file(self, file_scope, tks)?)
    }
    pub fn check_toks(&mut self, globals: Option<ScopeId>, tks:&mut TokenReader) -> Result<TermId,Error> {
-      let ast = self.parse_toks(globals, tks)?;
-      self.compile_rules()?;
-      self.typeck(&globals, ast, None)?;
-      self.sanityck()?;
+      let ast = self.parse_toks(globals, tks).map_err(|e| self.diagnose(e))?;
+      self.check_ast(globals, ast)?;
       Ok(ast)
    }
+   /// Check a term that has already been parsed into globals.
+   pub fn check_ast(&mut self, globals: Option<ScopeId>, ast: TermId) -> Result<(),Error> {
+      self.compile_rules().map_err(|e| self.diagnose(e))?;
+      if self.accumulate {
+         self.typeck_all(&globals, ast)?;
+      } else {
+         self.typeck(&globals, ast, None)?;
+      }
+      self.sanityck().map_err(|e| self.diagnose(e))
+   }
+   pub fn typeck_all(&mut self, scope: &Option<ScopeId>, t: TermId) -> Result<(),Error> {
+      //typecheck each top-level statement independently and report every failure as a diagnostic
+      let (sid,es) = if let Term::Block(sid,es) = &self.rows[t.id].term { (*sid,es.clone()) }
+      else { return self.typeck(scope, t, None).map_err(|e| self.diagnose(e)) };
+      let mut first = None;
+      for e in es.iter() {
+         if let Err(err) = self.typeck(&Some(sid), *e, None) {
+            let err = self.diagnose(err);
+            if first.is_none() { first = Some(err); }
+         }
+      }
+      self.rows[t.id].typ = self.nil_type.clone();
+      if let Some(err) = first { Err(err) } else { Ok(()) }
+   }
    pub fn import_toks(&mut self, globals: Option<ScopeId>, tks:&mut TokenReader) -> Result<ScopeId,Error> {
-      self.check_toks(globals, tks)?;
-      Ok(ScopeId {id:0})
+      let ast = self.check_toks(globals, tks)?;
+      match &self.rows[ast.id].term {
+         Term::Block(sid,_) => Ok(*sid),
+         _ => Ok(globals.unwrap_or(ScopeId {id:0})),
+      }
    }
    pub fn reduce_toks(&mut self, globals: Option<ScopeId>, tks:&mut TokenReader) -> Result<Constant,Error> {
       let ast = self.check_toks(globals, tks)?;
@@ -306,12 +486,55 @@ impl TLC {
    }
    pub fn import_file(&mut self, globals: Option<ScopeId>, filename:&str) -> Result<ScopeId,Error> {
       let mut tks = tokenize_file(self, filename)?;
-      self.import_toks(globals, &mut tks)
+      let scope = self.import_toks(globals, &mut tks)?;
+      //a file imported here is not checked again when it is imported by name or as the implicit prelude
+      self.imports.entry(TLC::import_key(filename)).or_insert((scope, filename.to_string()));
+      Ok(scope)
    }
    pub fn reduce_file(&mut self, globals: Option<ScopeId>, filename:&str) -> Result<Constant,Error> {
       let mut tks = tokenize_file(self, filename)?;
       self.reduce_toks(globals, &mut tks)
    }
+   /// A package is checked as a file that imports each of its files.
+   /// The package's prelude and strictness apply, and the source roots of its dependencies are searched for imports.
+   fn import_package_files(&mut self, p: &Manifest, search_path: &[String], entry: bool) -> Result<ScopeId,Error> {
+      self.prelude = p.prelude.clone();
+      self.strict = p.strict;
+      self.search_path = search_path.to_vec();
+      for r in p.roots.iter().chain(p.dependencies.iter().flat_map(|d| d.roots.iter())) {
+         self.search_path.push(r.to_string_lossy().to_string());
+      }
+      let mut tks = tokenize_string(self, &p.path, &p.source(entry))?;
+      self.import_toks(None, &mut tks)
+   }
+   /// Check every file of a package once, after the packages that it depends on.
+   pub fn import_package(&mut self, m: &Manifest) -> Result<ScopeId,Error> {
+      let saved = (self.prelude.clone(), self.strict, self.search_path.clone());
+      let mut r = Ok(ScopeId {id:0});
+      for p in m.build_order() {
+         r = self.import_package_files(p, &saved.2, true);
+         if r.is_err() { break; }
+      }
+      (self.prelude, self.strict, self.search_path) = saved;
+      r
+   }
+   /// Check a package and evaluate its entry file.
+   pub fn reduce_package(&mut self, m: &Manifest) -> Result<Constant,Error> {
+      let saved = (self.prelude.clone(), self.strict, self.search_path.clone());
+      let mut r = Ok(ScopeId {id:0});
+      for p in m.build_order() {
+         r = self.import_package_files(p, &saved.2, p.dir!=m.dir);
+         if r.is_err() { break; }
+      }
+      //the settings of the root package still apply while its entry is evaluated
+      let v = match (r,&m.entry) {
+         (Err(e),_) => Err(e),
+         (Ok(_),None) => Ok(Constant::Tuple(Vec::new())),
+         (Ok(_),Some(e)) => self.reduce_file(None, &e.to_string_lossy()),
+      };
+      (self.prelude, self.strict, self.search_path) = saved;
+      v
+   }
 
    pub fn parse_str(&mut self, globals: Option<ScopeId>, src:&str) -> Result<TermId,Error> {
       let mut tks = tokenize_string(self, "[string]", src)?;
@@ -329,6 +552,46 @@ impl TLC {
       let mut tks = tokenize_string(self, "[string]", src)?;
       self.reduce_toks(globals, &mut tks)
    }
+   /// Use another file as the implicit prelude of files that do not start with an import.
+   pub fn prelude(mut self, filename: &str) -> TLC {
+      self.prelude = Some(filename.to_string());
+      self
+   }
+   /// Do not import anything into files that do not start with an import.
+   pub fn no_prelude(mut self) -> TLC {
+      self.prelude = None;
+      self
+   }
+   /// Register the source of a prelude so that it can be imported by name without reading a file.
+   pub fn embed_prelude(mut self, name: &str, src: &str) -> TLC {
+      self.embedded.insert(name.to_string(), Rc::new(src.to_string()));
+      self
+   }
+   pub fn prelude_source(&self, name: &str) -> Option<Rc<String>> {
+      self.embedded.get(name).cloned()
+   }
+   /// Find the file named by an import.
+   /// Paths are tried relative to the importing file, then in each directory of the search path,
+   /// then relative to the working directory, and finally among the builtin preludes.
+   pub fn resolve_import(&self, importer: &str, path: &str) -> Option<String> {
+      let p = std::path::Path::new(path);
+      let mut candidates = Vec::new();
+      if let Some(dir) = std::path::Path::new(importer).parent() {
+         candidates.push(dir.join(p));
+      }
+      for dir in self.search_path.iter() {
+         candidates.push(std::path::Path::new(dir).join(p));
+      }
+      candidates.push(p.to_path_buf());
+      for c in candidates.iter() {
+         if c.is_file() { return Some(c.to_string_lossy().to_string()); }
+      }
+      self.prelude_source(path).map(|_| path.to_string())
+   }
+   /// Files are identified by their canonical path if they exist on disk.
+   pub fn import_key(filename: &str) -> String {
+      std::fs::canonicalize(filename).map(|p| p.to_string_lossy().to_string()).unwrap_or(filename.to_string())
+   }
    pub fn compile_rules(&mut self) -> Result<(),Error> {
       for rule in self.rules.clone().iter() { match rule {
          TypeRule::Forall(fr) => { if self.strict && !fr.axiom {
@@ -341,9 +604,7 @@ impl TLC {
                TypedefBranch::Regex(pat) => {
                   if let Ok(r) = Regex::new(&pat[1..pat.len()-1]) {
                      self.regexes.push((Type::Named(tr.name.clone(),Vec::new()),Rc::new(r)));
-                  } else {
-                     panic!("typedef regex rejected: {}", pat);
-                  }
+                  } else { return Err(Error::new("Type Error", format!("typedef regex rejected: {}", pat), tr.span.clone())) }
                },
                TypedefBranch::Constructor(cname,kts) => {
                   self.constructors.insert(cname.clone(), (Type::Named(tr.name.clone(),Vec::new()),Vec::new(),kts.clone()));
@@ -354,9 +615,206 @@ impl TLC {
             }
          },
       }}
-
+      self.compile_languages()
+   }
+   /// The language of a typedef that is defined only by regexes in the supported subset.
+   pub fn language_of(&self, tr: &TypedefRule) -> Option<DFA> {
+      if !tr.parameters.is_empty() { return None; }
+      let mut lang: Option<DFA> = None;
+      for td in tr.definition.iter() { match td {
+         TypedefBranch::Regex(pat) => {
+            let d = DFA::from_regex(pat)?;
+            lang = Some(if let Some(l) = lang { l.union(&d)? } else { d });
+         },
+         TypedefBranch::Constructor(_,_) => { return None; },
+      }}
+      lang
+   }
+   /// Regex typedefs are related by language inclusion.
+   /// A typedef whose language is strictly contained in another's implies the other type,
+   /// and a declared implication is rejected if the regexes contradict it.
+   pub fn compile_languages(&mut self) -> Result<(),Error> {
+      //languages are compiled once per typedef, and all new languages are known before any are compared
+      let mut fresh: Vec<(String,Rc<DFA>,TypedefRule)> = Vec::new();
+      for rule in self.rules.clone().into_iter() {
+         let TypeRule::Typedef(tr) = rule else { continue };
+         if self.languages.contains_key(&tr.name) { continue; }
+         if let Some(lang) = self.language_of(&tr) {
+            let lang = Rc::new(lang);
+            self.languages.insert(tr.name.clone(), lang.clone());
+            fresh.push((tr.name.clone(), lang, tr));
+         }
+      }
+      if !fresh.is_empty() { self.language_supersets.clear(); }
+      for (n,l,tr) in fresh.iter() {
+         let declared = match &tr.implies {
+            Some(Type::And(ts)) => ts.clone(),
+            Some(t) => vec![t.clone()],
+            None => Vec::new(),
+         };
+         for dt in declared.iter() {
+            let Type::Named(dn,dps) = dt else { continue };
+            if !dps.is_empty() { continue; }
+            let Some(dl) = self.languages.get(dn) else { continue };
+            if let Some(w) = l.intersection(&dl.complement()).and_then(|d| d.example()) {
+               return Err(Error::new("Type Error",
+                  format!("typedef {} : {} contradicts their regexes, {} accepts {:?} but {} does not", n, dn, n, w, dn), tr.span.clone()));
+            }
+         }
+         for (on,ol) in self.languages.clone().iter() {
+            if on == n { continue; }
+            let sub = l.is_subset(ol)==Some(true);
+            let sup = ol.is_subset(l)==Some(true);
+            if sub && !sup {
+               self.language_implies.entry(n.clone()).or_default().push(Type::Named(on.clone(),Vec::new()));
+            }
+            if sup && !sub && !fresh.iter().any(|(f,_,_)| f==on) {
+               self.language_implies.entry(on.clone()).or_default().push(Type::Named(n.clone(),Vec::new()));
+            }
+         }
+      }
       Ok(())
    }
+   /// The language of values of a type, if any of its conjuncts is a regex typedef or a literal constant.
+   pub fn language_of_type(&self, tt: &Type) -> Option<DFA> {
+      match tt {
+         Type::Named(tn,tps) if tps.is_empty() => self.languages.get(tn).map(|l| (**l).clone()),
+         Type::Constant(Constant::Literal(v)) => DFA::from_literal(&[LiteralPattern::String(v.clone())]),
+         Type::And(ts) => {
+            let mut lang: Option<DFA> = None;
+            for l in ts.iter().filter_map(|ct| self.language_of_type(ct)) {
+               lang = Some(if let Some(pl) = lang { pl.intersection(&l)? } else { l });
+            }
+            lang
+         },
+         _ => None,
+      }
+   }
+   /// The parts of a string concatenation with + or of a string join, such as an f-string.
+   fn concatenated_parts(&self, t: TermId) -> Option<Vec<TermId>> {
+      let Term::App(g,x) = &self.rows[t.id].term else { return None };
+      match (&self.rows[g.id].term, &self.rows[x.id].term) {
+         (Term::Ident(gn),Term::Tuple(ps)) if gn=="+" && ps.len()==2 => Some(ps.clone()),
+         (Term::Ident(gn),Term::Tuple(ps)) if gn==".join" && ps.len()==1 => match &self.rows[ps[0].id].term {
+            Term::Tuple(ps) => Some(ps.clone()),
+            _ => None,
+         },
+         _ => None,
+      }
+   }
+   /// The regex typedefs whose languages include a language.
+   /// Inclusion is checked once per language and remembered until another typedef is compiled.
+   pub fn language_supersets(&mut self, lang: &DFA) -> Vec<String> {
+      if let Some(ns) = self.language_supersets.get(lang) { return ns.clone(); }
+      let mut ns = self.languages.iter().filter(|(_,l)| lang.is_subset(l)==Some(true)).map(|(n,_)| n.clone()).collect::<Vec<String>>();
+      ns.sort();
+      self.language_supersets.insert(lang.clone(), ns.clone());
+      ns
+   }
+   /// The language of the text of a string term.
+   /// Concatenations are typed by concatenating the languages of their parts.
+   pub fn language_of_term(&mut self, t: TermId) -> Option<DFA> {
+      if !self.languages.contains_key("String") { return None; }
+      if let Some(parts) = self.concatenated_parts(t) {
+         let mut lang: Option<DFA> = None;
+         for p in parts.iter() {
+            let pl = self.language_of_term(*p)?;
+            //the closing quote of the left part and the opening quote of the right part are not part of the text
+            lang = Some(if let Some(l) = lang { l.strip_suffix('"').concat(&pl.strip_prefix('"'))? } else { pl });
+         }
+         return lang;
+      }
+      //a term cast to String keeps the language of its text if that text is already a string
+      if let Term::As(i,_) | Term::Ascript(i,_) = &self.rows[t.id].term {
+         if let Some(l) = self.language_of_term(*i) { return Some(l); }
+      }
+      let lang = self.language_of_type(&self.rows[t.id].typ)?;
+      if self.language_supersets(&lang).iter().any(|n| n=="String") { Some(lang) } else { None }
+   }
+   /// A string concatenation is given the most specific regex typedefs that accept every string it can produce.
+   pub fn concatenation(&mut self, t: TermId) -> Option<Type> {
+      self.concatenated_parts(t)?;
+      let lang = self.language_of_term(t)?;
+      let accepts = self.language_supersets(&lang);
+      //a typedef is dropped if another accepting typedef has a strictly smaller language
+      let narrower = |n: &String, on: &String| self.language_implies.get(on).map(|ts| ts.contains(&Type::Named(n.clone(),Vec::new()))).unwrap_or(false);
+      let ts = accepts.iter().filter(|n| !accepts.iter().any(|on| narrower(n,on)))
+                      .map(|n| Type::Named(n.clone(),Vec::new())).collect::<Vec<Type>>();
+      if ts.is_empty() { None } else { Some(Type::And(ts).normalize()) }
+   }
+   /// A term is statically known if it is a literal or if its type carries a constant.
+   fn static_constant(&self, t: TermId) -> Option<Constant> {
+      if let Term::Value(v) = &self.rows[t.id].term {
+         return Some(Constant::Literal(v.clone()));
+      }
+      self.rows[t.id].typ.constant()
+   }
+   fn static_integer(&self, t: TermId) -> Option<i64> {
+      match self.static_constant(t) {
+         Some(Constant::Literal(v)) => str::parse::<i64>(&v).ok(),
+         _ => None,
+      }
+   }
+   /// The return type and mangled symbol of an applied function, if it is bound to an extern.
+   /// Builtin operations are recognized by their symbol, so a user function of the same name is not mistaken for one.
+   fn extern_binding(&self, scope: &Option<ScopeId>, g: TermId) -> Option<(Type,String)> {
+      let Term::Ident(gn) = &self.rows[g.id].term else { return None };
+      let binding = Scope::lookup_term(self, (*scope)?, gn, &self.rows[g.id].typ, &self.rows[g.id].span).ok()??;
+      let Term::Let(lb) = &self.rows[binding.id].term else { return None };
+      if !lb.is_extern { return None; }
+      match &self.rows[lb.body?.id].term {
+         Term::Ident(mangled) => Some((lb.rtype.clone(),mangled.clone())),
+         _ => None,
+      }
+   }
+   /// An application of a builtin extern to statically known scalars is evaluated while it is checked.
+   pub fn static_application(&self, scope: &Option<ScopeId>, t: TermId) -> Option<Constant> {
+      if self.rows[t.id].untyped || self.rows[t.id].typ.constant().is_some() { return None; }
+      let Term::App(g,x) = &self.rows[t.id].term else { return None };
+      let Term::Tuple(ps) = &self.rows[x.id].term else { return None };
+      let (rt,mangled) = self.extern_binding(scope, *g)?;
+      //tuples are left to tuple_shape, which knows their length without building them
+      if matches!(rt, Type::Tuple(_) | Type::HTuple(..)) { return None; }
+      let args = ps.iter().map(|p| self.static_constant(*p)).collect::<Option<Vec<Constant>>>()?;
+      Term::apply_extern(&mangled, &args, &self.rows[t.id].span)
+   }
+   /// Lengths of homogeneous tuples are carried through the builtin operations on tuples.
+   /// Returns the type of an application with its length made static, if that length is known.
+   /// Indexing beyond a static length is an error.
+   pub fn tuple_shape(&self, scope: &Option<ScopeId>, t: TermId) -> Result<Option<Type>,Error> {
+      let Term::App(g,x) = &self.rows[t.id].term else { return Ok(None) };
+      let Term::Tuple(ps) = &self.rows[x.id].term else { return Ok(None) };
+      let Some((_rt,mangled)) = self.extern_binding(scope, *g) else { return Ok(None) };
+      let rt = &self.rows[t.id].typ;
+      let int = |p: &TermId| self.static_integer(*p);
+      let n = match (mangled.as_str(),ps.as_slice()) {
+         (".length:(Tuple)->U64",[xs]) => {
+            let n = self.rows[xs.id].typ.length_constant();
+            return Ok(n.map(|n| rt.and(&Type::Constant(n))));
+         },
+         ("[]:(Tuple,U64)->Value",[xs,i]) => {
+            if let (Some(n),Some(i)) = (self.rows[xs.id].typ.length(),int(i)) {
+            if i<0 || i as usize>=n { return Err(Error::new("Type Error",
+               format!("Cannot index out-of-bounds [{}] from type {:?}", i, &self.rows[xs.id].typ), self.rows[t.id].span.clone())) }}
+            return Ok(None);
+         },
+         ("range:(I64)->I64[]",[to]) => int(to).map(|to| std::cmp::max(to,0) as usize),
+         ("range:(I64,I64)->I64[]",[from,to]) => int(from).zip(int(to)).and_then(|(from,to)| to.checked_sub(from))
+                                                  .map(|n| std::cmp::max(n,0) as usize),
+         ("range:(I64,I64,I64)->I64[]",[from,to,step]) => match (int(from),int(to),int(step)) {
+            (Some(from),Some(to),Some(step)) if step>0 => to.checked_sub(from).and_then(|d| d.checked_add(step-1))
+                                                         .map(|d| std::cmp::max(d/step,0) as usize),
+            _ => None,
+         },
+         (".flatten:(Tuple)->Tuple",[xss]) => self.rows[xss.id].typ.flat_length(),
+         (".flatmap:XYZ",[xs,f]) => match &self.rows[f.id].typ {
+            Type::Arrow(_fd,fr) => self.rows[xs.id].typ.length().zip(fr.length()).and_then(|(n,m)| n.checked_mul(m)),
+            _ => None,
+         },
+         _ => None,
+      };
+      Ok(n.map(|n| rt.with_length(n)))
+   }
    pub fn push_term(&mut self, term: Term, span: &Span) -> TermId {
       let index = self.rows.len();
       let ti = TermId { id: index };
@@ -388,11 +846,8 @@ impl TLC {
       for (ri,r) in self.rows.iter().enumerate() {
          if ri==0 { continue; } //first row is nullary and not sane
          if !r.untyped && !r.typ.is_concrete() {
-            return Err(Error {
-               kind: "Type Error".to_string(),
-               rule: format!("inhabited type is not concrete: t#{} {:?} = typeof({})", ri, r.typ, self.print_term(TermId{id:ri})),
-               span: r.span.clone(),
-            })
+            return Err(Error::new("Type Error",
+               format!("inhabited type is not concrete: t#{} {:?} = typeof({})", ri, r.typ, self.print_term(TermId{id:ri})), r.span.clone()))
          }
          let mut rvars = r.typ.vars();
          match &r.term {
@@ -404,11 +859,8 @@ impl TLC {
          }
          for tvar in rvars.iter() {
             if tvar.chars().all(char::is_uppercase) { continue; } //Type variables don't need to be defined
-            if !self.typedef_index.contains_key(tvar) { return Err(Error {
-               kind: "Type Error".to_string(),
-               rule: format!("inhabited type is not defined: {}", tvar),
-               span: r.span.clone(),
-            })}
+            if !self.typedef_index.contains_key(tvar) { return Err(Error::new("Type Error",
+               format!("inhabited type is not defined: {}", tvar), r.span.clone()))}
          }
       }
       Ok(())
@@ -419,6 +871,7 @@ impl TLC {
          Type::MaybeZero(_tt) => Ok(()),
          Type::Arrow(p,b) => { self.soundck(p,span)?; self.soundck(b,span)?; Ok(()) },
          Type::Ratio(p,b) => { self.soundck(p,span)?; self.soundck(b,span)?; Ok(()) },
+         Type::Power(p,_e) => { self.soundck(p,span) },
          Type::And(ts) => {
             for tc in ts.iter() { self.soundck(tc,span)?; }
 
@@ -428,11 +881,8 @@ impl TLC {
             for tc in ts.iter() {
             if let Type::Named(tn,_ts) = tc {
                if let Some((bt,_,_)) = self.constructors.get(tn) {
-                  if uq.contains(bt) && !nuq.contains(tn) { return Err(Error {
-                     kind: "Type Error".to_string(),
-                     rule: format!("multiple type constructors of type {:?} are present in type {:?}", bt, tt),
-                     span: span.clone(),
-                  }) }
+                  if uq.contains(bt) && !nuq.contains(tn) { return Err(Error::new("Type Error",
+                     format!("multiple type constructors of type {:?} are present in type {:?}", bt, tt), span.clone())) }
                   uq.insert(bt.clone());
                   nuq.insert(tn.clone());
                }
@@ -460,13 +910,65 @@ impl TLC {
       }
    }
    pub fn extend_implied(&self, tt: &Type) -> Type {
+      self.extend_implied_with(tt, false)
+   }
+   /// Extend a type with the types it implies, including the types whose regexes accept a strictly larger language.
+   pub fn extend_languages(&self, tt: &Type) -> Type {
+      self.extend_implied_with(tt, true)
+   }
+   /// The exponents of a unit type, with each derived unit replaced by the Product or Ratio that defines it.
+   /// Joule and Newton*Metre both become Kilo<Gram>*Metre^2/Second^2.
+   /// The expanded flag is set if any derived unit was replaced.
+   /// Returns None if an exponent overflows.
+   fn unit_exponents(&self, tt: &Type, expanded: &mut bool, depth: usize) -> Option<BTreeMap<Type,i64>> {
+      let mut es = BTreeMap::new();
+      for (ft,fe) in tt.exponents()?.into_iter() {
+         let mut derived = None;
+         if let Type::Named(tn,ts) = &ft {
+         if let Some(ti) = self.typedef_index.get(tn) {
+         if let TypeRule::Typedef(tr) = &self.rules[*ti] {
+         if let Some(it @ (Type::Product(_) | Type::Ratio(_,_) | Type::Power(_,_))) = &tr.implies {
+         if depth < 16 {
+            let mut subs = HashMap::new();
+            for ((ot,_it,_k),st) in std::iter::zip(tr.parameters.iter(), ts.iter()) {
+               subs.insert(Type::Named(ot.clone(),Vec::new()), st.clone());
+            }
+            derived = Some(self.unit_exponents(&it.substitute(&subs), expanded, depth+1)?);
+         }}}}}
+         match derived {
+            Some(ds) => {
+               *expanded = true;
+               for (dt,de) in ds.into_iter() {
+                  let e = es.entry(dt).or_insert(0i64);
+                  *e = e.checked_add(de.checked_mul(fe)?)?;
+               }
+            },
+            None => {
+               let e = es.entry(ft).or_insert(0i64);
+               *e = e.checked_add(fe)?;
+            },
+         }
+      }
+      es.retain(|_,e| *e!=0);
+      Some(es)
+   }
+   fn extend_implied_with(&self, tt: &Type, languages: bool) -> Type {
+      if let Type::Named(_,_) | Type::Product(_) | Type::Ratio(_,_) | Type::Power(_,_) = tt {
+         //derived units are compared by the units that define them, so Joule and Newton*Metre are equal
+         let mut expanded = false;
+         if let Some(es) = self.unit_exponents(tt, &mut expanded, 0) {
+         if expanded {
+            return self.extend_implied_with(&Type::from_exponents(&es), languages);
+         }}
+      }
       match tt {
          Type::Any => tt.clone(),
-         Type::MaybeZero(tt) => Type::MaybeZero(Box::new(self.extend_implied(tt))),
-         Type::Arrow(p,b) => Type::Arrow(Box::new(self.extend_implied(p)),Box::new(self.extend_implied(b))),
-         Type::Ratio(p,b) => Type::Ratio(Box::new(self.extend_implied(p)),Box::new(self.extend_implied(b))),
+         Type::MaybeZero(tt) => Type::MaybeZero(Box::new(self.extend_implied_with(tt, languages))),
+         Type::Arrow(p,b) => Type::Arrow(Box::new(self.extend_implied_with(p, languages)),Box::new(self.extend_implied_with(b, languages))),
+         Type::Ratio(p,b) => Type::Ratio(Box::new(self.extend_implied_with(p, languages)),Box::new(self.extend_implied_with(b, languages))),
+         Type::Power(p,e) => Type::Power(Box::new(self.extend_implied_with(p, languages)),*e),
          Type::Named(tn,ts) => {
-            let ts = ts.iter().map(|ct|self.extend_implied(ct)).collect::<Vec<Type>>();
+            let ts = ts.iter().map(|ct|self.extend_implied_with(ct, languages)).collect::<Vec<Type>>();
             let mut implies: Vec<Type> = Vec::new();
             let mut subs = HashMap::new();
 
@@ -477,7 +979,16 @@ impl TLC {
                   subs.insert(Type::Named(ot.clone(),Vec::new()), st.clone());
                }
                if let Some(ref it) = tr.implies {
-                  match self.extend_implied(it) {
+                  match self.extend_implied_with(it, languages) {
+                     Type::And(mut its) => { implies.append(&mut its); },
+                     i => { implies.push(i); },
+                  }
+               }
+            }}
+            if languages {
+            if let Some(lis) = self.language_implies.get(tn) {
+               for it in lis.iter() {
+                  match self.extend_implied_with(it, languages) {
                      Type::And(mut its) => { implies.append(&mut its); },
                      i => { implies.push(i); },
                   }
@@ -495,7 +1006,7 @@ impl TLC {
          Type::And(ts) => {
             let mut ats = Vec::new();
             for tc in ts.iter() {
-               let ct = self.extend_implied(tc);
+               let ct = self.extend_implied_with(tc, languages);
                if let Type::And(mut cts) = ct {
                   ats.append(&mut cts);
                } else {
@@ -504,14 +1015,26 @@ impl TLC {
             }
             Type::And(ats)
          },
-         Type::Tuple(ts) => Type::Tuple(ts.iter().map(|tc| self.extend_implied(tc)).collect::<Vec<Type>>()),
-         Type::HTuple(bt,ct) => Type::HTuple(Box::new(self.extend_implied(bt)),ct.clone()),
-         Type::Product(ts) => Type::Product(ts.iter().map(|tc| self.extend_implied(tc)).collect::<Vec<Type>>()),
+         Type::Tuple(ts) => Type::Tuple(ts.iter().map(|tc| self.extend_implied_with(tc, languages)).collect::<Vec<Type>>()),
+         Type::HTuple(bt,ct) => Type::HTuple(Box::new(self.extend_implied_with(bt, languages)),ct.clone()),
+         Type::Product(ts) => Type::Product(ts.iter().map(|tc| self.extend_implied_with(tc, languages)).collect::<Vec<Type>>()),
          Type::Constant(cv) => Type::Constant(cv.clone())
       }
    }
-   pub fn kind(&self, _tt:&Type) -> Kind {
-      self.term_kind.clone()
+   pub fn kind(&self, tt:&Type) -> Kind {
+      match tt {
+         Type::Named(tn,_ts) => {
+            if let Some(ti) = self.typedef_index.get(tn) {
+            if let TypeRule::Typedef(tr) = &self.rules[*ti] {
+               return tr.kind.clone();
+            }}
+            self.term_kind.clone()
+         },
+         Type::And(ts) => Kind::and(ts.iter().map(|ct| self.kind(ct)).collect()),
+         //the Nil unit () is not a factor, so ()/Second is a Unit
+         Type::Product(_) | Type::Ratio(_,_) | Type::Power(_,_) => Kind::and(tt.exponents().unwrap_or_default().keys().map(|ct| self.kind(ct)).collect()),
+         _ => self.term_kind.clone(),
+      }
    }
    pub fn is_knormal(&self, k:&Kind) -> bool {
       let ks = k.flatten();
@@ -529,6 +1052,7 @@ impl TLC {
          Type::Product(ts) => ts.iter().all(|ct|self.is_normal(ct)),
          Type::Arrow(p,b) => self.is_normal(p) && self.is_normal(b),
          Type::Ratio(p,b) => self.is_normal(p) && self.is_normal(b),
+         Type::Power(p,_e) => self.is_normal(p),
          Type::Constant(_) => true,
       }
    }
@@ -563,6 +1087,11 @@ impl TLC {
             if tb.is_bottom() { return tb.clone(); }
             Type::Ratio( Box::new(tp), Box::new(tb))
          },
+         Type::Power(tp,e) => {
+            let tp = self.narrow(kinds,projection,tp);
+            if tp.is_bottom() { return tp.clone(); }
+            Type::Power( Box::new(tp), *e)
+         },
          Type::And(ts) => {
             let mut cts = Vec::new();
             for ct in ts.iter() {
@@ -605,13 +1134,13 @@ impl TLC {
          }
       }
    }
-   pub fn make_scope(&mut self, parent: &Option<ScopeId>, sc: ScopeId, subs: &HashMap<Type,Type>) -> ScopeId {
+   pub fn make_scope(&mut self, parent: &Option<ScopeId>, sc: ScopeId, subs: &HashMap<Type,Type>) -> Result<ScopeId,Error> {
       let scope = self.scopes[sc.id].clone();
       let mut children = Vec::new();
       for (c_n,c_k,c_t,c_b) in scope.children.iter() {
          let c_t = c_t.substitute(subs);
          let c_b = if let Some(c_b) = c_b {
-            let c_b = self.make_template(parent, *c_b, subs);
+            let c_b = self.make_template(parent, *c_b, subs)?;
             self.untyped(c_b);
             Some(c_b)
          } else { None };
@@ -621,9 +1150,9 @@ impl TLC {
          parent: parent.clone(),
          children: children,
       });
-      sc
+      Ok(sc)
    }
-   pub fn make_template(&mut self, scope: &Option<ScopeId>, b: TermId, subs: &HashMap<Type,Type>) -> TermId {
+   pub fn make_template(&mut self, scope: &Option<ScopeId>, b: TermId, subs: &HashMap<Type,Type>) -> Result<TermId,Error> {
       let span = self.rows[b.id].span.clone();
       let rt = match &self.rows[b.id].term.clone() {
          Term::Let(lt) => {
@@ -635,9 +1164,9 @@ impl TLC {
                }
                pars.push(ps);
             }
-            let scope = self.make_scope(scope, lt.scope,subs);
+            let scope = self.make_scope(scope, lt.scope,subs)?;
             let body = if let Some(b) = lt.body {
-               Some(self.make_template(&Some(scope), b, subs))
+               Some(self.make_template(&Some(scope), b, subs)?)
             } else { None };
             let lt = self.push_term(Term::Let(LetTerm {
                is_extern: lt.is_extern,
@@ -653,55 +1182,56 @@ impl TLC {
          Term::Ident(x) => { self.push_term(Term::Ident(x.clone()),&span) },
          Term::Value(x) => { self.push_term(Term::Ident(x.clone()),&span) },
          Term::App(g,x) => {
-            let g = self.make_template(scope,*g,subs);
-            let x = self.make_template(scope,*x,subs);
+            let g = self.make_template(scope,*g,subs)?;
+            let x = self.make_template(scope,*x,subs)?;
             self.push_term(Term::App(g,x),&span)
          },
          Term::Arrow(sc,p,rt,b) => {
-            let sc = self.make_scope(scope,*sc,subs);
-            let p = self.make_template(&None,*p,subs);
-            let b = self.make_template(&Some(sc),*b,subs);
+            let sc = self.make_scope(scope,*sc,subs)?;
+            let p = self.make_template(&None,*p,subs)?;
+            let b = self.make_template(&Some(sc),*b,subs)?;
             let rt = if let Some(rt) = rt {
                Some(rt.substitute(subs))
             } else { None };
             self.push_term(Term::Arrow(sc,p,rt,b),&span)
          },
          Term::Block(sid,es) => {
-            let sid = self.make_scope(scope,*sid,subs);
+            let sid = self.make_scope(scope,*sid,subs)?;
             let mut nes = Vec::new();
             for e in es.iter() {
-               nes.push(self.make_template(&Some(sid),*e,subs));
+               nes.push(self.make_template(&Some(sid),*e,subs)?);
             }
             self.push_term(Term::Block(sid,nes),&span)
          },
          Term::Tuple(es) => {
             let mut nes = Vec::new();
             for e in es.iter() {
-               nes.push(self.make_template(scope,*e,subs));
+               nes.push(self.make_template(scope,*e,subs)?);
             }
             self.push_term(Term::Tuple(nes),&span)
          },
          Term::Ascript(x,tt) => {
-            let x = self.make_template(scope,*x,subs);
+            let x = self.make_template(scope,*x,subs)?;
             let tt = tt.substitute(subs);
             self.push_term(Term::Ascript(x,tt),&span)
          },
          Term::Constructor(cn,fts) => {
             let mut nfts = Vec::new();
             for (f,ft) in fts.iter() {
-               let ft = self.make_template(scope,*ft,subs);
+               let ft = self.make_template(scope,*ft,subs)?;
                nfts.push((f.clone(),ft));
             }
             self.push_term(Term::Constructor(cn.clone(),nfts),&span)
          },
          Term::As(t,tt) => {
-            let t = self.make_template(scope,*t,subs);
+            let t = self.make_template(scope,*t,subs)?;
             let tt = tt.substitute(subs);
             self.push_term(Term::As(t,tt),&span)
          },
-         _ => unimplemented!("Make template: {}", self.print_term(b))
+         _ => return Err(Error::new("Type Error",
+            format!("term can not be specialized in a polymorphic binding: {}", self.print_term(b)), span)),
       };
-      rt
+      Ok(rt)
    }
    pub fn visit(&mut self, scope: &Option<ScopeId>, vt: &Option<TermId>, tt: &Type) -> Result<(),Error> {
       if let Some(vt) = vt {
@@ -721,8 +1251,11 @@ impl TLC {
                }
                hs
             };
-            assert!(!merge.is_bottom() || lt.is_extern);
-            let template = self.make_template(scope, *vt, &subs);
+            if merge.is_bottom() && !lt.is_extern {
+               return Err(Error::new("Type Error",
+                  format!("binding {} : {:?} can not be specialized to {:?}", lt.name, bt, tt), self.rows[vt.id].span.clone()));
+            }
+            let template = self.make_template(scope, *vt, &subs)?;
             self.poly_bindings.insert((lt.name.clone(),tt.clone()),template);
             self.typeck(&Some(lt.scope), template, None)?;
             self.untyped(*vt);
@@ -744,7 +1277,7 @@ impl TLC {
                candidates.push(tt.clone());
                if let Type::Arrow(_tp,_tb) = &tt {
                if let Some(it) = implied {
-                  let rt = Type::implies(self, &it, &tt);
+                  let rt = Type::implies(self, it, tt);
                   if rt.is_bottom() { continue; }
                   matches.push(rt.clone());
                   self.visit(scope, vt, &rt)?;
@@ -763,23 +1296,18 @@ impl TLC {
             Ok(rt)
          } else if candidates.len() > 0 {
             let implied = implied.clone().unwrap_or(Type::Any);
-         Err(Error {
-            kind: "Type Error".to_string(),
-            rule: format!("variable {}: {:?} did not match any candidate {}",
+         Err(Error::new("Type Error",
+            format!("variable {}: {:?} did not match any candidate {}",
                      v,
                      &implied,
                      candidates.iter().map(|t|format!("{:?}",t))
                                .collect::<Vec<String>>().join(" | "),
                   ),
-            span: span.clone(),
-         }) } else {
+            span.clone())) } else {
             self.typeof_var(&sc.parent.clone(), v, implied, span)
          }
-      } else { Err(Error {
-         kind: "Type Error".to_string(),
-         rule: format!("variable not found in scope: {} : {:?}", v, implied.clone().unwrap_or(Type::Any) ),
-         span: span.clone(),
-      }) }
+      } else { Err(Error::new("Type Error",
+         format!("variable not found in scope: {} : {:?}", v, implied.clone().unwrap_or(Type::Any) ), span.clone())) }
    }
    pub fn untyped(&mut self, t: TermId) {
       self.rows[t.id].untyped = true;
@@ -814,11 +1342,75 @@ impl TLC {
          Term::As(t,_tt) => {
             self.untyped(t);
          },
-         _ => panic!("TODO untype term: {}", self.print_term(t))
+         Term::RuleApplication(t,_n) => {
+            self.untyped(t);
+         },
+         Term::Match(dv,lrs) => {
+            self.untyped(dv);
+            for (_sc,l,r) in lrs.iter() {
+               self.untyped(*l);
+               self.untyped(*r);
+            }
+         },
+         Term::Project(_c) => (),
+         Term::Fail => (),
+         Term::Literal(_ps) => (),
+      }
+   }
+   /// Cast each factor of a type into a normal type of the same kind.
+   pub fn cast_normal(&mut self, l_only: &Type, span: &Span) -> Result<Type,Error> {
+      let k = self.kind(l_only).first();
+      let overflow = || Error::new("Type Error",
+         format!("exponent overflows in conversion of {:?} into a normal type", l_only), span.clone());
+      let mut es = BTreeMap::new();
+      for (ft,fe) in l_only.exponents().ok_or_else(overflow)?.into_iter() {
+         let nt = if self.is_normal(&ft) { ft.clone() }
+         else { self.cast_chain(&k, &ft, &|t| self.is_normal(t)).ok_or_else(|| Error::new("Type Error",
+            format!("no conversion from {:?} into a normal type of kind {:?}", ft, k), span.clone()))? };
+         for (nt,ne) in nt.exponents().ok_or_else(overflow)?.into_iter() {
+            let e = es.entry(nt).or_insert(0i64);
+            *e = ne.checked_mul(fe).and_then(|ne| e.checked_add(ne)).ok_or_else(overflow)?;
+         }
       }
+      es.retain(|_,e| *e!=0);
+      Ok(Type::from_exponents(&es))
    }
-   pub fn cast_normal(&mut self, _l_only: &Type, _span: &Span) -> Result<Type,Error> {
-      unimplemented!("Reimplement cast normalization")
+   /// The nesting depth of a type's parameters, which bounds the types that a cast will search through.
+   fn cast_depth(tt: &Type) -> usize {
+      match tt {
+         Type::Named(_tn,ts) => 1 + ts.iter().map(TLC::cast_depth).max().unwrap_or(0),
+         _ => 1,
+      }
+   }
+   /// Search the forall rules of a kind for the shortest chain of conversions from a type into an accepted type.
+   /// Each rule with one parameter, such as forall u:Feet::Unit. Metre = 0.3048*u :: Unit, converts its parameter into its inference.
+   /// Returns the accepted type at the end of the chain.
+   fn cast_chain(&self, k: &Kind, from: &Type, accept: &dyn Fn(&Type) -> bool) -> Option<Type> {
+      let rules = self.rules.iter().filter_map(|r| match r {
+         TypeRule::Forall(fr) if fr.parameters.len()==1 && fr.kind.has(k) =>
+            Some((fr.parameters[0].1.clone(), fr.inference.clone())),
+         _ => None,
+      }).collect::<Vec<(Type,Type)>>();
+      //prefixes can be applied indefinitely, so types are not searched deeper than they are written
+      let max_depth = std::cmp::max(TLC::cast_depth(from), 2);
+      let mut visited = vec![from.clone()];
+      let mut frontier = vec![from.clone()];
+      while !frontier.is_empty() {
+         let mut next = Vec::new();
+         for ft in frontier.iter() {
+         for (rf,rt) in rules.iter() {
+            let mut subs = Vec::new();
+            if Type::subs_implies(self, &mut subs, ft, rf).is_bottom() { continue; }
+            let Ok(msubs) = Type::compile_subs(&subs) else { continue };
+            let nt = rt.substitute(&msubs).normalize();
+            if accept(&nt) { return Some(nt); }
+            if TLC::cast_depth(&nt) > max_depth || visited.contains(&nt) { continue; }
+            visited.push(nt.clone());
+            next.push(nt);
+         }}
+         frontier = next;
+      }
+      None
    }
    pub fn implies(&mut self, lt: &Type, rt: &Type, span: &Span) -> Result<Type,Error> {
       self.arrow_implies(lt,rt,span,InArrow::No)
@@ -829,17 +1421,34 @@ impl TLC {
       let nt = Type::arrow_implies(self, &mut lt, &mut rt, inarrow);
       match nt {
          Type::And(nts) if nts.len()==0 => {
-            Err(Error {
-               kind: "Type Error".to_string(),
-               rule: format!("failed unification {:?} (x) {:?}", &lt, &rt),
-               span: span.clone(),
-            })
+            Err(Error::new("Type Error", format!("failed unification {:?} (x) {:?}", &lt, &rt), span.clone()))
          },
          _ => { Ok(nt) }
       }
    }
-   pub fn cast_into_kind(&mut self, mut _l_only: Type, _into: &Type, _span: &Span) -> Result<Type,Error> {
-      unimplemented!("Reimplement cast into kind")
+   /// Cast a type into another of the same normal kind, converting each factor of a Product or Ratio separately.
+   /// Each factor takes the shortest chain of conversions into a factor of the target type.
+   pub fn cast_into_kind(&mut self, l_only: Type, into: &Type, span: &Span) -> Result<Type,Error> {
+      let no_conversion = || Error::new("Type Error", format!("no conversion from {:?} into {:?}", l_only, into), span.clone());
+      let overflow = || Error::new("Type Error",
+         format!("exponent overflows in conversion from {:?} into {:?}", l_only, into), span.clone());
+      let k = self.kind(into).first();
+      //derived units are cast through the units that define them
+      let mut expanded = false;
+      let goals = self.unit_exponents(into, &mut expanded, 0).ok_or_else(overflow)?.into_keys().collect::<Vec<Type>>();
+      let mut es = BTreeMap::new();
+      for (ft,fe) in self.unit_exponents(&l_only, &mut expanded, 0).ok_or_else(overflow)?.into_iter() {
+         let nt = if goals.contains(&ft) { ft.clone() }
+         else { self.cast_chain(&k, &ft, &|t| goals.contains(t)).ok_or_else(no_conversion)? };
+         let e = es.entry(nt).or_insert(0i64);
+         *e = e.checked_add(fe).ok_or_else(overflow)?;
+      }
+      es.retain(|_,e| *e!=0);
+      let cast = Type::from_exponents(&es);
+      if Type::implies(self, &cast, into).is_bottom() {
+         return Err(no_conversion());
+      }
+      Ok(cast)
    }
 
    pub fn are_terms_equal(&self, lt: TermId, rt: TermId) -> bool {
@@ -853,6 +1462,9 @@ impl TLC {
       }
    }
 
+   fn unmatched_hint(&self, hint: &str, form: &str, lhs: TermId) -> Error {
+      Error::new("Type Error", format!("hint {} can not yet match {} in term: {}", hint, form, self.print_term(lhs)), self.rows[lhs.id].span.clone())
+   }
    pub fn typeck_hint(&mut self, bound: &mut HashMap<String,TermId>, scope: &Option<ScopeId>, hint: &String, lhs: TermId, rhs: TermId) -> Result<(),Error> {
       match ( self.rows[lhs.id].term.clone(), self.rows[rhs.id].term.clone() ) {
          (Term::Ident(ln), Term::Ident(rn)) if ln==rn => {
@@ -864,11 +1476,8 @@ impl TLC {
             self.implies(&realized, &required, &self.rows[lhs.id].span.clone())?;
             if let Some(prevx) = bound.get(&x) {
                if !Term::equals(self, lhs, *prevx) {
-                  return Err(Error {
-                     kind: "Type Error".to_string(),
-                     rule: format!("hint parameter does not structurally match in term: {}", hint),
-                     span: self.rows[lhs.id].span.clone(),
-                  })
+                  return Err(Error::new("Type Error",
+                     format!("hint parameter does not structurally match in term: {}", hint), self.rows[lhs.id].span.clone()))
                }
             } else {
                bound.insert(x.clone(), lhs);
@@ -876,7 +1485,7 @@ impl TLC {
             Ok(())
          },
          (Term::Block(_lsid,_les),Term::Block(_rsid,_res)) => {
-            unimplemented!("TODO: typeck_hint Term::Block")
+            Err(self.unmatched_hint(hint, "a block", lhs))
          },
          (Term::Tuple(les),Term::Tuple(res)) if les.len()==res.len() => {
             for (lx,rx) in std::iter::zip(les,res) {
@@ -885,19 +1494,19 @@ impl TLC {
             Ok(())
          },
          (Term::Let(_llt),Term::Let(_rlt)) => {
-            unimplemented!("TODO: typeck_hint Term::Let")
+            Err(self.unmatched_hint(hint, "a let binding", lhs))
          },
-	 (Term::Ascript(_lx,_ltt),Term::Ascript(_rx,_rtt)) => {
-            unimplemented!("TODO: typeck_hint Term::Ascript")
+         (Term::Ascript(_lx,_ltt),Term::Ascript(_rx,_rtt)) => {
+            Err(self.unmatched_hint(hint, "an ascription", lhs))
          },
-	 (Term::As(_lx,_ltt),Term::As(_rx,_rtt)) => {
-            unimplemented!("TODO: typeck_hint Term::As")
+         (Term::As(_lx,_ltt),Term::As(_rx,_rtt)) => {
+            Err(self.unmatched_hint(hint, "a cast", lhs))
          },
-	 (Term::RuleApplication(_lx,_ltt),Term::RuleApplication(_rx,_rtt)) => {
-            unimplemented!("TODO: typeck_hint Term::RuleApplication")
+         (Term::RuleApplication(_lx,_ltt),Term::RuleApplication(_rx,_rtt)) => {
+            Err(self.unmatched_hint(hint, "a rule application", lhs))
          },
-	 (Term::Arrow(_lsc,_llhs,_lrt,_lrhs),Term::Arrow(_rsc,_rlhs,_rrt,_rrhs)) => {
-            unimplemented!("TODO: typeck_hint Term::Arrow")
+         (Term::Arrow(_lsc,_llhs,_lrt,_lrhs),Term::Arrow(_rsc,_rlhs,_rrt,_rrhs)) => {
+            Err(self.unmatched_hint(hint, "a function", lhs))
          },
 	 (Term::App(lg,lx),Term::App(rg,rx)) => {
             self.typeck_hint(bound, scope, hint, lg, rg)?;
@@ -905,15 +1514,12 @@ impl TLC {
             Ok(())
          },
          (Term::Constructor(_ln,_lkvs),Term::Constructor(_rn,_rkvs)) => {
-            unimplemented!("TODO: typeck_hint Term::Constructor")
+            Err(self.unmatched_hint(hint, "a constructor", lhs))
          },
 	 (Term::Value(lx),Term::Value(rx)) if lx == rx => { Ok(()) },
          (_,_) => {
-            return Err(Error {
-               kind: "Type Error".to_string(),
-               rule: format!("hint does not structurally match term: {}", hint),
-               span: self.rows[lhs.id].span.clone(),
-            })
+            return Err(Error::new("Type Error",
+               format!("hint does not structurally match term: {}", hint), self.rows[lhs.id].span.clone()))
          }
       }
    }
@@ -922,6 +1528,22 @@ impl TLC {
       match (self.rows[t.id].term.clone(),tt) {
          (Term::Value(_),_) => {
          },
+         (Term::Literal(ps),_) => {
+            //textual parts are bound to substrings, so their type is not known until runtime
+            for (p,b) in ps.iter() {
+               let bound = match (p,b) {
+                  (_,Some(b)) => b,
+                  (LiteralPattern::Variable(v),None) if v != "_" => v,
+                  _ => continue,
+               };
+               self.scopes[scope.id].children.push((
+                  bound.clone(),
+                  HashMap::new(),
+                  Type::Any,
+                  None
+               ));
+            }
+         },
          (Term::Ident(tn),_) => {
             if tn != "_" {
                self.scopes[scope.id].children.push((
@@ -935,11 +1557,8 @@ impl TLC {
          (Term::Constructor(cname,_kvs),_) => {
             let ct = if let Some((ct,_tpars,_tkvs)) = self.constructors.get(&cname) {
                ct.clone()
-            } else { return Err(Error {
-               kind: "Type Error".to_string(),
-               rule: format!("type constructor, none found for: {}", self.print_term(t)),
-               span: self.rows[t.id].span.clone(),
-            }) };
+            } else { return Err(Error::new("Type Error",
+               format!("type constructor, none found for: {}", self.print_term(t)), self.rows[t.id].span.clone())) };
             self.implies(&ct, tt, &span)?;
          },
          (Term::Tuple(vts),Type::Tuple(tts)) if vts.len()==tts.len() => {
@@ -1004,37 +1623,346 @@ impl TLC {
                   }
                }
             }}}}}}
-            return Err(Error {
-               kind: "Type Error".to_string(),
-               rule: format!("destructure app rejected {} : {:?}", self.print_term(t), tt),
-               span: self.rows[t.id].span.clone(),
-            });
+            return Err(Error::new("Type Error",
+               format!("destructure app rejected {} : {:?}", self.print_term(t), tt), self.rows[t.id].span.clone()));
          },
          _ => {
-            return Err(Error {
-               kind: "Type Error".to_string(),
-               rule: format!("destructure rejected {} : {:?}", self.print_term(t), tt),
-               span: self.rows[t.id].span.clone(),
-            });
+            return Err(Error::new("Type Error",
+               format!("destructure rejected {} : {:?}", self.print_term(t), tt), self.rows[t.id].span.clone()));
          },
       };
       Ok(())
    }
+   pub fn precondition(&mut self, tt: &Type, v: TermId) -> Result<(),Error> {
+      //a value with a known constant must satisfy the invariants of every type that it enters
+      let c = if let Some(c) = self.rows[v.id].typ.constant() { c } else { return Ok(()) };
+      for nt in self.extend_implied(tt).all_named().iter() {
+         let tn = if let Type::Named(tn,_) = nt { tn } else { continue };
+         let tr = if let Some(ti) = self.typedef_index.get(tn) {
+            if let TypeRule::Typedef(tr) = &self.rules[*ti] { tr.clone() } else { continue }
+         } else { continue };
+         for inv in tr.invariants.iter() {
+            //an invariant that can not be evaluated on the value is reported at the value
+            let holds = self.invariant_holds(&tr, inv, &c).map_err(|e| Error::new(&e.kind, e.rule.clone(), self.rows[v.id].span.clone())
+                                                                         .with_label(&e.span, &format!("invariant of {}", tn)))?;
+            if !holds { return Err(Error::new("Precondition",
+               format!("value {:?} does not satisfy invariant of {}: {} | {:?}", c, tn, self.print_term(inv.prop), inv.algs),
+               self.rows[v.id].span.clone())) }
+         }
+      }
+      Ok(())
+   }
+   pub fn invariant_check(&mut self, tr: &TypedefRule, inv: &Invariant) -> Result<Rc<InvariantCheck>,Error> {
+      if let Some(ic) = self.invariant_checks.get(&inv.prop.id) {
+         return Ok(ic.clone());
+      }
+      let mut children = vec![(
+         "self".to_string(), HashMap::new(), Type::Named(tr.name.clone(),Vec::new()), None
+      )];
+      let mut quants = Vec::new();
+      for (i,it,_k) in inv.itks.iter() {
+         if let Some(i) = i {
+            children.push((i.clone(), HashMap::new(), it.clone().unwrap_or(Type::Any), None));
+            quants.push(i.clone());
+         }
+      }
+      let sc = self.push_scope(Scope {
+         parent: Some(inv.scope),
+         children,
+      });
+      self.typeck(&Some(sc), inv.prop, None)?;
+      let reduction = Term::compile_reduction(self, &Some(sc), inv.prop)?;
+      //a disjunct that can not be compiled can not be refuted
+      let disjuncts = self.disjuncts(inv.prop).into_iter()
+                          .map(|d| (d, Term::compile_reduction(self, &Some(sc), d).ok())).collect::<Vec<(TermId,Option<Reduction>)>>();
+      let divisors = quants.iter().filter(|q| disjuncts.iter().any(|(d,_)| self.is_nondivisor(*d, q))).cloned().collect();
+      let ic = Rc::new(InvariantCheck { scope: sc, quants, divisors, reduction, disjuncts });
+      self.invariant_checks.insert(inv.prop.id, ic.clone());
+      Ok(ic)
+   }
+   pub fn invariant_holds(&mut self, tr: &TypedefRule, inv: &Invariant, c: &Constant) -> Result<bool,Error> {
+      let ic = self.invariant_check(tr, inv)?;
+
+      //quantified variables are enumerated over 1..=|self|
+      //a variable that must divide self is enumerated over the divisors of self, which come in pairs around its square root
+      //invariants that can't be enumerated are assumed to hold, unless self is too large to enumerate
+      let mut domain = vec![vec![("self".to_string(),c.clone())]];
+      if !ic.quants.is_empty() {
+         let n = if let Constant::Literal(n) = c { str::parse::<i64>(n).ok() } else { None };
+         let n = if let Some(n) = n { n.abs() } else { return Ok(true) };
+         for q in ic.quants.iter() {
+            let qis = if ic.divisors.contains(q) {
+               if n > 10000*10000 { return Err(self.undecidable(tr, inv, c)); }
+               let mut qis = Vec::new();
+               let mut qi = 1;
+               while qi*qi <= n {
+                  if n % qi == 0 {
+                     qis.push(qi);
+                     if qi*qi != n { qis.push(n/qi); }
+                  }
+                  qi += 1;
+               }
+               qis
+            } else {
+               if n > 10000 { return Err(self.undecidable(tr, inv, c)); }
+               (1..=n).collect::<Vec<i64>>()
+            };
+            let mut qdomain = Vec::new();
+            for bs in domain.iter() {
+            for qi in qis.iter() {
+               let mut bs = bs.clone();
+               bs.push((q.clone(),Constant::Literal(format!("{}",qi))));
+               qdomain.push(bs);
+            }}
+            domain = qdomain;
+         }
+      }
+      let algs = inv.algs.truthy();
+      for bs in domain.iter() {
+         let r = ic.reduction.reduce_with(bs)?;
+         if r.truthy() != algs { return Ok(false) }
+      }
+      Ok(true)
+   }
+   fn undecidable(&self, tr: &TypedefRule, inv: &Invariant, c: &Constant) -> Error {
+      Error::new("Precondition",
+         format!("undecidable invariant of {}: {} can not be enumerated for value {:?}", tr.name, self.print_term(inv.prop), c),
+         self.rows[inv.prop.id].span.clone())
+   }
+   /// True if the term is self % q != 0, which only fails when q divides self.
+   fn is_nondivisor(&self, t: TermId, q: &str) -> bool {
+      let binop = |t: TermId, op: &str| match &self.rows[t.id].term {
+         Term::App(g,x) => match (&self.rows[g.id].term, &self.rows[x.id].term) {
+            (Term::Ident(gn),Term::Tuple(xs)) if gn==op && xs.len()==2 => Some((xs[0],xs[1])),
+            _ => None,
+         },
+         _ => None,
+      };
+      let Some((m,z)) = binop(t, "!=") else { return false };
+      let Some((l,r)) = binop(m, "%") else { return false };
+      matches!(&self.rows[z.id].term, Term::Value(v) if v=="0")
+      && matches!(&self.rows[l.id].term, Term::Ident(v) if v=="self")
+      && matches!(&self.rows[r.id].term, Term::Ident(v) if v==q)
+   }
+   fn typedef(&self, tn: &str) -> Option<TypedefRule> {
+      match self.typedef_index.get(tn).map(|ti| &self.rules[*ti]) {
+         Some(TypeRule::Typedef(tr)) => Some(tr.clone()),
+         _ => None,
+      }
+   }
+   /// True if the value is accepted by the regexes or constructors of the type and satisfies its invariants.
+   pub fn inhabits(&mut self, tt: &Type, c: &Constant) -> Result<bool,Error> {
+      let Constant::Literal(v) = c else { return Ok(true) };
+      //a constructor inhabits the types that its typedef implies
+      if let Some((ct,_,_)) = self.constructors.get(v).cloned() {
+         return Ok(!Type::implies(self, &ct, tt).is_bottom());
+      }
+      let trs = self.extend_implied(tt).all_named().iter().filter_map(|nt| match nt {
+         Type::Named(tn,_) => self.typedef(tn),
+         _ => None,
+      }).collect::<Vec<TypedefRule>>();
+      for tr in trs.iter() {
+         let nt = Type::Named(tr.name.clone(),Vec::new());
+         let res = self.regexes.iter().filter(|(pat,_)| pat==&nt).map(|(_,re)| re.clone()).collect::<Vec<Rc<Regex>>>();
+         if !res.is_empty() && !res.iter().any(|re| re.is_match(v)) { return Ok(false); }
+         for inv in tr.invariants.iter() {
+            if !self.invariant_holds(tr, inv, c)? { return Ok(false); }
+         }
+      }
+      Ok(true)
+   }
+   /// Candidate values of a type, from the nullary constructors of its typedefs or else from the language of its regexes.
+   pub fn generate(&mut self, tt: &Type, limit: usize) -> Result<Vec<Constant>,Error> {
+      //checking invariants adds scopes and rows, which are dropped afterwards
+      let snapshot = self.snapshot();
+      let vs = self.generate_values(tt, limit);
+      self.restore(snapshot);
+      vs
+   }
+   fn generate_values(&mut self, tt: &Type, limit: usize) -> Result<Vec<Constant>,Error> {
+      let mut cs = Vec::new();
+      for nt in tt.all_named().iter() {
+         let Type::Named(tn,_) = nt else { continue };
+         let Some(tr) = self.typedef(tn) else { continue };
+         for td in tr.definition.iter() {
+            if let TypedefBranch::Constructor(cn,kts) = td {
+               if kts.is_empty() { cs.push(Constant::Literal(cn.clone())); }
+            }
+         }
+      }
+      if cs.is_empty() {
+         if let Some(lang) = self.language_of_type(&self.extend_implied(tt)) {
+            cs = lang.enumerate(limit).into_iter().map(Constant::Literal).collect();
+         }
+      }
+      let mut vs = Vec::new();
+      for c in cs.into_iter() {
+         if self.inhabits(tt, &c)? { vs.push(c); }
+      }
+      Ok(vs)
+   }
+   /// Search for values of the quantified parameters of a forall rule that make its term leave the inferred type.
+   /// Rules without parameters or without a term can not be falsified.
+   pub fn falsify(&mut self, rule: &ForallRule) -> Result<Option<Counterexample>,Error> {
+      //the search checks and reduces terms in this context, and what it adds is dropped afterwards
+      let snapshot = self.snapshot();
+      let ce = self.search_counterexample(rule);
+      self.restore(snapshot);
+      ce
+   }
+   fn search_counterexample(&mut self, rule: &ForallRule) -> Result<Option<Counterexample>,Error> {
+      let Some(rhs) = rule.rhs else { return Ok(None) };
+      if rule.parameters.is_empty() { return Ok(None); }
+      self.typeck(&Some(rule.scope), rhs, None)?;
+      //each binding is sized by the sum of the positions of its values, which are generated smallest first
+      let mut domain = vec![(Vec::new(),0)];
+      for (pn,pt,_pk) in rule.parameters.iter() {
+         let vs = self.generate(pt, 32)?;
+         let mut pdomain = Vec::new();
+         for (bs,size) in domain.iter() {
+         for (vi,v) in vs.iter().enumerate() {
+            let mut bs: Vec<(String,Constant)> = bs.clone();
+            bs.push((pn.clone(),v.clone()));
+            pdomain.push((bs,size+vi));
+         }}
+         //the search is bounded, so larger domains are only checked on their smallest values
+         pdomain.sort_by_key(|(_,size)| *size);
+         pdomain.truncate(256);
+         domain = pdomain;
+      }
+      for (bs,_) in domain.into_iter() {
+         let value = Term::reduce_with(self, &Some(rule.scope), &bs, rhs)?;
+         if !self.inhabits(&rule.inference, &value)? {
+            return Ok(Some(Counterexample { bindings: bs, value }));
+         }
+      }
+      Ok(None)
+   }
+   pub fn postcondition(&mut self, t: TermId) -> Result<Option<Constant>,Error> {
+      //an application over values of an invariant-carrying type may be entailed by the invariant
+      //invariant props are untyped and are not themselves subject to postconditions
+      if self.rows[t.id].untyped || self.rows[t.id].typ.constant().is_some() { return Ok(None) }
+      let x = if let Term::App(_g,x) = &self.rows[t.id].term { *x } else { return Ok(None) };
+      let mut trs: Vec<TypedefRule> = Vec::new();
+      for a in self.term_leaves(x).iter() {
+         for nt in self.extend_implied(&self.rows[a.id].typ).all_named().iter() {
+            let tn = if let Type::Named(tn,_) = nt { tn } else { continue };
+            if trs.iter().any(|tr| &tr.name==tn) { continue }
+            if let Some(ti) = self.typedef_index.get(tn) {
+            if let TypeRule::Typedef(tr) = &self.rules[*ti] {
+            if !tr.invariants.is_empty() {
+               trs.push(tr.clone());
+            }}}
+         }
+      }
+      for tr in trs.iter() {
+      for inv in tr.invariants.iter() {
+         let ic = self.invariant_check(tr, inv)?;
+         'disjunct: for (di,(d,_)) in ic.disjuncts.iter().enumerate() {
+            let mut bound = HashMap::new();
+            if !self.match_invariant(&tr.name, &ic.quants, &mut bound, t, *d) { continue; }
+            let mut bindings = Vec::new();
+            for (q,qt) in bound.iter() {
+               if let Some(qc) = self.rows[qt.id].typ.constant() {
+                  bindings.push((q.clone(),qc));
+               } else if q!="self" { continue 'disjunct; }
+            }
+
+            //every other disjunct must be refuted for this disjunct to be entailed
+            //a disjunct that mentions an unknown self can not be refuted
+            for (oi,(_,o)) in ic.disjuncts.iter().enumerate() {
+               if oi==di { continue; }
+               match o.as_ref().map(|o| o.reduce_with(&bindings)) {
+                  Some(Ok(r)) if r.truthy() == Constant::Literal("0".to_string()) => (),
+                  _ => { continue 'disjunct; },
+               }
+            }
+            return Ok(Some(inv.algs.clone()));
+         }
+      }}
+      Ok(None)
+   }
+   pub fn disjuncts(&self, t: TermId) -> Vec<TermId> {
+      if let Term::App(g,x) = &self.rows[t.id].term {
+      if let Term::Ident(gn) = &self.rows[g.id].term {
+      if gn=="||" {
+      if let Term::Tuple(xs) = &self.rows[x.id].term {
+      if xs.len()==2 {
+         let mut ds = self.disjuncts(xs[0]);
+         ds.append(&mut self.disjuncts(xs[1]));
+         return ds;
+      }}}}}
+      vec![t]
+   }
+   pub fn term_leaves(&self, t: TermId) -> Vec<TermId> {
+      match &self.rows[t.id].term {
+         Term::App(_g,x) => self.term_leaves(*x),
+         Term::Tuple(ts) => ts.iter().flat_map(|ct| self.term_leaves(*ct)).collect::<Vec<TermId>>(),
+         _ => vec![t],
+      }
+   }
+   pub fn match_invariant(&self, tn: &str, quants: &[String], bound: &mut HashMap<String,TermId>, t: TermId, p: TermId) -> bool {
+      match (&self.rows[t.id].term,&self.rows[p.id].term) {
+         (_,Term::Ident(pn)) if pn=="self" || quants.contains(pn) => {
+            if pn=="self" && !self.extend_implied(&self.rows[t.id].typ).all_named().iter()
+                                  .any(|nt| matches!(nt, Type::Named(n,_) if n==tn)) {
+               return false;
+            }
+            if let Some(bt) = bound.get(pn) {
+               return self.are_terms_equal(*bt, t);
+            }
+            bound.insert(pn.clone(), t);
+            true
+         },
+         (Term::Ident(lv),Term::Ident(rv)) => lv==rv,
+         (Term::Value(lv),Term::Value(rv)) => lv==rv,
+         (Term::App(lg,lx),Term::App(rg,rx)) => {
+            self.match_invariant(tn, quants, bound, *lg, *rg) &&
+            self.match_invariant(tn, quants, bound, *lx, *rx)
+         },
+         (Term::Tuple(ls),Term::Tuple(rs)) => {
+            ls.len()==rs.len() &&
+            std::iter::zip(ls,rs).all(|(lc,rc)| self.match_invariant(tn, quants, bound, *lc, *rc))
+         },
+         _ => false,
+      }
+   }
+   pub fn loop_construct(&self, g: TermId, x: TermId) -> Option<(bool,TermId,TermId)> {
+      //while(cond){body} = while((cond,body)) and loop{body}while(cond) = loop((body,cond))
+      //the returned flag is true for a while loop, which may never run its body
+      if let (Term::Ident(gn),Term::Tuple(xs)) = (&self.rows[g.id].term,&self.rows[x.id].term) {
+      if xs.len()==2 {
+         if gn=="while" { return Some((true,xs[0],xs[1])); }
+         if gn=="loop" { return Some((false,xs[1],xs[0])); }
+      }}
+      None
+   }
    pub fn typeck(&mut self, scope: &Option<ScopeId>, t: TermId, implied: Option<Type>) -> Result<(),Error> {
       let implied = implied.map(|tt|tt.normalize());
       //TODO: remove clone here because it is bloating the memory footprint
       match self.rows[t.id].term.clone() {
-         Term::Project(_v) => panic!("Projection Constants cannot be Values at {:?}", &self.rows[t.id].span),
+         Term::Project(_v) => { return Err(Error::new("Type Error",
+            format!("Projection Constants cannot be Values: {}", self.print_term(t)), self.rows[t.id].span.clone())) },
          Term::Fail => {
             self.rows[t.id].typ = implied.clone().unwrap_or(Type::Any);
          },
+         Term::Literal(ps) => {
+            //a literal expression concatenates text, so its parts must be fixed text or variables in scope
+            let span = self.rows[t.id].span.clone();
+            for (p,b) in ps.iter() {
+               match (p,b) {
+                  (LiteralPattern::Variable(v),None) => { self.typeof_var(scope, v, &None, &span)?; },
+                  (LiteralPattern::Char(_),None) | (LiteralPattern::String(_),None) => {},
+                  _ => return Err(Error::new("Type Error",
+                     format!("literal expression can only concatenate text and variables: {}", self.print_term(t)), span)),
+               }
+            }
+            self.rows[t.id].typ = implied.clone().unwrap_or(Type::Any);
+         },
          Term::Match(dv, lrs) => {
             if lrs.len()==0 {
-               return Err(Error {
-                  kind: "Type Error".to_string(),
-                  rule: format!("pattern cannot match because it has no branches"),
-                  span: self.rows[t.id].span.clone(),
-               })
+               return Err(Error::new("Type Error",
+                  format!("pattern cannot match because it has no branches"), self.rows[t.id].span.clone()))
             };
             self.typeck(scope, dv, None)?;
             let mut rts = Vec::new();
@@ -1080,6 +2008,12 @@ impl TLC {
                let bt = lt.typeof_binding();
                if !bt.is_open() {
                   self.typeck(&Some(lt.scope), *b, Some(lt.rtype.clone()))?;
+                  if !Type::rigid_implies(self, &self.rows[b.id].typ, &lt.rtype) { return Err(Error::new("Type Error",
+                     format!("body of {} does not have the declared length for every length of its parameters: {:?} (x) {:?}", lt.name, &self.rows[b.id].typ, &lt.rtype),
+                     self.rows[b.id].span.clone())) }
+                  if lt.parameters.is_empty() {
+                     self.precondition(&lt.rtype, *b)?;
+                  }
                } else {
                   self.untyped(t);
                }
@@ -1091,6 +2025,7 @@ impl TLC {
          },
          Term::Ascript(x,tt) => {
             self.typeck(scope, x, Some(tt.clone()))?;
+            self.precondition(&tt, x)?;
             self.rows[t.id].typ = self.implies(&self.rows[x.id].typ.clone(), &tt, &self.rows[t.id].span.clone())?;
          },
          Term::As(x,into) => {
@@ -1119,9 +2054,24 @@ impl TLC {
             self.rows[t.id].typ = self.typeof_var(&scope, &x, &implied, &self.rows[t.id].span.clone())?;
          },
          Term::Value(x) => {
-            let i = if let Some(ref i) = implied { i.clone() } else { self.bottom_type.clone() };
+            let mut i = if let Some(ref i) = implied { i.clone() } else { self.bottom_type.clone() };
+            //a literal that is only given a unit, such as Metre/Second, is typed by its regex and keeps the unit
+            let units = self.remove_kinded(&self.term_kind, &i);
+            if !units.is_bottom() && self.project_kinded(&self.term_kind, &i).is_bottom() {
+               i = self.bottom_type.clone();
+            }
+            //a literal is checked against the regexes of the types it is given before any regex those types imply
+            let own = self.regexes.iter().filter(|(pat,_)| match &i {
+               Type::And(its) => its.contains(pat),
+               it => it==pat,
+            }).map(|(_,re)| re.clone()).collect::<Vec<Rc<Regex>>>();
             let mut r = None;
+            if !own.is_empty() {
+               r = own.iter().find(|re| !re.is_match(&x)).unwrap_or(&own[0]).clone().into();
+               self.rows[t.id].typ = i.clone();
+            }
             for (pat,re) in self.regexes.clone().into_iter() {
+               if r.is_some() { break; }
                if i==self.bottom_type && re.is_match(&x) {
                   r = Some(re.clone());
                   self.rows[t.id].typ = pat;
@@ -1134,20 +2084,16 @@ impl TLC {
             }
             if let Some(re) = r {
                if !re.is_match(&x) {
-                  return Err(Error {
-                     kind: "Type Error".to_string(),
-                     rule: format!("type {:?} rejected the literal {}", i, x),
-                     span: self.rows[t.id].span.clone(),
-                  })
+                  return Err(Error::new("Type Error",
+                     format!("type {:?} rejected the literal {}", i, x), self.rows[t.id].span.clone()))
                }
             } else {
-               return Err(Error {
-                  kind: "Type Error".to_string(),
-                  rule: format!("type {:?} is not literal: {}", i, x),
-                  span: self.rows[t.id].span.clone(),
-               })
+               return Err(Error::new("Type Error", format!("type {:?} is not literal: {}", i, x), self.rows[t.id].span.clone()))
             }
             self.rows[t.id].typ = self.rows[t.id].typ.and( &Type::Constant(Constant::parse(self,&x).unwrap()) );
+            if i==self.bottom_type && !units.is_bottom() {
+               self.rows[t.id].typ = self.rows[t.id].typ.and(&units);
+            }
 	 },
          Term::RuleApplication(lhs,h) => {
             //borrowing self even in a .clone'd expression fails the borrow checker
@@ -1175,44 +2121,55 @@ impl TLC {
                   }
                };
                if !matched { err_msg?; }
-            } else { return Err(Error {
-               kind: "Type Error".to_string(),
-               rule: format!("hint not found in statements: {}", h),
-               span: self.rows[t.id].span.clone(),
-            }) }
+            } else { return Err(Error::new("Type Error",
+               format!("hint not found in statements: {}", h), self.rows[t.id].span.clone())) }
          },
          Term::Arrow(ref sc,p,rt,b) => {
             if let Term::Ascript(pt,ptt) = &self.rows[p.id].term.clone() {
                self.rows[pt.id].typ = ptt.clone();
                self.rows[p.id].typ = ptt.clone();
             }
-            self.typeck(&Some(*sc), b, rt)?;
+            if let Term::Tuple(pts) = &self.rows[p.id].term.clone() {
+               for pt in pts.iter() {
+               if let Term::Ascript(ptt,pttt) = &self.rows[pt.id].term.clone() {
+                  self.rows[ptt.id].typ = pttt.clone();
+                  self.rows[pt.id].typ = pttt.clone();
+               }}
+               self.rows[p.id].typ = Type::Tuple(pts.iter().map(|pt| self.rows[pt.id].typ.clone()).collect::<Vec<Type>>());
+            }
+            self.typeck(&Some(*sc), b, rt.clone())?;
             self.rows[t.id].typ = Type::Arrow(
                Box::new(self.rows[p.id].typ.clone()),
-               Box::new(self.rows[b.id].typ.clone()),
+               Box::new(rt.unwrap_or(self.rows[b.id].typ.clone())),
             );
          },
          Term::App(g,x) => {
             self.typeck(scope, x, None)?;
             if let Term::Project(Constant::Literal(cs)) = &self.rows[g.id].term {
-               let pi = str::parse::<usize>(&cs).unwrap();
+               let Ok(pi) = str::parse::<usize>(cs) else { return Err(Error::new("Type Error",
+                  format!("Projection index must be a natural number: π{}", cs), self.rows[t.id].span.clone())) };
                if let Type::Tuple(gts) = self.rows[x.id].typ.clone() {
+                  if pi>=gts.len() { return Err(Error::new("Type Error",
+                     format!("Cannot project out-of-bounds π{} from type {:?}", pi, &self.rows[x.id].typ), self.rows[t.id].span.clone())) }
                   self.rows[g.id].typ = gts[pi].clone();
                   self.rows[t.id].typ = gts[pi].clone();
                } else if let Type::HTuple(bt,Constant::Literal(blen)) = self.rows[x.id].typ.clone() {
-                  let blen = str::parse::<usize>(&blen).unwrap();
-                  if pi>=blen { return Err(Error {
-                     kind: "Type Error".to_string(),
-                     rule: format!("Cannot project out-of-bounds π{} from type {:?}", pi, &self.rows[x.id].typ),
-                     span: self.rows[t.id].span.clone(),
-                  }) }
+                  if str::parse::<usize>(&blen).map(|blen| pi>=blen).unwrap_or(false) { return Err(Error::new("Type Error",
+                     format!("Cannot project out-of-bounds π{} from type {:?}", pi, &self.rows[x.id].typ), self.rows[t.id].span.clone())) }
                   self.rows[g.id].typ = *bt.clone();
                   self.rows[t.id].typ = *bt.clone();
-               } else { return Err(Error {
-                  kind: "Type Error".to_string(),
-                  rule: format!("Cannot project π{} from type {:?}", pi, &self.rows[x.id].typ),
-                  span: self.rows[t.id].span.clone(),
-               }) }
+               } else { return Err(Error::new("Type Error",
+                  format!("Cannot project π{} from type {:?}", pi, &self.rows[x.id].typ), self.rows[t.id].span.clone())) }
+            } else if let Some((is_while,cond,body)) = self.loop_construct(g, x) {
+               //while and loop are builtin, the condition is checked and the loop is typed by its result
+               let u8_type = Type::Named("U8".to_string(),Vec::new());
+               self.implies(&self.rows[cond.id].typ.clone(), &u8_type, &self.rows[cond.id].span.clone())?;
+               let rt = if is_while { self.nil_type.clone() } else { self.rows[body.id].typ.clone() };
+               self.rows[g.id].typ = Type::Arrow(
+                  Box::new(self.rows[x.id].typ.clone()),
+                  Box::new(rt.clone()),
+               );
+               self.rows[t.id].typ = rt;
             } else {
                let grt = match &self.rows[x.id].typ {
                   Type::Tuple(ts) if ts.len()==0 => { implied.clone().unwrap_or(Type::Any) },
@@ -1222,7 +2179,29 @@ impl TLC {
                   Type::Arrow(Box::new(self.rows[x.id].typ.clone()),
                              Box::new(grt.clone()))
                ))?;
+               if let (Some(it),Type::And(gts)) = (&implied,&self.rows[g.id].typ) {
+                  //an ascribed result narrows overloaded candidates by their range
+                  let narrowed = gts.iter().filter(|gt| match gt {
+                     Type::Arrow(_gd,gr) => !Type::implies(self, gr, it).is_bottom(),
+                     _ => true,
+                  }).cloned().collect::<Vec<Type>>();
+                  if !narrowed.is_empty() && narrowed.len()<gts.len() {
+                     self.rows[g.id].typ = Type::And(narrowed).normalize();
+                  }
+               }
                self.rows[t.id].typ = self.rows[g.id].typ.range();
+               if let Some(c) = self.postcondition(t)? {
+                  self.rows[t.id].typ = self.rows[t.id].typ.and(&Type::Constant(c));
+               }
+               if let Some(ct) = self.concatenation(t) {
+                  self.rows[t.id].typ = self.rows[t.id].typ.and(&ct);
+               }
+               if let Some(st) = self.tuple_shape(scope, t)? {
+                  self.rows[t.id].typ = st;
+               }
+               if let Some(c) = self.static_application(scope, t) {
+                  self.rows[t.id].typ = self.rows[t.id].typ.and(&Type::Constant(c));
+               }
             }
          },
          Term::Constructor(cname,kvs) => {
@@ -1231,11 +2210,8 @@ impl TLC {
             }
             if let Some((ref tt,_tpars,_tkvs)) = self.constructors.get(&cname) {
                self.rows[t.id].typ = tt.clone();
-            } else { return Err(Error {
-               kind: "Type Error".to_string(),
-               rule: format!("type constructor, none found for: {}", self.print_term(t)),
-               span: self.rows[t.id].span.clone(),
-            }) }
+            } else { return Err(Error::new("Type Error",
+               format!("type constructor, none found for: {}", self.print_term(t)), self.rows[t.id].span.clone())) }
          },
       };
       if let Some(implied) = implied {
@@ -1246,40 +2222,12 @@ impl TLC {
       Ok(())
    }
    pub fn check(&mut self, globals: Option<ScopeId>, src:&str) -> Result<(),Error> {
-      let rows_l = self.rows.len();
-      let rules_l = self.rules.len();
-      let scopes_l = self.scopes.len();
-      let value_regexes_l = self.value_regexes.len();
-      let regexes_l = self.regexes.len();
-      let globals_l = if let Some(g) = globals {
-         if self.scopes.len()>0 {
-            self.scopes[g.id].children.len()
-         } else {
-            0
-         }
-      } else { 0 };
-      let type_is_normal_l = self.type_is_normal.clone();
-      let kind_is_normal_l = self.kind_is_normal.clone();
-      let typedef_index_l = self.typedef_index.clone();
-      let poly_bindings_l = self.poly_bindings.clone();
-
+      let snapshot = self.snapshot();
       let r = self.import_str(globals, src);
-
-      self.rows.truncate(rows_l);
-      self.rules.truncate(rules_l);
-      self.scopes.truncate(scopes_l);
-      self.value_regexes.truncate(value_regexes_l);
-      self.regexes.truncate(regexes_l);
-      if let Some(g) = globals {
-         if self.scopes.len()>0 {
-            self.scopes[g.id].children.truncate(globals_l);
-         }
-      };
-      self.type_is_normal = type_is_normal_l;
-      self.kind_is_normal = kind_is_normal_l;
-      self.typedef_index = typedef_index_l;
-      self.poly_bindings = poly_bindings_l;
-
+      //diagnostics are reported to the caller, so they survive the rollback
+      let diagnostics = self.diagnostics.split_off(std::cmp::min(snapshot.diagnostics, self.diagnostics.len()));
+      self.restore(snapshot);
+      self.diagnostics.extend(diagnostics);
       r?; Ok(())
    }
 }
@@ -5,8 +5,9 @@ use regex::Regex;
 use std::rc::Rc;
 use std::io::prelude::*;
 use std::fs::File;
+use serde::{Serialize,Deserialize};
 
-#[derive(Clone)]
+#[derive(Clone,Serialize,Deserialize)]
 pub struct Span {
    pub filename: Rc<String>,
    pub offset_start: usize,
@@ -40,6 +41,20 @@ pub fn span_of(ts: &mut TokenReader) -> Span {
    }
 }
 
+/// The span from start through the last token taken, such as the whole of a term.
+pub fn span_since(ts: &TokenReader, start: &Span) -> Span {
+   match &ts.last {
+      Some(l) if l.filename==start.filename && l.offset_end>=start.offset_start => Span {
+         filename: start.filename.clone(),
+         offset_start: start.offset_start,
+         offset_end: l.offset_end,
+         linecol_start: start.linecol_start,
+         linecol_end: l.linecol_end,
+      },
+      _ => start.clone(),
+   }
+}
+
 #[derive(Clone)]
 pub struct Token {
    pub symbol: Symbol,
@@ -111,6 +126,9 @@ pub enum Symbol {
    Fail,
    LiteralS(String),
    LiteralV(String,Type),
+   LiteralPattern,
+   LiteralC(char),
+   LiteralR(String),
 }
 impl std::fmt::Debug for Symbol {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -182,6 +200,9 @@ impl std::fmt::Debug for Symbol {
 
            Symbol::LiteralV(v,vt)     => write!(f, "v#{}:{:?}", v, vt),
            Symbol::LiteralS(s)        => write!(f, r#""{}""#, s),
+           Symbol::LiteralPattern     => write!(f, "literal"),
+           Symbol::LiteralC(c)        => write!(f, "'{}'", c),
+           Symbol::LiteralR(r)        => write!(f, "[{}]", r),
         }
     }
 }
@@ -208,13 +229,15 @@ pub struct TokenReader {
    values: Vec<(String,Regex)>,
    in_literal: bool,
    in_literal_expression: usize,
+   in_literal_pattern: bool,
+   last: Option<Span>, //the last token taken, where the term being parsed ends
 }
 impl TokenReader {
    pub fn peek(&mut self) -> Result<Option<Token>,Error> {
       if self.peek.is_some() {
          Ok(self.peek.clone())
       } else {
-         self.peek = self.take()?;
+         self.peek = self.take_impl()?;
          Ok(self.peek.clone())
       }
    }
@@ -284,6 +307,7 @@ impl TokenReader {
    pub fn take(&mut self) -> Result<Option<Token>,Error> {
       match self.take_impl() {
          Ok(Some(tok)) => {
+            self.last = Some(tok.span.clone());
             Ok(Some(tok))
          },
          t => t
@@ -295,10 +319,20 @@ impl TokenReader {
          self.peek = None;
          return Ok(t);
       }
-
+      let t = self.take_token()?;
+      //a literal pattern continues through its parts, so [0-9] is a character range rather than an index
+      if let Some(ref t) = t {
+         self.in_literal_pattern = t.symbol == Symbol::LiteralPattern || (self.in_literal_pattern && matches!(t.symbol,
+            Symbol::LiteralC(_) | Symbol::LiteralR(_) | Symbol::Ident(_) | Symbol::Value(_)));
+      }
+      Ok(t)
+   }
+   fn take_token(&mut self) -> Result<Option<Token>,Error> {
       let mut c = self.takec();
 
       while c > 0 {
+      //every token starts at the byte that was just taken
+      self.offset_start = self.buf_at - 1;
       match c {
          b'f' if self.peekc()==b'"' => {
             self.takec();
@@ -351,6 +385,39 @@ impl TokenReader {
             self.column += token.len();
             return Ok(Some(t));
          },
+         b'\'' => {
+            let mut token = Vec::new();
+            while self.peekc()>0 && self.peekc()!=b'\'' {
+               token.push(self.takec());
+            }
+            self.takec(); //discard closing quote
+            let span = self.span_of(token.len()+2);
+            let lit = std::str::from_utf8(&token).unwrap_or("");
+            let mut cs = lit.chars();
+            if let (Some(lc),None) = (cs.next(),cs.next()) {
+               self.column += token.len()+2;
+               return Ok(Some(Token {
+                  symbol: Symbol::LiteralC(lc),
+                  span,
+               }));
+            }
+            return Err(Error::new("Tokenization Error",
+               format!("Character literal must contain exactly one character: '{}'", lit), span));
+         },
+         b'[' if self.in_literal_pattern => {
+            let mut token = Vec::new();
+            while self.peekc()>0 && self.peekc()!=b']' {
+               token.push(self.takec());
+            }
+            self.takec(); //discard closing bracket
+            let span = self.span_of(token.len()+2);
+            self.column += token.len()+2;
+            let range = std::str::from_utf8(&token).unwrap_or("");
+            return Ok(Some(Token {
+               symbol: Symbol::LiteralR(range.to_string()),
+               span,
+            }));
+         },
          b' ' => { self.column += 1; self.offset_start += 1; c = self.takec(); },
          b'\n' => { self.column = 1; self.line += 1; self.offset_start += 1; c = self.takec(); },
          b'A'..=b'Z' => {
@@ -397,6 +464,7 @@ impl TokenReader {
                "yield" => { return Ok(Some(Token { symbol: Symbol::Yield, span: span, })); },
                "fn" => { return Ok(Some(Token { symbol: Symbol::Fn, span: span, })); },
                "fail" => { return Ok(Some(Token { symbol: Symbol::Fail, span: span, })); },
+               "literal" => { return Ok(Some(Token { symbol: Symbol::LiteralPattern, span, })); },
                _ => { return Ok(Some(Token { symbol: Symbol::Ident(ident.to_string()), span: span, })); },
             }
          },
@@ -475,10 +543,11 @@ impl TokenReader {
                      if longest_match.len() > 0 {
                         let byte_len = longest_match.as_bytes().len();
                         for _ in 1..byte_len { self.takec(); }
+                        let span = self.span_of(longest_match.len());
                         self.column += longest_match.len();
                         return Ok(Some(Token {
                            symbol: Symbol::Value(longest_match.clone()),
-                           span: self.span_of(longest_match.len()),
+                           span,
                         }))
                      }
                   }
@@ -498,6 +567,7 @@ impl TokenReader {
             }
          }
       }}
+      self.offset_start = self.buf_at;
       Ok(Some(Token {
          symbol: Symbol::EOF,
          span: self.span_of(0),
@@ -524,59 +594,40 @@ impl TokenReader {
       } else { unreachable!("peek symbol") }
    }
    pub fn error(&mut self, c: char) -> Result<Option<Token>,Error> {
-      Err(Error{
-         kind: "Tokenization Error".to_string(),
-         rule: format!("Unexpected character '{}'", c),
-         span: Span {
-            filename: self.source_name.clone(),
-            offset_start: self.offset_start,
-            offset_end: self.offset_start+1,
-            linecol_start: (self.line,self.column),
-            linecol_end: (self.line,self.column+1),
-         },
-      })
+      Err(Error::new("Tokenization Error", format!("Unexpected character '{}'", c), Span {
+         filename: self.source_name.clone(),
+         offset_start: self.offset_start,
+         offset_end: self.offset_start+1,
+         linecol_start: (self.line,self.column),
+         linecol_end: (self.line,self.column+1),
+      }))
    }
 }
 
 pub fn tokenize_file<'a>(tlc: &mut TLC, source_name: &str) -> Result<TokenReader,Error> {
-   if tlc.value_regexes.len() == 0 && source_name != "preludes/l1.tlc" {
-      tokenize_file(tlc, "preludes/l1.tlc")?;
-   }
-   if source_name == "preludes/l1.tlc" {
-      tokenize_string(tlc, &source_name, include_str!("../preludes/l1.tlc"))
-   } else if source_name == "preludes/si.tlc" {
-      tokenize_string(tlc, &source_name, include_str!("../preludes/si.tlc"))
-   } else if source_name == "preludes/algebra.tlc" {
-      tokenize_string(tlc, &source_name, include_str!("../preludes/algebra.tlc"))
+   if let Some(src) = tlc.prelude_source(source_name) {
+      tokenize_string(tlc, source_name, &src)
    } else if let Ok(mut f) = File::open(&source_name) {
       let mut line = Vec::new();
       if let Ok(_len) = f.read_to_end(&mut line) {
          tokenize_bytes(tlc, &source_name, line)
       } else {
-         Err(Error{
-            kind: "Tokenization Error".to_string(),
-            rule: format!("Could not read file: {}", source_name),
-            span: Span {
-               filename: Rc::new(source_name.to_string()),
-               offset_start: 0,
-               offset_end: 0,
-               linecol_start: (1,1),
-               linecol_end: (1,1),
-            }
-         })
-      }
-   } else {
-      Err(Error{
-         kind: "Tokenization Error".to_string(),
-         rule: format!("Could not open file: {}", source_name),
-         span: Span {
+         Err(Error::new("Tokenization Error", format!("Could not read file: {}", source_name), Span {
             filename: Rc::new(source_name.to_string()),
             offset_start: 0,
             offset_end: 0,
             linecol_start: (1,1),
             linecol_end: (1,1),
-         }
-      })
+         }))
+      }
+   } else {
+      Err(Error::new("Tokenization Error", format!("Could not open file: {}", source_name), Span {
+         filename: Rc::new(source_name.to_string()),
+         offset_start: 0,
+         offset_end: 0,
+         linecol_start: (1,1),
+         linecol_end: (1,1),
+      }))
    }
 }
 
@@ -586,6 +637,25 @@ pub fn tokenize_string(tlc: &mut TLC, source_name: &str, buf: &str) -> Result<To
 }
 
 pub fn tokenize_bytes<'a>(tlc: &mut TLC, source_name: &str, buf: Vec<u8>) -> Result<TokenReader,Error> {
+   //values are recognized by the regexes of the implicit prelude even before it is imported
+   if let Some(prelude) = tlc.prelude.clone() {
+   if tlc.value_regexes.is_empty() && source_name != prelude.as_str() {
+      tokenize_file(tlc, &prelude)?;
+   }}
+
+   //every token is read as text, so a source that is not UTF-8 is rejected where it stops being text
+   if let Err(e) = std::str::from_utf8(&buf) {
+      let at = e.valid_up_to();
+      let line = buf[..at].iter().filter(|c| **c==b'\n').count() + 1;
+      let column = at - buf[..at].iter().rposition(|c| *c==b'\n').map(|i| i+1).unwrap_or(0) + 1;
+      return Err(Error::new("Tokenization Error", format!("source is not valid UTF-8 at byte {}", at), Span {
+         filename: Rc::new(source_name.to_string()),
+         offset_start: at,
+         offset_end: at+1,
+         linecol_start: (line,column),
+         linecol_end: (line,column+1),
+      }))
+   }
 
    let mut buf_at = 0;
    while buf_at < buf.len() {
@@ -599,11 +669,19 @@ pub fn tokenize_bytes<'a>(tlc: &mut TLC, source_name: &str, buf: Vec<u8>) -> Res
             }}
             end_at += 1;
          }
-         let rs = std::str::from_utf8(&buf[buf_at+1..end_at-2]).unwrap();
+         let rs = String::from_utf8_lossy(&buf[buf_at+1..std::cmp::max(buf_at+1,end_at-2)]).to_string();
          if let Ok(r) = Regex::new(&rs) {
             tlc.value_regexes.push((rs.to_string(),r));
          } else {
-            panic!("invalid regex: {}", rs)
+            let line = buf[..buf_at].iter().filter(|c| **c==b'\n').count() + 1;
+            let column = buf_at - buf[..buf_at].iter().rposition(|c| *c==b'\n').map(|i| i+1).unwrap_or(0) + 1;
+            return Err(Error::new("Tokenization Error", format!("invalid regex: {}", rs), Span {
+               filename: Rc::new(source_name.to_string()),
+               offset_start: buf_at,
+               offset_end: end_at,
+               linecol_start: (line,column),
+               linecol_end: (line,column+end_at-buf_at),
+            }))
          }
          buf_at += rs.len() + 2; continue;
       }}
@@ -617,5 +695,7 @@ pub fn tokenize_bytes<'a>(tlc: &mut TLC, source_name: &str, buf: Vec<u8>) -> Res
       values: tlc.value_regexes.clone(),
       in_literal: false,
       in_literal_expression: 0,
+      in_literal_pattern: false,
+      last: None,
    })
 }
@@ -1,8 +1,11 @@
 
-use std::collections::HashMap;
+use std::collections::{HashMap,BTreeMap};
 use crate::constant::Constant;
 use crate::kind::Kind;
 use crate::tlc::TLC;
+use crate::token::Span;
+use crate::debug::Error;
+use serde::{Serialize,Deserialize};
 
 #[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Hash,Copy)]
 pub enum InArrow {
@@ -24,7 +27,7 @@ pub enum InArrow {
 ///Conjunctive-Normal-Form.
 ///
 ///Subtyping is implemented with And types. An implication, A + A => B, may be rewritten as just A + B.
-#[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Hash)]
+#[derive(Clone,Eq,PartialEq,Ord,PartialOrd,Hash,Serialize,Deserialize)]
 pub enum Type {
    Any,
    MaybeZero(Box<Type>),
@@ -35,30 +38,32 @@ pub enum Type {
    HTuple(Box<Type>,Constant),
    Product(Vec<Type>), //Product is order-insensitive
    Ratio(Box<Type>,Box<Type>),
+   Power(Box<Type>,i64), //a factor of a Product or Ratio raised to an exponent greater than one
    Constant(Constant),
 }
 
 impl Type {
-   pub fn datatype(&self) -> String {
+   /// The runtime representation of a type, such as U64 or Tuple.
+   pub fn datatype(&self, span: &Span) -> Result<String,Error> {
       let dts = vec!["U8","U64","I64","F32","F64","Unit","String"];
       match self {
-         Type::Tuple(_) => "Tuple".to_string(),
-         Type::HTuple(_,_) => "Tuple".to_string(),
+         Type::Tuple(_) => Ok("Tuple".to_string()),
+         Type::HTuple(_,_) => Ok("Tuple".to_string()),
          Type::Named(base,pars) if pars.len()==0 &&
                                    dts.contains(&base.as_str()) => {
-            base.clone()
+            Ok(base.clone())
          },
          Type::And(ts) => {
             for t in ts.iter() {
-            if let Type::Tuple(_) = t { return "Tuple".to_string();
-            } else if let Type::HTuple(_,_) = t { return "Tuple".to_string();
+            if let Type::Tuple(_) = t { return Ok("Tuple".to_string());
+            } else if let Type::HTuple(_,_) = t { return Ok("Tuple".to_string());
             } else if let Type::Named(base,pars) = t {
             if pars.len()==0 && dts.contains(&base.as_str()) {
-               return base.clone();
+               return Ok(base.clone());
             }}}
-            unimplemented!("Type::datatype({:?})", self)
+            Err(Error::new("Type Error", format!("type has no runtime representation: {:?}", self), span.clone()))
          },
-         _ => unimplemented!("Type::datatype({:?})", self)
+         _ => Err(Error::new("Type Error", format!("type has no runtime representation: {:?}", self), span.clone()))
       }
    }
    pub fn project_ratio(&self) -> (Vec<Type>,Vec<Type>) {
@@ -100,6 +105,7 @@ impl Type {
          Type::Named(tn,ts) => tn.chars().all(char::is_uppercase) || ts.iter().any(|tt| tt.is_open()),
          Type::Arrow(p,b) => p.is_open() || b.is_open(),
          Type::Ratio(p,b) => p.is_open() || b.is_open(),
+         Type::Power(p,_e) => p.is_open(),
          Type::And(ts) => ts.iter().any(|tt| tt.is_open()),
          Type::Tuple(ts) => ts.iter().any(|tt| tt.is_open()),
          Type::Product(ts) => ts.iter().any(|tt| tt.is_open()),
@@ -113,6 +119,49 @@ impl Type {
          _ => false,
       }
    }
+   pub fn constant(&self) -> Option<Constant> {
+      match self {
+         Type::Constant(c) => Some(c.clone()),
+         Type::And(ts) => ts.iter().find_map(|ct| ct.constant()),
+         _ => None,
+      }
+   }
+   /// The statically known length of a tuple type.
+   pub fn length(&self) -> Option<usize> {
+      match self {
+         Type::Tuple(ts) => Some(ts.len()),
+         Type::HTuple(_bt,Constant::Literal(n)) => str::parse::<usize>(n).ok(),
+         Type::And(ts) => ts.iter().find_map(|ct| ct.length()),
+         _ => None,
+      }
+   }
+   /// The length of a tuple as a constant, which may be symbolic such as n+m.
+   pub fn length_constant(&self) -> Option<Constant> {
+      match self {
+         Type::Tuple(ts) => Some(Constant::Literal(ts.len().to_string())),
+         Type::HTuple(_bt,Constant::Tuple(_)) => None,
+         Type::HTuple(_bt,ct) => Some(ct.clone()),
+         Type::And(ts) => ts.iter().find_map(|ct| ct.length_constant()),
+         _ => None,
+      }
+   }
+   /// The statically known length of a tuple of tuples once it is flattened.
+   pub fn flat_length(&self) -> Option<usize> {
+      match self {
+         Type::Tuple(ts) => ts.iter().try_fold(0usize, |n,ct| n.checked_add(ct.length()?)),
+         Type::HTuple(bt,Constant::Literal(n)) => str::parse::<usize>(n).ok()?.checked_mul(bt.length()?),
+         Type::And(ts) => ts.iter().find_map(|ct| ct.flat_length()),
+         _ => None,
+      }
+   }
+   /// A homogeneous tuple type with its length made static.
+   pub fn with_length(&self, n: usize) -> Type {
+      match self {
+         Type::HTuple(bt,_ct) => Type::HTuple(bt.clone(), Constant::Literal(n.to_string())),
+         Type::And(ts) => Type::And(ts.iter().map(|ct| ct.with_length(n)).collect()),
+         tt => tt.clone(),
+      }
+   }
    pub fn all_named(&self) -> Vec<Type> {
       match self {
          Type::Named(_,_) => { vec![self.clone()] },
@@ -214,6 +263,7 @@ impl Type {
          },
          Type::Arrow(p,b) => { let mut pv=p.vars(); pv.append(&mut b.vars()); pv },
          Type::Ratio(p,b) => { let mut pv=p.vars(); pv.append(&mut b.vars()); pv },
+         Type::Power(p,_e) => { p.vars() },
          Type::And(ts) => {
             let mut nv = Vec::new();
             for tt in ts.iter() {
@@ -239,17 +289,36 @@ impl Type {
          Type::Constant(_) => vec![]
       }
    }
-   pub fn simplify_ratio(&self) -> Type {
-      //assume Typee has already been normalized
-      let (mut num, den) = self.project_ratio();
-      let mut rden = Vec::new();
-      for d in den.into_iter() {
-         if let Some(ni) = num.iter().position(|n|n==&d) {
-            num.remove(ni);
-         } else {
-            rden.push(d);
+   /// Products and Ratios are a map from each factor to its integer exponent.
+   /// Factors with an exponent of zero cancel, and the Nil unit () is not a factor.
+   /// Returns None if an exponent overflows.
+   pub fn exponents(&self) -> Option<BTreeMap<Type,i64>> {
+      let (num, den) = self.project_ratio();
+      let mut es = BTreeMap::new();
+      for (f,sign) in num.into_iter().map(|n| (n,1)).chain(den.into_iter().map(|d| (d,-1))) {
+         match f {
+            Type::Power(t,e) => for (pt,pe) in t.exponents()?.into_iter() {
+               let te = es.entry(pt).or_insert(0i64);
+               *te = te.checked_add(pe.checked_mul(e)?.checked_mul(sign)?)?;
+            },
+            t if t == Type::Tuple(Vec::new()) => (),
+            t => {
+               let te = es.entry(t).or_insert(0i64);
+               *te = te.checked_add(sign)?;
+            },
          }
       }
+      es.retain(|_,e| *e!=0);
+      Some(es)
+   }
+   /// The canonical type of an exponent map, with each factor raised to its exponent in sorted order.
+   pub fn from_exponents(es: &BTreeMap<Type,i64>) -> Type {
+      let mut num = Vec::new();
+      let mut rden = Vec::new();
+      for (t,e) in es.iter() {
+         let f = if e.unsigned_abs()==1 { t.clone() } else { Type::Power(Box::new(t.clone()), e.unsigned_abs() as i64) };
+         if *e>0 { num.push(f); } else { rden.push(f); }
+      }
       let n = if num.len()==0 {
          Type::Tuple(Vec::new())
       } else if num.len()==1 {
@@ -267,6 +336,24 @@ impl Type {
       };
       tt
    }
+   pub fn simplify_ratio(&self) -> Type {
+      //assume Type has already been normalized
+      //a type whose exponents overflow is left as it is
+      match self.exponents() {
+         Some(es) => Type::from_exponents(&es),
+         None => self.clone(),
+      }
+   }
+   /// A factor raised to an integer power, such as Metre^2 or Second^-1.
+   /// Returns None if an exponent overflows.
+   pub fn pow(&self, e: i64) -> Option<Type> {
+      let mut es = BTreeMap::new();
+      for (t,te) in self.exponents()?.into_iter() {
+         es.insert(t, te.checked_mul(e)?);
+      }
+      es.retain(|_,e| *e!=0);
+      Some(Type::from_exponents(&es))
+   }
    pub fn normalize(&self) -> Type {
       match self {
          Type::And(ts) => {
@@ -301,7 +388,15 @@ impl Type {
          Type::Arrow(p,b) => {
             Type::Arrow(Box::new(p.normalize()), Box::new(b.normalize()))
          },
-         Type::Ratio(_p,_b) => self.simplify_ratio(),
+         Type::Ratio(p,b) => {
+            //an explicit Nil divisor, as in ?/(), is kept to mark a type that is not a fraction
+            let b = b.normalize();
+            if b == Type::Tuple(Vec::new()) { Type::Ratio(Box::new(p.normalize()),Box::new(b)) }
+            else { Type::Ratio(Box::new(p.normalize()),Box::new(b)).simplify_ratio() }
+         },
+         Type::HTuple(bt,ct) => Type::HTuple(Box::new(bt.normalize()), ct.normalize()),
+         Type::Power(p,e) => Type::Power(Box::new(p.normalize()),*e).simplify_ratio(),
+         Type::Constant(cv) => Type::Constant(cv.normalize()),
          tt => tt.clone(),
       }
    }
@@ -312,6 +407,7 @@ impl Type {
          Type::MaybeZero(tt) => Type::MaybeZero(Box::new(tt.remove(x))),
          Type::Arrow(p,b) => Type::Arrow(Box::new(p.remove(x)),Box::new(b.remove(x))),
          Type::Ratio(p,b) => Type::Ratio(Box::new(p.remove(x)),Box::new(b.remove(x))),
+         Type::Power(p,e) => Type::Power(Box::new(p.remove(x)),*e),
          Type::Named(tn,ts) => Type::Named(tn.clone(),ts.iter().map(|t| t.remove(x)).collect::<Vec<Type>>()),
          Type::And(ts) => Type::And(ts.iter().map(|t| t.remove(x)).collect::<Vec<Type>>()),
          Type::Tuple(ts) => Type::Tuple(ts.iter().map(|t| t.remove(x)).collect::<Vec<Type>>()),
@@ -329,20 +425,57 @@ impl Type {
          Type::MaybeZero(tt) => Type::MaybeZero(Box::new(tt.substitute(subs))),
          Type::Arrow(p,b) => Type::Arrow(Box::new(p.substitute(subs)),Box::new(b.substitute(subs))),
          Type::Ratio(p,b) => Type::Ratio(Box::new(p.substitute(subs)),Box::new(b.substitute(subs))),
+         Type::Power(p,e) => Type::Power(Box::new(p.substitute(subs)),*e),
          Type::Named(tn,ts) => Type::Named(tn.clone(),ts.iter().map(|t| t.substitute(subs)).collect::<Vec<Type>>()),
          Type::And(ts) => Type::And(ts.iter().map(|t| t.substitute(subs)).collect::<Vec<Type>>()),
          Type::Tuple(ts) => Type::Tuple(ts.iter().map(|t| t.substitute(subs)).collect::<Vec<Type>>()),
-         Type::HTuple(bt,ct) => Type::HTuple(Box::new(bt.substitute(subs)),ct.clone()),
+         Type::HTuple(bt,ct) => Type::HTuple(Box::new(bt.substitute(subs)), Type::substitute_constant(ct,subs)),
          Type::Product(ts) => Type::Product(ts.iter().map(|t| t.substitute(subs)).collect::<Vec<Type>>()),
-         Type::Constant(cv) => Type::Constant(cv.clone())
+         Type::Constant(cv) => Type::Constant(Type::substitute_constant(cv,subs)),
       }
    }
+   fn substitute_constant(c: &Constant, subs:&HashMap<Type,Type>) -> Constant {
+      let vars = c.vars();
+      if vars.is_empty() { return c.clone(); }
+      //arithmetic over an unknown length is itself unknown
+      if vars.iter().any(|v| matches!(subs.get(&Type::Constant(Constant::Literal(v.clone()))),
+                                      Some(Type::Constant(Constant::Tuple(_))))) {
+         return Constant::Tuple(Vec::new());
+      }
+      c.substitute(&|v| match subs.get(&Type::Constant(Constant::Literal(v.to_string()))) {
+         Some(Type::Constant(sc)) => Some(sc.clone()),
+         _ => None,
+      }).normalize()
+   }
+   /// Unify a constant parameter, such as a length, where variables of the right hand side may be solved for.
+   fn unify_constant(lc: &Constant, rc: &Constant, subs: &mut Vec<(Type,Type)>) -> bool {
+      //variables that are already bound are replaced first, so that n+m can be checked once n and m are known
+      let bound = |v: &str| subs.iter().rev().find_map(|(sv,st)| match (sv,st) {
+         (Type::Constant(Constant::Literal(sv)),Type::Constant(st)) if sv==v => Some(st.clone()),
+         _ => None,
+      });
+      let lc = lc.normalize();
+      let rc = if rc.is_variable() { rc.clone() } else { rc.substitute(&bound).normalize() };
+      if lc==rc { return true; }
+      if rc.is_variable() {
+         //a variable can not be bound to arithmetic over itself, such as n to n+1
+         if lc.vars().iter().any(|v| Constant::Literal(v.clone())==rc) { return false; }
+         subs.push((Type::Constant(rc), Type::Constant(lc)));
+         return true;
+      }
+      if let Some((v,sc)) = rc.solve(&lc) {
+         subs.push((Type::Constant(Constant::Literal(v)), Type::Constant(sc)));
+         return true;
+      }
+      false
+   }
    pub fn is_concrete(&self) -> bool {
       match self {
          Type::Any => false,
          Type::MaybeZero(_tt) => false,
          Type::Arrow(p,b) => p.is_concrete() && b.is_concrete(),
          Type::Ratio(p,b) => p.is_concrete() && b.is_concrete(),
+         Type::Power(p,_e) => p.is_concrete(),
          Type::Named(_tn,ts) => ts.iter().all(|tc| tc.is_concrete()),
          Type::And(ts) => ts.iter().all(|tc| tc.is_concrete()), //bottom Typee is also concrete
          Type::Tuple(ts) => ts.iter().all(|tc| tc.is_concrete()),
@@ -411,16 +544,41 @@ impl Type {
    }
    pub fn arrow_implies(tlc: &TLC, lt: &mut Type, rt: &mut Type, inarrow: InArrow) -> Type {
       let mut subs = Vec::new();
+      let l0 = lt.clone();
+      let r0 = rt.clone();
       *lt = tlc.extend_implied(lt);
       *lt = lt.normalize();
       *rt = tlc.extend_implied(rt);
       *rt = rt.normalize();
-      lt.__implication_unifier(&rt, &mut subs, inarrow).normalize()
+      let nt = lt.__implication_unifier(&rt, &mut subs, inarrow).normalize();
+      if !nt.is_bottom() || tlc.language_implies.is_empty() { return nt; }
+      //inclusion between regex languages is only used if declared implications are not enough
+      let mut subs = Vec::new();
+      *lt = tlc.extend_languages(&l0).normalize();
+      *rt = tlc.extend_languages(&r0).normalize();
+      lt.__implication_unifier(rt, &mut subs, inarrow).normalize()
    }
    pub fn subs_implies(tlc: &TLC, subs: &mut Vec<(Type,Type)>, lt: &Type, rt: &Type) -> Type {
-      let lt = tlc.extend_implied(lt).normalize();
-      let rt = tlc.extend_implied(rt).normalize();
-      lt.subs_implication_unifier(subs, &rt).normalize()
+      let el = tlc.extend_implied(lt).normalize();
+      let er = tlc.extend_implied(rt).normalize();
+      let mut declared = subs.clone();
+      let nt = el.subs_implication_unifier(&mut declared, &er).normalize();
+      if !nt.is_bottom() || tlc.language_implies.is_empty() {
+         *subs = declared;
+         return nt;
+      }
+      let el = tlc.extend_languages(lt).normalize();
+      let er = tlc.extend_languages(rt).normalize();
+      el.subs_implication_unifier(subs, &er).normalize()
+   }
+   /// Implication where length variables are rigid, as in the body of a function.
+   /// A declared length such as n must then hold for every n, so it is never solved for.
+   pub fn rigid_implies(tlc: &TLC, lt: &Type, rt: &Type) -> bool {
+      let mut subs = Vec::new();
+      !Type::subs_implies(tlc, &mut subs, lt, rt).is_bottom() && subs.iter().all(|(sv,st)| match (sv,st) {
+         (Type::Constant(sc),Type::Constant(tc)) if sc.is_variable() => sc==&tc.normalize(),
+         _ => true,
+      })
    }
    pub fn nored_implies(tlc: &TLC, subs: &mut Vec<(Type,Type)>, lt: &Type, rt: &Type) -> Type {
       let lt = tlc.extend_implied(lt).normalize();
@@ -559,6 +717,18 @@ impl Type {
             }
             Type::Product(ts)
          },
+         (Type::Power(lt,le),Type::Power(rt,re)) if le==re => {
+            let nt = lt.__implication_unifier(rt,subs,inarrow);
+            if nt.is_bottom() { return nt.clone(); }
+            Type::Power(Box::new(nt),*le)
+         },
+         //a power is equal to a product that repeats its factor, such as Aa*Aa written out
+         (Type::Power(lt,le),Type::Product(ra)) if *le as usize==ra.len() => {
+            Type::Product(vec![(**lt).clone(); ra.len()]).__implication_unifier(other,subs,inarrow)
+         },
+         (Type::Product(la),Type::Power(rt,re)) if *re as usize==la.len() => {
+            self.__implication_unifier(&Type::Product(vec![(**rt).clone(); la.len()]),subs,inarrow)
+         },
          (Type::Tuple(la),Type::Tuple(ra)) if la.len()==ra.len() => {
             let mut ts = Vec::new();
             for (lt,rt) in std::iter::zip(la,ra) {
@@ -573,6 +743,22 @@ impl Type {
             if bt.is_bottom() { return bt.clone(); }
             Type::HTuple(Box::new(bt), lc.clone())
          },
+         //length variables are substituted like type variables, so T[n] and T[n] must have the same length
+         //a length such as n+1 is solved for its variable
+         (Type::HTuple(lb,lc),Type::HTuple(rb,rc)) if !rc.vars().is_empty() => {
+            if !Type::unify_constant(lc,rc,subs) { return Type::And(vec![]); }
+            let bt = lb.__implication_unifier(rb,subs,inarrow);
+            if bt.is_bottom() { return bt.clone(); }
+            Type::HTuple(Box::new(bt), rc.clone())
+         },
+         (Type::Tuple(lts),Type::HTuple(rb,rc)) if !rc.vars().is_empty() => {
+            if !Type::unify_constant(&Constant::Literal(lts.len().to_string()),rc,subs) { return Type::And(vec![]); }
+            for lt in lts.iter() {
+               let nt = lt.__implication_unifier(rb,subs,inarrow);
+               if nt.is_bottom() { return nt.clone(); }
+            }
+            Type::HTuple(rb.clone(), rc.clone())
+         },
          (Type::HTuple(lb,_lc),Type::HTuple(rb,Constant::Tuple(rc))) => {
             let bt = lb.__implication_unifier(rb,subs,inarrow);
             if bt.is_bottom() { return bt.clone(); }
@@ -595,9 +781,14 @@ impl Type {
             Type::HTuple(rb.clone(), Constant::Tuple(rc.clone()))
          },
 	
-         (Type::Constant(lv),Type::Constant(rv)) if lv==rv => {
+         //True and False are the constants 1 and 0
+         (Type::Constant(lv),Type::Constant(rv)) if lv.truthy()==rv.truthy() => {
             Type::Constant(lv.clone())
          },
+         (Type::Constant(lv),Type::Constant(rv)) if !rv.vars().is_empty() => {
+            if !Type::unify_constant(lv,rv,subs) { return Type::And(vec![]); }
+            Type::Constant(rv.clone())
+         },
          _ => Type::And(vec![]),
       };
       tt
@@ -711,6 +902,11 @@ impl Type {
             }
             Type::Product(ts)
          },
+         (Type::Power(lt,le),Type::Power(rt,re)) if le==re => {
+            let nt = lt.most_general_unifier(rt);
+            if nt.is_bottom() { return nt.clone(); }
+            Type::Power(Box::new(nt),*le)
+         },
          (Type::Tuple(la),Type::Tuple(ra)) if la.len()==ra.len() => {
             let mut ts = Vec::new();
             for (lt,rt) in std::iter::zip(la,ra) {
@@ -751,10 +947,37 @@ impl std::fmt::Debug for Type {
            }
            Type::And(ts) => write!(f, "{{{}}}", ts.iter().map(|t|format!("{:?}",t)).collect::<Vec<String>>().join("+") ),
            Type::Tuple(ts) => write!(f, "({})", ts.iter().map(|t|format!("{:?}",t)).collect::<Vec<String>>().join(",") ),
-           Type::HTuple(bt,ct) => write!(f, "{:?}[{:?}]", bt, ct),
-           Type::Product(ts) => write!(f, "({})", ts.iter().map(|t|format!("{:?}",t)).collect::<Vec<String>>().join("*") ),
+           Type::HTuple(_,_) => {
+              //lengths are written in the same order that they are parsed, outermost first
+              let mut bt = self;
+              let mut cts = Vec::new();
+              while let Type::HTuple(ibt,ict) = bt {
+                 cts.push(ict);
+                 bt = ibt;
+              }
+              write!(f, "{:?}", bt)?;
+              for ct in cts.iter() {
+                 if **ct == Constant::Tuple(Vec::new()) { write!(f, "[]")?; }
+                 else { write!(f, "[{:?}]", ct)?; }
+              }
+              Ok(())
+           },
+           Type::Product(ts) => {
+              //repeated factors are printed as powers
+              let mut fs: Vec<(String,usize)> = Vec::new();
+              for t in ts.iter() {
+                 let t = format!("{:?}",t);
+                 match fs.last_mut() {
+                    Some((lt,e)) if *lt==t => { *e += 1; },
+                    _ => { fs.push((t,1)); },
+                 }
+              }
+              write!(f, "({})", fs.iter().map(|(t,e)| if *e==1 { t.clone() } else { format!("{}^{}",t,e) })
+                                   .collect::<Vec<String>>().join("*") )
+           },
            Type::Arrow(p,b) => write!(f, "({:?})->({:?})", p, b),
            Type::Ratio(n,d) => write!(f, "({:?})/({:?})", n, d),
+           Type::Power(t,e) => write!(f, "{:?}^{}", t, e),
            Type::Constant(cv) => write!(f, "[{:?}]", cv),
         }
     }
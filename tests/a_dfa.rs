@@ -0,0 +1,118 @@
+use lsts::dfa::{DFA,LiteralPattern};
+
+#[test]
+fn check_dfa_membership() {
+   let digits = DFA::from_regex("/^[0-9]+$/").unwrap();
+   assert!(digits.accepts("0"));
+   assert!(digits.accepts("0123"));
+   assert!(!digits.accepts(""));
+   assert!(!digits.accepts("12a"));
+
+   let signed = DFA::from_regex("^[-]?[0-9]+$").unwrap();
+   assert!(signed.accepts("-12"));
+   assert!(signed.accepts("12"));
+   assert!(!signed.accepts("--12"));
+
+   let float = DFA::from_regex("^[0-9]+(\\.[0-9]+)?$").unwrap();
+   assert!(float.accepts("1"));
+   assert!(float.accepts("1.25"));
+   assert!(!float.accepts("1."));
+
+   let string = DFA::from_regex(r#"^["][^"]*["]$"#).unwrap();
+   assert!(string.accepts(r#""abc""#));
+   assert!(string.accepts(r#""αβγ""#));
+   assert!(!string.accepts(r#""a"b""#));
+
+   let bounded = DFA::from_regex("^a{2,3}|b$").unwrap();
+   assert!(bounded.accepts("aa"));
+   assert!(bounded.accepts("aaa"));
+   assert!(!bounded.accepts("aaaa"));
+   assert!(bounded.accepts("b"));
+
+   let unanchored = DFA::from_regex("[0-9]").unwrap();
+   assert!(unanchored.accepts("abc1def"));
+   assert!(!unanchored.accepts("abcdef"));
+
+   assert!(DFA::from_regex("^[$").is_none());
+   assert!(DFA::from_regex("^\\d+$").is_none());
+}
+
+#[test]
+fn check_dfa_literal() {
+   let ps = vec![LiteralPattern::Char('0'), LiteralPattern::Variable("a".to_string()), LiteralPattern::Range("0-9".to_string())];
+   let dfa = DFA::from_literal(&ps).unwrap();
+   assert!(dfa.accepts("0x1"));
+   assert!(dfa.accepts("0xyz9"));
+   assert!(!dfa.accepts("01"));
+   assert!(!dfa.accepts("1x1"));
+}
+
+#[test]
+fn check_dfa_algebra() {
+   let digits = DFA::from_regex("^[0-9]+$").unwrap();
+   let digit = DFA::from_regex("^[0-9]$").unwrap();
+   let signed = DFA::from_regex("^[-]?[0-9]+$").unwrap();
+   let empty = DFA::from_regex("^$").unwrap();
+
+   assert!(digit.intersection(&digits.complement()).unwrap().is_empty());
+   assert!(!digits.intersection(&digit.complement()).unwrap().is_empty());
+   assert!(digits.intersection(&signed.complement()).unwrap().is_empty());
+   assert!(!digits.intersection(&digit).unwrap().is_empty());
+   assert!(DFA::empty().is_empty());
+   assert!(!empty.is_empty());
+   assert!(empty.accepts(""));
+
+   let either = digit.union(&empty).unwrap();
+   assert!(either.accepts(""));
+   assert!(either.accepts("7"));
+   assert!(!either.accepts("77"));
+
+   let not_digits = digits.complement();
+   assert!(not_digits.accepts(""));
+   assert!(not_digits.accepts("a"));
+   assert!(!not_digits.accepts("12"));
+   assert!(not_digits.complement().intersection(&digits.complement()).unwrap().is_empty());
+
+   //minimization is canonical, so equal languages have the same number of states
+   let a = DFA::from_regex("^(0|1)*1$").unwrap();
+   let b = DFA::from_regex("^[01]*1$").unwrap();
+   assert_eq!(a.states.len(), b.states.len());
+   assert_eq!(a.states.len(), 2);
+   assert_eq!(digits.minimize().states.len(), 2);
+}
+
+#[test]
+fn check_dfa_concatenation() {
+   let digits = DFA::from_regex("^[0-9]+$").unwrap();
+   let dot = DFA::from_regex("^[.]$").unwrap();
+   let decimal = digits.concat(&dot).unwrap().concat(&digits).unwrap();
+   assert!(decimal.accepts("1.25"));
+   assert!(!decimal.accepts("1."));
+   assert!(!decimal.accepts("125"));
+   assert_eq!(decimal.is_subset(&DFA::from_regex("^[0-9]+[.][0-9]+$").unwrap()), Some(true));
+   assert_eq!(digits.is_subset(&decimal), Some(false));
+
+   let quoted = DFA::from_regex(r#"^["][a-z]+["]$"#).unwrap();
+   assert!(quoted.strip_prefix('"').accepts(r#"abc""#));
+   assert!(!quoted.strip_prefix('"').accepts(r#""abc""#));
+   assert!(quoted.strip_suffix('"').accepts(r#""abc"#));
+   assert!(quoted.strip_prefix('a').is_empty());
+   let joined = quoted.strip_suffix('"').concat(&quoted.strip_prefix('"')).unwrap();
+   assert!(joined.accepts(r#""abcde""#));
+   assert!(!joined.accepts(r#""a""#));
+}
+
+#[test]
+fn check_dfa_budget() {
+   //large bounded repeats are built in linear time
+   let many = DFA::from_regex("^a{1000}$").unwrap();
+   assert_eq!(many.states.len(), 1001);
+   assert!(many.accepts(&"a".repeat(1000)));
+   assert!(!many.accepts(&"a".repeat(999)));
+   assert_eq!(many.is_subset(&DFA::from_regex("^a+$").unwrap()), Some(true));
+
+   //languages that are too large to construct are unknown
+   assert!(DFA::from_regex("^a{100000}$").is_none());
+   assert!(DFA::from_regex("^(a{1000}){1000}$").is_none());
+   assert!(DFA::from_regex("^[ab]*a[ab]{20}$").is_none());
+}
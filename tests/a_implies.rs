@@ -200,9 +200,9 @@ fn check_compound_subtyping() {
    let tp1  = Type::Product(vec![tn1.clone(), tn1.clone()]);
    let tp2  = Type::Product(vec![ts1.clone(), tn1.clone()]);
    let tp3  = Type::Product(vec![tn1.clone(), ts1.clone()]);
-   assert_eq!(tp1, tp1.implication_unifier(&tp1));
-   assert_eq!(tp1, tp2.implication_unifier(&tp1));
-   assert_eq!(tp1, tp3.implication_unifier(&tp1));
+   assert_eq!(tp1.normalize(), tp1.implication_unifier(&tp1));
+   assert_eq!(tp1.normalize(), tp2.implication_unifier(&tp1));
+   assert_eq!(tp1.normalize(), tp3.implication_unifier(&tp1));
    assert_eq!(td, tp1.implication_unifier(&tp2));
    assert_eq!(td, tp1.implication_unifier(&tp3));
 
@@ -337,7 +337,7 @@ fn check_arrow_ratio() {
    let ta2  = Type::Arrow( Box::new(tn3.clone()), Box::new(tp3.clone()) );
    let ta3  = Type::Arrow( Box::new(tr1.clone()), Box::new(tr2.clone()) );
    //Pt/Qt -> ? => X -> X*X = Pt/Qt -> Pt*Pt/Qt*Qt
-   assert_eq!( ta3, ta1.implication_unifier(&ta2) );
+   assert_eq!( ta3.normalize(), ta1.implication_unifier(&ta2) );
 }
 
 #[test]
@@ -360,3 +360,34 @@ fn check_constant_arrows() {
    let ta4  = Type::Arrow( Box::new(tt1.clone()), Box::new(tt2.clone()) );
    assert_eq!( ta4, ta4.implication_unifier(&ta4) );
 }
+
+#[test]
+fn check_exponents() {
+   let tt1  = Type::Tuple(vec![]);
+   let tn1  = Type::Named("Aa".to_string(),vec![]);
+   let tn2  = Type::Named("Bb".to_string(),vec![]);
+   let tp1  = Type::Product(vec![ tn1.clone(), tn1.clone() ]);
+   let tp2  = Type::Product(vec![ tn1.clone(), tn1.clone(), tn1.clone() ]);
+   let tp3  = Type::Product(vec![ tn2.clone(), tn1.clone(), tn1.clone() ]);
+   let tp4  = Type::Product(vec![ tn1.clone(), tn2.clone(), tn1.clone() ]);
+   let tr1  = Type::Ratio( Box::new(tp1.clone()), Box::new(tp2.clone()) );
+   let tr2  = Type::Ratio( Box::new(tt1.clone()), Box::new(tn1.clone()) );
+   let tr3  = Type::Ratio( Box::new(tp3.clone()), Box::new(tn2.clone()) );
+   let tw1  = Type::Power( Box::new(tn1.clone()), 2 );
+
+   //factors are counted and cancelled regardless of their order
+   assert_eq!( tp3.exponents(), tp4.exponents() );
+   assert_eq!( tp3.normalize(), tp4.normalize() );
+   assert_eq!( tr1.exponents(), Some(vec![(tn1.clone(),-1)].into_iter().collect()) );
+   assert_eq!( tr1.normalize(), tr2 );
+   assert_eq!( tr3.normalize(), tw1 );
+
+   //repeated factors are powers
+   assert_eq!( tp1.normalize(), tw1 );
+   assert_eq!( tn1.pow(2), Some(tw1.clone()) );
+   assert_eq!( tn1.pow(-1), Some(tr2.clone()) );
+   assert_eq!( tn1.pow(0), Some(tt1.clone()) );
+   assert_eq!( tr2.pow(-2), Some(tw1.clone()) );
+   assert_eq!( tn1.pow(i64::MAX).and_then(|t| t.pow(2)), None );
+   assert_eq!( format!("{:?}", tp3.normalize()), "(Aa^2*Bb)" );
+}
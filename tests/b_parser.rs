@@ -35,6 +35,9 @@ fn parse_simplytyped() {
    tlc.parse_str(None,"forall :A,:B::C. (A,B);").unwrap();
    tlc.parse_str(None,"forall :A,:B::C. (A,B) :: R;").unwrap();
    tlc.parse_str(None,"{a; b;};").unwrap();
+   tlc.parse_str(None,"fn() = a;").unwrap();
+   tlc.parse_str(None,"fn(a:A) = a;").unwrap();
+   tlc.parse_str(None,"fn(a:A,b:B):C = f(a,b);").unwrap();
 
    //Type Names, like Ab, are always valid constants, even without a prelude
    tlc.parse_str(None,"let t: T[Ab];").unwrap();
@@ -75,6 +75,40 @@ fn check_products_and_ratios() {
    tlc.check(None, "type At; let a: At/At*At; a:?/();").unwrap_err();
 }
 
+#[test]
+fn check_unit_exponents() {
+   let mut tlc = TLC::new();
+   let units = "type Mt::Unit; type Sc::Unit; type Kg::Unit; type Nt: Mt*Kg/Sc*Sc::Unit; type Jl: Kg*Mt^2/Sc^2::Unit; type Hz: ()/Sc::Unit;";
+
+   //powers are written with ^ and are equal to repeated factors
+   tlc.check(None, &format!("{} let a: Mt^2; a:Mt*Mt;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Mt*Mt; a:Mt^2;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Mt^2; a:Mt^3;", units)).unwrap_err();
+   tlc.check(None, &format!("{} let a: Mt^-1; a:()/Mt;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Mt^0; a:();", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Mt*Mt/Mt*Mt*Mt; a:Mt^-1;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Mt/Sc^2; a:Mt/Sc*Sc;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Mt^2.5;", units)).unwrap_err();
+   tlc.check(None, &format!("{} let a: Mt^x;", units)).unwrap_err();
+
+   //large powers are not expanded, and exponents that overflow are errors
+   tlc.check(None, &format!("{} let a: Mt^10000000; a:Mt^10000000;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Mt^9223372036854775807; a:Mt^9223372036854775807;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Mt^9223372036854775807*Mt;", units)).unwrap_err();
+   tlc.check(None, &format!("{} let a: (Mt^2)^4611686018427387904;", units)).unwrap_err();
+
+   //derived units are equal to the units that define them
+   tlc.check(None, &format!("{} let a: Jl; a:Nt*Mt;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Jl; a:Mt*Nt;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Nt*Mt; a:Jl;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Nt*Mt; a:Kg*Mt^2/Sc^2;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Nt*Sc^2; a:Kg*Mt;", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Hz*Sc; a:();", units)).unwrap();
+   tlc.check(None, &format!("{} let a: Jl; a:Nt;", units)).unwrap_err();
+   tlc.check(None, &format!("{} let a: Nt*Mt; a:Nt;", units)).unwrap_err();
+   tlc.check(None, &format!("{} let a: Hz; a:Sc;", units)).unwrap_err();
+}
+
 #[test]
 fn check_kinded_polymorphism() {
    let mut tlc = TLC::new();
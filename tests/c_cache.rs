@@ -0,0 +1,74 @@
+use lsts::tlc::TLC;
+use lsts::typ::Type;
+
+#[test]
+fn check_cached_prelude() {
+   let dir = format!("{}/cache-prelude", env!("CARGO_TARGET_TMPDIR"));
+   let _ = std::fs::remove_dir_all(&dir);
+
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_cached(&dir, "preludes/l1.tlc").unwrap();
+   let path = tlc.cache_path(&dir, "preludes/l1.tlc");
+   assert!(std::path::Path::new(&path).is_file());
+
+   //a row marked in the cache is loaded as it was written, so no row is checked again
+   let marked = tlc.rows.len()-1;
+   let typ = std::mem::replace(&mut tlc.rows[marked].typ, Type::Named("Marked".to_string(),Vec::new()));
+   tlc.write_cache(&path, "preludes/l1.tlc", l1).unwrap();
+   let mut loaded = TLC::new();
+   loaded.import_cached(&dir, "preludes/l1.tlc").unwrap();
+   assert_eq!(loaded.rows[marked].typ, Type::Named("Marked".to_string(),Vec::new()));
+   tlc.rows[marked].typ = typ;
+   tlc.write_cache(&path, "preludes/l1.tlc", l1).unwrap();
+
+   let mut cached = TLC::new();
+   let cl1 = cached.import_cached(&dir, "preludes/l1.tlc").unwrap();
+   assert_eq!(l1.id, cl1.id);
+   assert_eq!(tlc.rows.len(), cached.rows.len());
+   assert_eq!(tlc.rules.len(), cached.rules.len());
+
+   //the loaded context checks and rejects the same terms
+   cached.check(Some(cl1), "1 + 2;").unwrap();
+   cached.check(Some(cl1), "1 : U8;").unwrap();
+   cached.check(Some(cl1), "-1: U64;").unwrap_err();
+   cached.check(None, "let f(x: I64): I64 = x + 1; f(2);").unwrap();
+
+   //a user file is checked on top of the cached prelude without checking the prelude again
+   let user = format!("{}/user.tlc", dir);
+   std::fs::write(&user, "let f(x: I64): I64 = x + 1;").unwrap();
+   let rules = cached.rules.len();
+   let us = cached.import_file(Some(cl1), &user).unwrap();
+   tlc.import_file(Some(l1), &user).unwrap();
+   assert_eq!(cached.rules.len(), rules);
+   assert_eq!(tlc.rows.len(), cached.rows.len());
+   cached.check(Some(us), "f(2) + 1;").unwrap();
+
+   //a context that has already checked something does not read the cache
+   let mut used = TLC::new();
+   used.import_str(None, "1;").unwrap();
+   assert!(used.read_cache(&path).is_none());
+}
+
+#[test]
+fn check_stale_cache() {
+   let dir = format!("{}/cache-stale", env!("CARGO_TARGET_TMPDIR"));
+   let _ = std::fs::remove_dir_all(&dir);
+   std::fs::create_dir_all(&dir).unwrap();
+   let lib = format!("{}/lib.tlc", dir);
+   let main = format!("{}/main.tlc", dir);
+   std::fs::write(&lib, "let f(x: I64): I64 = x + 1;").unwrap();
+   std::fs::write(&main, "import $\"preludes/l1.tlc\"; import $\"lib.tlc\";").unwrap();
+
+   let mut tlc = TLC::new();
+   tlc.import_cached(&dir, &main).unwrap();
+   let path = tlc.cache_path(&dir, &main);
+   assert!(TLC::new().read_cache(&path).is_some());
+
+   //an imported file changes, so the cache of the file that imports it is stale
+   std::fs::write(&lib, "let g(x: I64): I64 = x + 1;").unwrap();
+   assert!(TLC::new().read_cache(&path).is_none());
+   let mut tlc = TLC::new();
+   let sc = tlc.import_cached(&dir, &main).unwrap();
+   tlc.check(Some(sc), "g(1);").unwrap();
+   tlc.check(Some(sc), "f(1);").unwrap_err();
+}
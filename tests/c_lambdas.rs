@@ -0,0 +1,28 @@
+use lsts::tlc::TLC;
+
+#[test]
+fn check_lambdas() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(l1), "fn(x: I64): I64 = x + 1;").unwrap();
+   tlc.check(Some(l1), "fn(x: I64, y: I64): I64 = x + y;").unwrap();
+   tlc.check(Some(l1), "fn(x: I64): U64 = x + 1;").unwrap_err();
+   tlc.check(Some(l1), "fn(x: I64): I64 = y;").unwrap_err();
+
+   tlc.check(Some(l1), "let f: (I64,)->I64 = fn(x: I64): I64 = x + 1;").unwrap();
+   tlc.check(Some(l1), "let f: (I64,)->U64 = fn(x: I64): I64 = x + 1;").unwrap_err();
+   tlc.check(Some(l1), "let apply(f: (I64,)->I64, x: I64): I64 = f(x); apply(fn(y: I64): I64 = y + 1, 2);").unwrap();
+   tlc.check(Some(l1), "range(3).flatmap(fn(x: I64): I64[] = (x,));").unwrap();
+}
+
+#[test]
+fn reduce_lambdas() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(l1), "(fn(x: I64): I64 = x * 2)(4) @reduce : [8];").unwrap();
+   tlc.check(Some(l1), "(fn(x: I64, y: I64): I64 = x - y)(4, 1) @reduce : [3];").unwrap();
+   tlc.check(Some(l1), "let f: (I64,)->I64 = fn(x: I64): I64 = x + 1; f(2) @reduce : [3];").unwrap();
+   tlc.check(Some(l1), "let apply(f: (I64,)->I64, x: I64): I64 = f(x); apply(fn(y: I64): I64 = y + 1, 2) @reduce : [3];").unwrap();
+}
@@ -0,0 +1,75 @@
+use lsts::tlc::TLC;
+use lsts::typ::Type;
+
+#[test]
+fn check_language_inclusion() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   let u64_type = Type::Named("U64".to_string(),Vec::new());
+   let i64_type = Type::Named("I64".to_string(),Vec::new());
+   assert!(tlc.language_implies["U64"].contains(&i64_type));
+   assert!(!tlc.language_implies.get("I64").map(|ts| ts.contains(&u64_type)).unwrap_or(false));
+   //types with the same language stay distinct
+   assert!(!tlc.language_implies["U64"].contains(&Type::Named("U8".to_string(),Vec::new())));
+
+   tlc.check(Some(l1), "(1: U64) : I64;").unwrap();
+   tlc.check(Some(l1), "let f(x:I64):I64 = x; f(1:U64);").unwrap();
+   tlc.check(Some(l1), "(-1: I64) : U64;").unwrap_err();
+   tlc.check(Some(l1), "let x: F64 = -3;").unwrap_err();
+   tlc.check(Some(l1), "let f(x:I64):I64 = x; let f(x:String):String = x; f(1:U64);").unwrap();
+   //the literal is an I64, so U64 addition does not match and I64 addition is the most specific
+   tlc.check(Some(l1), "((1:U64) + 1) : I64;").unwrap();
+   tlc.check(Some(l1), "((1:U64) + 1) : U64;").unwrap_err();
+   tlc.check(Some(l1), "type Digit = /^[0-9]$/; (1: Digit) : U64;").unwrap();
+   tlc.check(Some(l1), "type Digit = /^[0-9]$/; (1: U64) : Digit;").unwrap_err();
+   tlc.check(Some(l1), "type Digit = /^[0-9]$/; 12 : Digit;").unwrap_err();
+   //long regexes are compared in time, and regexes too large to construct have no language
+   tlc.check(Some(l1), "type Long = /^a{1000}$/;").unwrap();
+   tlc.check(Some(l1), "type Huge = /^[ab]*a[ab]{20}$/;").unwrap();
+}
+
+#[test]
+fn check_language_contradiction() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(l1), "type Digit: U64 = /^[0-9]$/; 1 : Digit;").unwrap();
+   let error = tlc.check(Some(l1), "type Fraction: Integer = /^[0-9]+(\\.[0-9]+)?$/;").unwrap_err();
+   assert_eq!(error.kind, "Type Error");
+   assert!(error.rule.contains("typedef Fraction : Integer contradicts their regexes"));
+   assert!(error.rule.contains("Fraction accepts \"0.0\" but Integer does not"));
+}
+
+#[test]
+fn check_language_concatenation() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   let defs = r#"type Identifier = /^["][a-z]+["]$/; type QualifiedName = /^["][a-z]+([.][a-z]+)*["]$/;
+                 let a: Identifier = "abc"; let b: Identifier = "de";"#;
+
+   tlc.check(Some(l1), &format!(r#"{} (a + "." + b) : QualifiedName;"#, defs)).unwrap();
+   tlc.check(Some(l1), &format!(r#"{} (a + "x") : Identifier;"#, defs)).unwrap();
+   tlc.check(Some(l1), &format!(r#"{} f"{{a}}.{{b}}" : QualifiedName;"#, defs)).unwrap();
+   tlc.check(Some(l1), &format!(r#"{} f"{{a}}.{{b}}.{{a}}" : QualifiedName;"#, defs)).unwrap();
+   tlc.check(Some(l1), &format!(r#"{} (a + " " + b) : QualifiedName;"#, defs)).unwrap_err();
+   tlc.check(Some(l1), &format!(r#"{} f"{{a}}.{{b}}" : Identifier;"#, defs)).unwrap_err();
+   tlc.check(Some(l1), &format!(r#"{} let c: String = "x"; (a + c) : Identifier;"#, defs)).unwrap_err();
+}
+
+#[test]
+fn check_concatenation_values() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   let defs = tlc.import_str(Some(l1), r#"type Identifier: String = /^["][a-z]+["]$/;
+                                          type QualifiedName: String = /^["][a-z]+([.][a-z]+)*["]$/;"#).unwrap();
+
+   //a concatenation reduces to a string in the language that it is typed with
+   tlc.check(Some(defs), r#"let b: Identifier = ("abc" + "x") @reduce;"#).unwrap();
+   let v = tlc.reduce_str(Some(defs), r#"("abc" + "x") : Identifier;"#).unwrap();
+   assert_eq!(format!("{:?}", v), r#""abcx""#);
+   assert!(tlc.languages["Identifier"].accepts(&format!("{:?}", v)));
+   let v = tlc.reduce_str(Some(defs), r#"("abc" + "." + "de") : QualifiedName;"#).unwrap();
+   assert_eq!(format!("{:?}", v), r#""abc.de""#);
+   assert!(tlc.languages["QualifiedName"].accepts(&format!("{:?}", v)));
+}
@@ -0,0 +1,30 @@
+use lsts::tlc::TLC;
+use lsts::constant::Constant;
+
+#[test]
+fn check_loops() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(l1), "while (1 < 2) { 3; };").unwrap();
+   tlc.check(Some(l1), "(while (1 > 2) { 3; }) : ();").unwrap();
+   tlc.check(Some(l1), "(loop { 3; } while (1 > 2)) : I64;").unwrap();
+   tlc.check(Some(l1), "(loop { 3; } while (1 > 2)) : U64;").unwrap_err();
+   tlc.check(Some(l1), "while (2) { 3; };").unwrap_err();
+   tlc.check(Some(l1), "while (\"a\") { 3; };").unwrap_err();
+}
+
+#[test]
+fn reduce_loops() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   assert_eq!(tlc.reduce_str(Some(l1), "while (1 > 2) { 3; };").unwrap(), Constant::Tuple(Vec::new()));
+   assert_eq!(tlc.reduce_str(Some(l1), "while (False) { while (True) {}; };").unwrap(), Constant::Tuple(Vec::new()));
+   assert_eq!(tlc.reduce_str(Some(l1), "loop { 3; } while (1 > 2);").unwrap(), Constant::Literal("3".to_string()));
+   assert_eq!(tlc.reduce_str(Some(l1), "let f(x: I64): I64 = loop { x + 1; } while (x > 5); f(2);").unwrap(), Constant::Literal("3".to_string()));
+
+   //without mutation, a loop whose condition holds will never terminate
+   tlc.reduce_str(Some(l1), "while (1 < 2) { 3; };").unwrap_err();
+   tlc.reduce_str(Some(l1), "loop { 3; } while (True);").unwrap_err();
+}
@@ -0,0 +1,98 @@
+use lsts::tlc::TLC;
+use lsts::constant::Constant;
+
+#[test]
+fn check_module_scopes() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   let inc = tlc.import_file(None, "tests/modules/increment.tlc").unwrap();
+   let dbl = tlc.import_file(None, "tests/modules/double.tlc").unwrap();
+   assert!(inc.id != l1.id && dbl.id != inc.id);
+   assert!(tlc.scopes[inc.id].children.iter().any(|(n,_,_,_)| n=="increment"));
+   assert!(!tlc.scopes[inc.id].children.iter().any(|(n,_,_,_)| n=="double"));
+}
+
+#[test]
+fn check_module_imports() {
+   let mut tlc = TLC::new();
+   tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   let v = tlc.reduce_str(None, r#"
+      import $"preludes/l1.tlc";
+      import $"tests/modules/increment.tlc";
+      import $"tests/modules/double.tlc";
+      increment(3) + double(3);
+   "#).unwrap();
+   assert_eq!(v, Constant::Literal("10".to_string()));
+
+   //names defined by an imported file are exposed, names that it imports are not
+   tlc.check(None, r#"
+      import $"tests/modules/increment.tlc";
+      increment(3);
+   "#).unwrap();
+   tlc.check(None, r#"
+      import $"tests/modules/increment.tlc";
+      increment(3) + 1;
+   "#).unwrap_err();
+}
+
+#[test]
+fn check_import_resolution() {
+   let mut tlc = TLC::new();
+   tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   //imports are relative to the importing file and each file is only imported once
+   let v = tlc.reduce_str(None, r#"
+      import $"preludes/l1.tlc";
+      import $"tests/modules/both.tlc";
+      import $"tests/modules/increment.tlc";
+      both(2) + increment(1);
+   "#).unwrap();
+   assert_eq!(v, Constant::Literal("8".to_string()));
+   assert_eq!(tlc.scopes[tlc.imports[&TLC::import_key("tests/modules/increment.tlc")].0.id].children
+                 .iter().filter(|(n,_,_,_)| n=="increment").count(), 1);
+
+   let error = tlc.check(None, r#"import $"triple.tlc"; triple(1);"#).unwrap_err();
+   assert_eq!(error.kind, "Import Error");
+
+   let mut tlc = TLC::new().search_path(vec!["tests/modules/lib".to_string()]);
+   tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   tlc.check(None, r#"import $"preludes/l1.tlc"; import $"triple.tlc"; triple(1);"#).unwrap();
+}
+
+#[test]
+fn check_prelude_imported_once() {
+   //the implicit prelude is checked once and exposed to every file that is imported after it
+   let mut tlc = TLC::new();
+   tlc.import_file(None, "tests/modules/increment.tlc").unwrap();
+   let rules = tlc.rules.len();
+   let rows = tlc.rows.len();
+   tlc.import_file(None, "tests/modules/double.tlc").unwrap();
+   tlc.import_file(None, "tests/modules/both.tlc").unwrap();
+   assert_eq!(tlc.rules.len(), rules);
+   assert!(tlc.rows.len() - rows < 100);
+   tlc.check(None, "both(1) + 1;").unwrap_err();
+   tlc.check(None, r#"import $"preludes/l1.tlc"; import $"tests/modules/both.tlc"; both(1) + 1;"#).unwrap();
+
+   //a prelude imported directly is reused by the implicit prelude and by import statements
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   let rules = tlc.rules.len();
+   tlc.import_str(Some(l1), "let a: I64 = 1;").unwrap();
+   tlc.import_str(None, r#"import $"preludes/l1.tlc"; let b: I64 = 2;"#).unwrap();
+   assert_eq!(tlc.rules.len(), rules);
+   assert_eq!(tlc.scopes[l1.id].children.iter().filter(|(n,_,_,_)| n=="+").count(), 4);
+}
+
+#[test]
+fn check_circular_imports() {
+   let mut tlc = TLC::new();
+   tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   let error = tlc.check(None, r#"import $"tests/modules/cycle_a.tlc";"#).unwrap_err();
+   assert_eq!(error.kind, "Import Error");
+   assert_eq!(error.rule, "Circular import: [string] -> tests/modules/cycle_a.tlc -> tests/modules/cycle_b.tlc -> tests/modules/cycle_a.tlc");
+   assert!(tlc.importing.is_empty());
+
+   let error = tlc.import_file(None, "tests/modules/cycle_a.tlc").unwrap_err();
+   assert_eq!(error.rule, "Circular import: tests/modules/cycle_a.tlc -> tests/modules/cycle_b.tlc -> tests/modules/cycle_a.tlc");
+}
@@ -0,0 +1,33 @@
+use lsts::tlc::TLC;
+use lsts::manifest::Manifest;
+use lsts::constant::Constant;
+
+#[test]
+fn check_manifest() {
+   assert_eq!(Manifest::find("tests/packages/app"), Some("tests/packages/app/lsts.toml".to_string()));
+   assert_eq!(Manifest::find("tests/packages/app/src"), None);
+   let m = Manifest::load("tests/packages/app/lsts.toml").unwrap();
+   assert_eq!(m.name, "app");
+   assert_eq!(m.prelude, Some("preludes/l1.tlc".to_string()));
+   assert!(!m.strict);
+   //dependencies are built first and the entry is checked last
+   assert_eq!(m.build_order().iter().map(|p| p.name.clone()).collect::<Vec<String>>(), vec!["mathlib","app"]);
+   assert!(m.files().last().unwrap().ends_with("main.tlc"));
+
+   let e = Manifest::load("tests/packages/cycle_a/lsts.toml").err().unwrap();
+   assert_eq!(e.kind, "Manifest Error");
+   assert!(e.rule.contains("cycle_a -> cycle_b -> cycle_a"));
+}
+
+#[test]
+fn check_packages() {
+   let mut tlc = TLC::new();
+   let m = Manifest::load("tests/packages/app/lsts.toml").unwrap();
+   tlc.import_package(&m).unwrap();
+   assert_eq!(tlc.reduce_package(&m).unwrap(), Constant::Literal("11".to_string()));
+   //the search path is restored after a package is checked
+   assert!(tlc.search_path.is_empty());
+
+   let m = Manifest::load("tests/packages/broken/lsts.toml").unwrap();
+   assert_eq!(tlc.import_package(&m).unwrap_err().kind, "Type Error");
+}
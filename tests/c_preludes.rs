@@ -0,0 +1,32 @@
+use lsts::tlc::TLC;
+use lsts::repl::Repl;
+
+#[test]
+fn check_embedded_prelude() {
+   let domain = "type Digit = /^[0-9]$/; let twice(x: Digit): Digit = x;";
+   let mut tlc = TLC::new().embed_prelude("preludes/domain.tlc", domain).prelude("preludes/domain.tlc");
+   tlc.check(None, "twice(1);").unwrap();
+   tlc.check(None, "twice(1) : Digit;").unwrap();
+   //the domain prelude replaces l1 entirely
+   tlc.check(None, "1 + 1;").unwrap_err();
+   tlc.check(None, r#"import $"preludes/domain.tlc"; twice(1);"#).unwrap();
+
+   //L1 can still be imported explicitly
+   //a prelude without regexes is used, because literals are typed by the first regex that accepts them
+   let flags = "type Flag = On | Off; let flip(x: Flag): Flag = x;";
+   let mut tlc = TLC::new().embed_prelude("preludes/flags.tlc", flags).prelude("preludes/flags.tlc");
+   tlc.check(None, "let double(x: I64): I64 = x + x;").unwrap_err();
+   tlc.check(None, r#"import $"preludes/l1.tlc"; let double(x: I64): I64 = x + x;"#).unwrap();
+}
+
+#[test]
+fn check_no_prelude() {
+   let mut tlc = TLC::new().no_prelude();
+   tlc.check(None, "type Digit = /^[0-9]$/; 1 : Digit;").unwrap();
+   tlc.check(None, "type Digit = /^[0-9]$/; 1 + 1;").unwrap_err();
+
+   let mut repl = Repl::with_tlc(TLC::new().no_prelude());
+   repl.eval("type Digit = /^[0-9]$/; let d: Digit = 1;").unwrap();
+   repl.eval("d;").unwrap();
+   repl.eval("d + d;").unwrap_err();
+}
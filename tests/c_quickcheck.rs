@@ -0,0 +1,70 @@
+use lsts::tlc::{TLC,TypeRule,ForallRule};
+use lsts::constant::Constant;
+use lsts::typ::Type;
+
+fn last_forall(tlc: &TLC) -> ForallRule {
+   tlc.rules.iter().rev().find_map(|r| match r {
+      TypeRule::Forall(fr) => Some(fr.clone()),
+      _ => None,
+   }).unwrap()
+}
+
+#[test]
+fn check_falsify() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.import_str(Some(l1), "forall x:Odd. Even = x + 1;").unwrap();
+   let fr = last_forall(&tlc);
+   assert!(tlc.falsify(&fr).unwrap().is_none());
+
+   tlc.import_str(Some(l1), "forall x:Odd. Even = x + 2;").unwrap();
+   let fr = last_forall(&tlc);
+   let ce = tlc.falsify(&fr).unwrap().unwrap();
+   assert_eq!(ce.bindings, vec![("x".to_string(), Constant::Literal("1".to_string()))]);
+   assert_eq!(ce.value, Constant::Literal("3".to_string()));
+
+   tlc.import_str(Some(l1), "forall x:Odd, y:Even. Even = x + y;").unwrap();
+   let fr = last_forall(&tlc);
+   assert!(tlc.falsify(&fr).unwrap().is_some());
+
+   tlc.import_str(Some(l1), "forall x:I64. U64 = x + 1;").unwrap();
+   let fr = last_forall(&tlc);
+   assert!(tlc.falsify(&fr).unwrap().is_some());
+
+   tlc.import_str(Some(l1), "forall b:Boolean. U8 = b;").unwrap();
+   let fr = last_forall(&tlc);
+   assert!(tlc.falsify(&fr).unwrap().is_none());
+
+   //every parameter is searched on its smallest values, not only the last one
+   tlc.import_str(Some(l1), "type Digit = /^[0-9]$/; forall x:U64, y:U64. Digit = x;").unwrap();
+   let fr = last_forall(&tlc);
+   let ce = tlc.falsify(&fr).unwrap().unwrap();
+   assert_eq!(ce.bindings, vec![("x".to_string(), Constant::Literal("00".to_string())), ("y".to_string(), Constant::Literal("0".to_string()))]);
+}
+
+#[test]
+fn check_falsify_restores() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   tlc.import_str(Some(l1), "forall x:Odd. Even = x + 1;").unwrap();
+   let fr = last_forall(&tlc);
+
+   //the scopes and rows of the search are dropped afterwards
+   let before = (tlc.rows.len(), tlc.scopes.len());
+   assert!(tlc.falsify(&fr).unwrap().is_none());
+   tlc.generate(&Type::Named("Odd".to_string(),Vec::new()), 10).unwrap();
+   assert_eq!((tlc.rows.len(), tlc.scopes.len()), before);
+}
+
+#[test]
+fn check_generate() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   tlc.import_str(Some(l1), "type Color = Red | Green;").unwrap();
+
+   let colors = tlc.generate(&Type::Named("Color".to_string(),Vec::new()), 8).unwrap();
+   assert_eq!(colors, vec![Constant::Literal("Red".to_string()), Constant::Literal("Green".to_string())]);
+   let odds = tlc.generate(&Type::Named("Odd".to_string(),Vec::new()), 10).unwrap();
+   assert_eq!(odds.iter().map(|c| format!("{:?}",c)).collect::<Vec<String>>(), vec!["1","3","5","7","9"]);
+}
@@ -0,0 +1,43 @@
+use lsts::tlc::TLC;
+
+#[test]
+fn check_failure_leaves_no_trace() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   let hints = tlc.hints.len();
+   let constructors = tlc.constructors.len();
+   let rules = tlc.rules.len();
+   let children = tlc.scopes[l1.id].children.len();
+
+   tlc.check(Some(l1), "forall @leaky x:Odd. Even = x + 1; type Color = Red | Green; let c: U64 = -1;").unwrap_err();
+   assert!(!tlc.hints.contains_key("leaky"));
+   assert!(!tlc.constructors.contains_key("Red"));
+   assert_eq!(tlc.hints.len(), hints);
+   assert_eq!(tlc.constructors.len(), constructors);
+   assert_eq!(tlc.rules.len(), rules);
+   assert_eq!(tlc.scopes[l1.id].children.len(), children);
+
+   tlc.check(Some(l1), "forall @leaky x:Odd. Even = x + 1; type Color = Red | Green;").unwrap();
+   assert!(!tlc.hints.contains_key("leaky"));
+   assert!(!tlc.constructors.contains_key("Red"));
+}
+
+#[test]
+fn check_snapshot_restore() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+   let snapshot = tlc.snapshot();
+   let rows = tlc.rows.len();
+
+   tlc.check_str(Some(l1), "type Color = Red | Green; let c: Color = Red;").unwrap();
+   assert!(tlc.constructors.contains_key("Red"));
+   tlc.restore(snapshot.clone());
+   assert!(!tlc.constructors.contains_key("Red"));
+   assert_eq!(tlc.rows.len(), rows);
+
+   //a snapshot can be restored more than once
+   tlc.check_str(Some(l1), "type Shade = Dark | Light;").unwrap();
+   tlc.restore(snapshot);
+   assert!(!tlc.constructors.contains_key("Dark"));
+   tlc.check(Some(l1), "let c: Color = Red;").unwrap_err();
+}
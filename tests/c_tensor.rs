@@ -0,0 +1,56 @@
+use lsts::tlc::TLC;
+
+#[test]
+fn check_tensor_shapes() {
+   let mut tlc = TLC::new();
+   let t = tlc.import_file(None, "preludes/tensor.tlc").unwrap();
+
+   tlc.check(Some(t), "let a:F64[2][3]; let b:F64[3][4]; matmul(a,b): F64[2][4];").unwrap();
+   tlc.check(Some(t), "let a:F64[2][3]; let b:F64[3][4]; matmul(a,b): F64[4][2];").unwrap_err();
+   tlc.check(Some(t), "let a:F64[2][3]; let b:F64[4][4]; matmul(a,b);").unwrap_err();
+   tlc.check(Some(t), "let a:F64[2][3]; transpose(a): F64[3][2];").unwrap();
+   tlc.check(Some(t), "let a:F64[2][3]; transpose(a): F64[2][3];").unwrap_err();
+   tlc.check(Some(t), "let a:F64[3]; let b:F64[3]; dot(a,b): F64;").unwrap();
+   tlc.check(Some(t), "let a:F64[3]; let b:F64[2]; dot(a,b);").unwrap_err();
+
+   //elementwise operations and broadcasting
+   tlc.check(Some(t), "let a:F64[3]; let b:F64[3]; a + b: F64[3];").unwrap();
+   tlc.check(Some(t), "let a:F64[3]; let b:F64[2]; a + b;").unwrap_err();
+   tlc.check(Some(t), "let a:F64[2][3]; let b:F64[2][3]; a * b: F64[2][3];").unwrap();
+   tlc.check(Some(t), "let a:F64[2][3]; let b:F64[3][2]; a * b;").unwrap_err();
+   tlc.check(Some(t), "let a:F64[2][3]; 2.0 * a: F64[2][3];").unwrap();
+   tlc.check(Some(t), "let a:F64[2][3]; a * 2.0: F64[2][3];").unwrap();
+   tlc.check(Some(t), "let a:F64[3]; 2.0 + a: F64[3];").unwrap();
+   tlc.check(Some(t), "let a:F64[3]; a - 2.0: F64[3];").unwrap();
+   tlc.check(Some(t), "let a:F64[3]; a - 2.0: F64[2];").unwrap_err();
+   tlc.check(Some(t), "let a:F64[2][3]; let b:F64[3]; a + b: F64[2][3];").unwrap();
+   tlc.check(Some(t), "let a:F64[2][3]; let b:F64[2]; a + b;").unwrap_err();
+   tlc.check(Some(t), "1 + 2;").unwrap();
+
+   //length variables are shared between the parameters and the result of a function
+   tlc.check(Some(t), "let gram(a: F64[m][n]): F64[n][n] = matmul(transpose(a), a); let b: F64[2][3]; gram(b): F64[3][3];").unwrap();
+   tlc.check(Some(t), "let square(a: F64[m][n]): F64[m][m] = matmul(a, a);").unwrap_err();
+
+   //lengths are natural numbers or variables named in lowercase
+   tlc.check(Some(t), "let a:F64[N];").unwrap_err();
+   tlc.check(Some(t), "let a:F64[_n];").unwrap_err();
+   tlc.check(Some(t), "let a:F64[1.5];").unwrap_err();
+   tlc.check(Some(t), "let a:F64[n+1];").unwrap();
+}
+
+#[test]
+fn check_tensor_reduce() {
+   let mut tlc = TLC::new();
+   let t = tlc.import_file(None, "preludes/tensor.tlc").unwrap();
+
+   tlc.check(Some(t), "matmul(((1.0,2.0),(3.0,4.0)), ((5.0,),(6.0,)))[1][0] @reduce :[39];").unwrap();
+   tlc.check(Some(t), "matmul(((1.0,2.0),(3.0,4.0)), ((5.0,),(6.0,)))[1][0] @reduce :[40];").unwrap_err();
+   tlc.check(Some(t), "transpose(((1.0,2.0),(3.0,4.0)))[0][1] @reduce :[3];").unwrap();
+   tlc.check(Some(t), "dot((1.0,2.0),(3.0,4.0)) @reduce :[11];").unwrap();
+   tlc.check(Some(t), "((1.0,2.0) - (3.0,4.0))[0] @reduce :[-2];").unwrap();
+   tlc.check(Some(t), "(2.0 * ((1.0,2.0),(3.0,4.0)))[1][1] @reduce :[8];").unwrap();
+   tlc.check(Some(t), "(((1.0,2.0),(3.0,4.0)) * 2.0)[1][1] @reduce :[8];").unwrap();
+   tlc.check(Some(t), "(10.0 - (1.0,2.0))[1] @reduce :[8];").unwrap();
+   tlc.check(Some(t), "(((1.0,2.0),(3.0,4.0)) + (10.0,20.0))[1][0] @reduce :[13];").unwrap();
+   tlc.check(Some(t), "(((1.0,2.0),(3.0,4.0)) + (10.0,20.0))[0][1] @reduce :[22];").unwrap();
+}
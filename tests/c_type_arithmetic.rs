@@ -0,0 +1,74 @@
+use lsts::tlc::TLC;
+use lsts::constant::Constant;
+
+fn c(s: &str) -> Constant {
+   Constant::Literal(s.to_string())
+}
+
+#[test]
+fn check_constant_normalization() {
+   assert_eq!( Constant::op("+", c("2"), c("2")).normalize(), c("4") );
+   assert_eq!( Constant::op("*", c("3"), c("-2")).normalize(), c("-6") );
+   assert_eq!( Constant::op("-", c("n"), c("n")).normalize(), c("0") );
+   assert_eq!( Constant::op("+", c("n"), c("m")).normalize(), Constant::op("+", c("m"), c("n")).normalize() );
+   assert_eq!( Constant::op("+", c("n"), c("n")).normalize(), Constant::op("*", c("2"), c("n")).normalize() );
+   assert_ne!( Constant::op("+", c("n"), c("n")).normalize(), Constant::op("+", c("n"), c("m")).normalize() );
+   assert_eq!( format!("{:?}", Constant::op("-", c("n"), Constant::op("+", c("m"), c("1")))), "n-(m+1)" );
+   assert_eq!( format!("{:?}", Constant::op("+", c("1"), c("n")).normalize()), "n+1" );
+   assert_eq!( format!("{:?}", Constant::op("-", c("1"), c("n")).normalize()), "-1*n+1" );
+   //bare literals are left as written
+   assert_eq!( c("00").normalize(), c("00") );
+   //arithmetic that overflows is left as written
+   assert_eq!( format!("{:?}", Constant::op("+", c("9223372036854775807"), c("1")).normalize()), "9223372036854775807+1" );
+   assert_eq!( format!("{:?}", Constant::op("*", c("4611686018427387904"), c("4")).normalize()), "4611686018427387904*4" );
+}
+
+#[test]
+fn check_type_arithmetic() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(l1), "(1,2,3,4): I64[2+2];").unwrap();
+   tlc.check(Some(l1), "(1,2,3,4): I64[2*2];").unwrap();
+   tlc.check(Some(l1), "(1,2,3,4): I64[2*2-1];").unwrap_err();
+   tlc.check(Some(l1), "((1,2,3,4): I64[4]): I64[(1+1)*2];").unwrap();
+
+   tlc.check(Some(l1), "let app(a: I64[n], b: I64[m]): I64[n+m] = a.concat(b);").unwrap();
+   tlc.check(Some(l1), "let app(a: I64[n], b: I64[m]): I64[m+n] = a.concat(b);").unwrap();
+   tlc.check(Some(l1), "let app(a: I64[n], b: I64[m]): I64[n+n] = a.concat(b);").unwrap_err();
+   tlc.check(Some(l1), "let app(a: I64[n]): I64[n+1] = a;").unwrap_err();
+   tlc.check(Some(l1), "let twice(a: I64[n]): I64[2*n] = a.concat(a);").unwrap();
+
+   tlc.check(Some(l1), "(1,2).concat((3,4,5)): I64[5];").unwrap();
+   tlc.check(Some(l1), "(1,2).concat((3,4,5)): I64[4];").unwrap_err();
+   tlc.check(Some(l1), "(1,2).concat((3,4,5))[4] @reduce :[5];").unwrap();
+   tlc.check(Some(l1), "(1,2).concat((3,4,5))[5];").unwrap_err();
+   tlc.check(Some(l1), "(1,2).concat((3,4,5)).length @reduce :[5];").unwrap();
+   tlc.check(Some(l1), "range(3).concat(range(2)).length @reduce :[5];").unwrap();
+   tlc.check(Some(l1), "let len(a: I64[n], b: I64[m]): I64 = a.concat(b).length; len((1,2),(3,)) @reduce :[3];").unwrap();
+   tlc.check(Some(l1), "let twice(a: I64[n]): I64[n+n] = a.concat(a); twice((1,2))[3] @reduce :[2];").unwrap();
+}
+
+#[test]
+fn check_length_solving() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(l1), "let f(a: I64[n+1]): I64[n]; f((1,2,3)): I64[2];").unwrap();
+   tlc.check(Some(l1), "let f(a: I64[n+1]): I64[n]; f((1,2,3)): I64[3];").unwrap_err();
+   tlc.check(Some(l1), "let f(a: I64[n-1]): I64[n]; f((1,2)): I64[3];").unwrap();
+   tlc.check(Some(l1), "let f(a: I64[2*n]): I64[n]; f((1,2,3,4)): I64[2];").unwrap();
+   tlc.check(Some(l1), "let f(a: I64[2*n]): I64[n]; f((1,2,3));").unwrap_err();
+   tlc.check(Some(l1), "let f(a: I64[n], b: I64[n+1]): I64[n] = a; f((1,2),(1,2,3));").unwrap();
+   tlc.check(Some(l1), "let f(a: I64[n], b: I64[n+1]): I64[n] = a; f((1,2),(1,2));").unwrap_err();
+
+   //lengths are rigid in the body of a function, so they are not solved for there
+   tlc.check(Some(l1), "let f(a: I64[n+1]): I64[n] = a;").unwrap_err();
+   tlc.check(Some(l1), "let f(a: I64[n-1]): I64[n] = a;").unwrap_err();
+   tlc.check(Some(l1), "let f(a: I64[2*n]): I64[n] = a;").unwrap_err();
+   tlc.check(Some(l1), "let f(a: I64[m]): I64[n] = a;").unwrap_err();
+
+   //lengths that overflow are not evaluated
+   tlc.check(Some(l1), "let a: I64[9223372036854775807+1];").unwrap();
+   tlc.check(Some(l1), "let a: I64[4611686018427387904*4];").unwrap();
+}
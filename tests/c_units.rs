@@ -0,0 +1,74 @@
+use std::rc::Rc;
+use lsts::tlc::TLC;
+use lsts::typ::Type;
+use lsts::token::Span;
+
+fn si(tlc: &mut TLC) -> lsts::scope::ScopeId {
+   tlc.import_str(None, "import $\"preludes/l1.tlc\"; import $\"preludes/si.tlc\";").unwrap()
+}
+
+#[test]
+fn check_unit_literals() {
+   let mut tlc = TLC::new();
+   let si = si(&mut tlc);
+
+   //a literal that is only given a unit keeps the type of its regex
+   tlc.check(Some(si), "let v: Metre = 1.5; v: Metre;").unwrap();
+   tlc.check(Some(si), "let v: Metre/Second = 299792458; v: Metre/Second;").unwrap();
+   tlc.check(Some(si), "let v: Joule*Second = 0.000000000000000000000000000000000662607015; v: Newton*Metre*Second;").unwrap();
+   tlc.check(Some(si), "let v: Metre = 1.5; v: Second;").unwrap_err();
+   tlc.check(Some(si), "let v: () = 1;").unwrap_err();
+}
+
+#[test]
+fn check_unit_casts() {
+   let mut tlc = TLC::new();
+   let si = si(&mut tlc);
+
+   //casts follow the shortest chain of forall conversions of the Unit kind
+   tlc.check(Some(si), "let v: Metre = 1.0; v as Feet;").unwrap();
+   tlc.check(Some(si), "let v: Metre = 1.0; v as Kilo<Metre>;").unwrap();
+   tlc.check(Some(si), "let v: Feet = 1.0; (v as Mile): Mile;").unwrap();
+   tlc.check(Some(si), "let v: Kilo<Metre> = 1.0; (v as Feet): Feet;").unwrap();
+   tlc.check(Some(si), "let v: Feet = 1.0; (v as Mile): Feet;").unwrap_err();
+   tlc.check(Some(si), "let v: Feet = 1.0; v as Second;").unwrap_err();
+
+   //components of Products and Ratios are converted separately
+   tlc.check(Some(si), "let v: Kilo<Metre>/Second = 1.0; (v as Mile/Minute): Mile/Minute;").unwrap();
+   tlc.check(Some(si), "let v: Kilo<Metre>/Second = 1.0; v as Metre/Hour;").unwrap();
+   tlc.check(Some(si), "let v: Metre^2 = 1.0; v as Feet*Feet;").unwrap();
+   tlc.check(Some(si), "let v: Hertz = 1.0; v as ()/Minute;").unwrap();
+   tlc.check(Some(si), "let v: Joule = 1.0; v as Kilo<Gram>*Feet^2/Second^2;").unwrap();
+   tlc.check(Some(si), "let v: Kilo<Metre>/Second = 1.0; v as Metre;").unwrap_err();
+   tlc.check(Some(si), "let v: Kilo<Metre>/Second = 1.0; v as Kelvin/Second;").unwrap_err();
+   tlc.check(Some(si), "let v: Metre^2 = 1.0; v as Feet;").unwrap_err();
+
+   //unit casts are checked but not reduced
+   let error = tlc.check(Some(si), "let v: Kilo<Metre> = 1.0; (v as Metre) @reduce;").unwrap_err();
+   assert_eq!(error.kind, "Runtime");
+   assert!(error.rule.starts_with("unit cast can not be reduced"));
+   tlc.check(Some(si), "let v: Metre = 1.0; (v as Metre) @reduce;").unwrap();
+
+   tlc.check_file(None, "examples/si.tlc").unwrap();
+}
+
+#[test]
+fn check_cast_normal() {
+   let mut tlc = TLC::new();
+   let _si = si(&mut tlc);
+   let span = Span {
+      filename: Rc::new("".to_string()),
+      offset_start: 0,
+      offset_end: 0,
+      linecol_start: (1,1),
+      linecol_end: (1,1),
+   };
+   let named = |n: &str| Type::Named(n.to_string(),Vec::new());
+   let kilo = |t: Type| Type::Named("Kilo".to_string(),vec![t]);
+   let ratio = |n: Type, d: Type| Type::Ratio(Box::new(n),Box::new(d));
+
+   assert_eq!( tlc.cast_normal(&named("Feet"), &span).unwrap(), named("Metre") );
+   assert_eq!( tlc.cast_normal(&kilo(named("Metre")), &span).unwrap(), named("Metre") );
+   assert_eq!( tlc.cast_normal(&ratio(named("Mile"),named("Hour")), &span).unwrap(), ratio(named("Metre"),named("Second")) );
+   assert_eq!( tlc.cast_normal(&named("Second"), &span).unwrap(), named("Second") );
+}
@@ -1,4 +1,3 @@
-/* TODO FIXME update to use L1IR for @reduce
 use lsts::tlc::TLC;
 
 #[test]
@@ -43,4 +42,3 @@ fn destructure_literal() {
    tlc.check(Some(l1), r#"match (2,12) { (literal [0-9]xs, literal yp [0-9]ys) => (literal xs yp ys) } : Integer @reduce : [212];"#).unwrap();
    tlc.check(Some(l1), r#"match (25,125) { (literal xp [0-9]xs, literal yp [0-9]ys) => (literal xp xs yp ys) } : Integer @reduce : [25125];"#).unwrap();
 }
-*/
@@ -1,10 +1,9 @@
-/* TODO FIXME update to use @reduce and L1 prelude
 use lsts::tlc::TLC;
 
 #[test]
 fn check_precondition() {
    let mut tlc = TLC::new();
-   let si = tlc.import_file(None, "preludes/si.tlc").unwrap();
+   let si = tlc.import_file(None, "preludes/l1.tlc").unwrap();
 
    tlc.check(Some(si), "let a:Odd = 0;").unwrap_err();
    tlc.check(Some(si), "let a:Odd = 1;").unwrap();
@@ -18,19 +17,19 @@ fn check_precondition() {
 #[test]
 fn check_postcondition() {
    let mut tlc = TLC::new();
-   let si = tlc.import_file(None, "preludes/si.tlc").unwrap();
+   let si = tlc.import_file(None, "preludes/l1.tlc").unwrap();
 
-   tlc.check(Some(si), "let a:Odd; a%2:[0]").unwrap_err();
-   tlc.check(Some(si), "let a:Odd; a%2:[1]").unwrap();
+   tlc.check(Some(si), "let a:Odd; a%2:[0];").unwrap_err();
+   tlc.check(Some(si), "let a:Odd; a%2:[1];").unwrap();
 
-   tlc.check(Some(si), "let a:Even; a%2:[0]").unwrap();
-   tlc.check(Some(si), "let a:Even; a%2:[1]").unwrap_err();
+   tlc.check(Some(si), "let a:Even; a%2:[0];").unwrap();
+   tlc.check(Some(si), "let a:Even; a%2:[1];").unwrap_err();
 }
 
 #[test]
 fn check_proof_lines_precondition() {
    let mut tlc = TLC::new();
-   let si = tlc.import_file(None, "preludes/si.tlc").unwrap();
+   let si = tlc.import_file(None, "preludes/l1.tlc").unwrap();
 
    tlc.check(Some(si), "let a:Prime = 0;").unwrap_err();
    tlc.check(Some(si), "let a:Prime = 1;").unwrap_err();
@@ -39,20 +38,37 @@ fn check_proof_lines_precondition() {
    tlc.check(Some(si), "let a:Prime = 4;").unwrap_err();
    tlc.check(Some(si), "let a:Prime = 5;").unwrap();
    tlc.check(Some(si), "let a:Prime = 6;").unwrap_err();
+   tlc.check(Some(si), "let a:Prime = 9973;").unwrap();
+   tlc.check(Some(si), "let a:Prime = 9971;").unwrap_err();
+
+   //divisors are only searched up to the square root of the value
+   tlc.check(Some(si), "let a:Prime = 10001;").unwrap_err();
+   tlc.check(Some(si), "let a:Prime = 10007;").unwrap();
+   tlc.check(Some(si), "let a:Prime = 99990001;").unwrap();
+   let error = tlc.check(Some(si), "let a:Prime = 1000000007;").unwrap_err();
+   assert_eq!(error.kind, "Precondition");
+   assert!(error.rule.starts_with("undecidable invariant of Prime"));
+   assert_eq!(error.span.linecol_start, (1,15));
+
+   //a value that overflows the invariant is reported at the value
+   let error = tlc.check(Some(si), "let a: Odd = 99999999999999999999;").unwrap_err();
+   assert_eq!(error.span.filename.as_str(), "[string]");
+   assert_eq!(error.span.linecol_start, (1,14));
+   assert_eq!(error.labels()[0].span.filename.as_str(), "preludes/l1.tlc");
 }
-*/
 
-/*
 #[test]
 fn check_proof_lines_postcondition() {
    let mut tlc = TLC::new();
-   let si = tlc.import_file(None, "preludes/si.tlc").unwrap();
-
-   tlc.check(Some(si), "let a:Prime; a>1: [True]").unwrap();
-   tlc.check(Some(si), "let a:Prime; a>2: [True]").unwrap_err(); //maybe true, but not satisfied here
-   tlc.check(Some(si), "let a:Prime; a%2!=0: [True]").unwrap();
-   tlc.check(Some(si), "let a:Prime; a%4!=0: [True]").unwrap();
-   tlc.check(Some(si), "let a:Prime; a%2!=1: [False]").unwrap_err(); //maybe false, but not satisfied here
-   tlc.check(Some(si), "let a:Prime; a%4!=1: [False]").unwrap_err();
+   let si = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(si), "let a:Prime; a>1: [True];").unwrap();
+   tlc.check(Some(si), "let a:Prime; a>2: [True];").unwrap_err(); //maybe true, but not satisfied here
+   tlc.check(Some(si), "let a:Prime; a%2!=0: [True];").unwrap_err(); //false, 2 is Prime
+   //TODO FIXME true, but proving it needs cases on self: a > self - 1 is only refuted when self > 4
+   //tlc.check(Some(si), "let a:Prime; a%4!=0: [True];").unwrap();
+   tlc.check(Some(si), "let f(a:Prime): [True] = a%2!=0;").unwrap_err();
+   tlc.check(Some(si), "((2:Prime)%2!=0) @reduce : [0];").unwrap();
+   tlc.check(Some(si), "let a:Prime; a%2!=1: [False];").unwrap_err(); //maybe false, but not satisfied here
+   tlc.check(Some(si), "let a:Prime; a%4!=1: [False];").unwrap_err();
 }
-*/
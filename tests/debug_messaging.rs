@@ -1,4 +1,8 @@
+use std::collections::HashMap;
 use lsts::tlc::TLC;
+use lsts::term::{Term,LetTerm};
+use lsts::typ::Type;
+use lsts::kind::Kind;
 
 #[test]
 fn check_lookup_failure_messaging() {
@@ -7,3 +11,121 @@ fn check_lookup_failure_messaging() {
 
    let _error = tlc.check(Some(l1), "x:Number").unwrap_err();
 }
+
+#[test]
+fn check_accumulated_diagnostics() {
+   let mut tlc = TLC::new().accumulate();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   let src = "let a: I64 = 1;\nlet b: U64 = a;\nlet c: Odd = 2;\nlet d: I64 = 4;";
+   tlc.check(Some(l1), src).unwrap_err();
+   assert_eq!(tlc.diagnostics.len(), 2);
+   assert_eq!(tlc.diagnostics[0].code, "E0003");
+   assert_eq!(tlc.diagnostics[1].code, "E0004");
+
+   let rendered = tlc.diagnostics[0].render(src);
+   assert!(rendered.contains("--> [string]:2:14"));
+   assert!(rendered.contains("2 | let b: U64 = a;\n  |              ^\n"));
+
+   tlc.diagnostics.clear();
+   tlc.check(Some(l1), "let a: I64 = 1;").unwrap();
+   assert_eq!(tlc.diagnostics.len(), 0);
+}
+
+#[test]
+fn check_diagnostic_underlines_whole_term() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   let src = "let a: I64 = 1;\nlet b: U64 = a + a;";
+   let rendered = tlc.check(Some(l1), src).unwrap_err().diagnostic().render(src);
+   assert!(rendered.contains("2 | let b: U64 = a + a;\n  |              ^^^^^\n"));
+
+   let src = "(1+1): String;";
+   let rendered = tlc.check(Some(l1), src).unwrap_err().diagnostic().render(src);
+   assert!(rendered.contains("1 | (1+1): String;\n  |  ^^^\n"));
+}
+
+#[test]
+fn check_malformed_programs_are_errors() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   let error = tlc.check(Some(l1), "type A = /^[$/;").unwrap_err();
+   assert_eq!(error.kind, "Tokenization Error");
+   let error = tlc.check(Some(l1), "for 1 in range(0,3,1) yield 1;").unwrap_err();
+   assert_eq!(error.kind, "Parse Error");
+   let error = tlc.check(Some(l1), "(fn(x:I64) = x) @reduce;").unwrap_err();
+   assert_eq!(error.kind, "Runtime");
+   let error = tlc.check(Some(l1), "let f(x:I64)(y:I64):I64 = x; f(1) @reduce;").unwrap_err();
+   assert_eq!(error.kind, "Runtime");
+   let error = tlc.check(Some(l1), "99999999999999999999 + 1 @reduce;").unwrap_err();
+   assert_eq!(error.kind, "Runtime");
+   let error = tlc.check(Some(l1), "(1:I64) / 0 @reduce;").unwrap_err();
+   assert_eq!(error.kind, "Runtime");
+   let error = lsts::token::tokenize_bytes(&mut tlc, "[bytes]", b"type A = /^\xff$/;".to_vec()).err().unwrap();
+   assert_eq!(error.kind, "Tokenization Error");
+
+   tlc.check(Some(l1), "1 + 2 @reduce;").unwrap();
+}
+
+#[test]
+fn check_unsupported_terms_are_errors() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   //hints do not yet match these forms of term
+   for (pattern,term) in [("(x: I64)","(1: I64)"), ("(x as I64)","(1 as I64)"), ("(x @reduce)","(1 @reduce)"),
+                          ("(fn(y:I64) = x)","(fn(y:I64) = 1)"), ("True","True")] {
+      let error = tlc.check(Some(l1), &format!("forall @h x:I64. I64 = {};\n{} @h;", pattern, term)).unwrap_err();
+      assert_eq!(error.kind, "Type Error");
+      assert_eq!(error.span.linecol_start.0, 2);
+   }
+   let span = tlc.rows[0].span.clone();
+   let block = tlc.push_term(Term::Block(l1, Vec::new()), &span);
+   tlc.typeck_hint(&mut HashMap::new(), &Some(l1), &"h".to_string(), block, block).unwrap_err();
+   let binding = tlc.push_term(Term::Let(LetTerm {
+      is_extern: false, scope: l1, name: "a".to_string(), parameters: Vec::new(),
+      body: None, rtype: Type::Any, rkind: Kind::Nil,
+   }), &span);
+   tlc.typeck_hint(&mut HashMap::new(), &Some(l1), &"h".to_string(), binding, binding).unwrap_err();
+
+   //polymorphic bindings are not yet specialized through every form of term
+   let error = tlc.check(Some(l1), "let f(x:X):X = match x { _ => x };\nf(1:I64);").unwrap_err();
+   assert_eq!(error.kind, "Type Error");
+   let error = tlc.check(Some(l1), "let f(x:X):X = x @reduce;\nf(1:I64);").unwrap_err();
+   assert_eq!(error.kind, "Type Error");
+
+   //only some types have a runtime representation
+   assert_eq!(Type::Named("U64".to_string(),Vec::new()).datatype(&span).unwrap(), "U64");
+   Type::Any.datatype(&span).unwrap_err();
+   Type::And(vec![Type::Named("Odd".to_string(),Vec::new())]).datatype(&span).unwrap_err();
+}
+
+#[test]
+fn check_ambiguous_overload_messaging() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   let src = "let f(x:I64):String = \"a\";\nlet f(x:I64):I64 = x;\nf(1) @reduce;";
+   let error = tlc.check(Some(l1), src).unwrap_err();
+   assert_eq!(error.kind, "Ambiguous Overload");
+   assert_eq!(error.code(), "E0006");
+   let candidates = error.labels().iter().map(|l| (l.message.clone(),l.span.linecol_start)).collect::<Vec<_>>();
+   assert_eq!(candidates, vec![
+      ("candidate f : ((I64))->(String)".to_string(),(1,1)),
+      ("candidate f : ((I64))->(I64)".to_string(),(2,1)),
+   ]);
+   let rendered = error.diagnostic().render(src);
+   assert!(rendered.contains("1 | let f(x:I64):String = \"a\";\n  | --- candidate f : ((I64))->(String)\n"));
+   assert!(rendered.contains("2 | let f(x:I64):I64 = x;\n  | --- candidate f : ((I64))->(I64)\n"));
+   assert!(rendered.contains("= note: ascribe the result to choose a candidate, e.g. f(..) : String"));
+   tlc.check(Some(l1), "let f(x:I64):String = \"a\";\nlet f(x:I64):I64 = x;\n(f(1) : String) @reduce;").unwrap();
+   //a range that is included in the other by its regex is more specific
+   tlc.check(Some(l1), "let f(x:I64):U64 = 1;\nlet f(x:I64):I64 = x;\nf(1) @reduce;").unwrap();
+
+   //U64 implies I64, so both candidates match, and neither is more specific on both sides of the arrow
+   let error = tlc.check(Some(l1), "let f(x:I64):U64 = 1; let f(x:U64):I64 = 2; f(1:U64) @reduce;").unwrap_err();
+   assert_eq!(error.kind, "Ambiguous Overload");
+   tlc.check(Some(l1), "let f(x:I64):U64 = 1; let f(x:U64):I64 = 2; (f(1:U64) : U64) @reduce;").unwrap();
+}
@@ -1,58 +1,98 @@
-/* TODO FIXME: use homogenous tuples and L1 prelude
 use lsts::tlc::TLC;
 
 #[test]
 fn check_tensor_syntax() {
    let mut tlc = TLC::new();
-   let si = tlc.import_file(None, "preludes/si.tlc").unwrap();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
 
-   tlc.check(Some(si), "let a:Number[];").unwrap();
-   tlc.check(Some(si), "let a:Number[1];").unwrap();
-   tlc.check(Some(si), "let a:Number[1][2];").unwrap();
-   tlc.check(Some(si), "let a:Number[1][];").unwrap();
-   tlc.check(Some(si), "let a:Number[][2];").unwrap();
+   tlc.check(Some(l1), "let a:I64[];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1][2];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1][];").unwrap();
+   tlc.check(Some(l1), "let a:I64[][2];").unwrap();
 }
 
 #[test]
 fn check_tensor_sugar() {
    let mut tlc = TLC::new();
-   let si = tlc.import_file(None, "preludes/si.tlc").unwrap();
-
-   tlc.check(Some(si), "let a:Number[]; a: Tensor<Number,?>;").unwrap();
-   tlc.check(Some(si), "let a:Number[]; a: Tensor<Number,[1]>;").unwrap_err();
-   tlc.check(Some(si), "let a:Number[1]; a: Tensor<Number,[1]>;").unwrap();
-   tlc.check(Some(si), "let a:Number[1]; a: Tensor<Number,[2]>;").unwrap_err();
-   tlc.check(Some(si), "let a:Number[1][2]; a: Tensor<Tensor<Number,[2]>,[1]>;").unwrap();
-   tlc.check(Some(si), "let a:Number[1][2]; a: Tensor<Tensor<Number,[1]>,[2]>;").unwrap_err();
-   tlc.check(Some(si), "let a:Number[1][]; a: Tensor<Tensor<Number,?>,[1]>;").unwrap();
-   tlc.check(Some(si), "let a:Number[1][]; a: Tensor<Tensor<Number,?>,[2]>;").unwrap_err();
-   tlc.check(Some(si), "let a:Number[][2]; a: Tensor<Tensor<Number,[2]>,?>;").unwrap();
-   tlc.check(Some(si), "let a:Number[][2]; a: Tensor<Tensor<Number,[1]>,?>;").unwrap_err();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(l1), "let a:I64[]; a: I64[];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1]; a: I64[1];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1]; a: I64[2];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[1][2]; a: I64[1][2];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1][2]; a: I64[2][1];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[1][]; a: I64[1][];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1][]; a: I64[2][];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[][2]; a: I64[][2];").unwrap();
+   tlc.check(Some(l1), "let a:I64[][2]; a: I64[][1];").unwrap_err();
+
+   //T[n] implies T[]
+   tlc.check(Some(l1), "let a:I64[1]; a: I64[];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1][2]; a: I64[1][];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1][2]; a: I64[][];").unwrap();
 }
 
 #[test]
 fn check_tensor_covariance() {
    let mut tlc = TLC::new();
-   let si = tlc.import_file(None, "preludes/si.tlc").unwrap();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
 
-   tlc.check(Some(si), "let a:Number[]; a: Tensor<Number,[1]>").unwrap_err();
-   tlc.check(Some(si), "let a:Number[][]; a: Tensor<Tensor<Number,[1]>,?>;").unwrap_err();
-   tlc.check(Some(si), "let a:Number[][]; a: Tensor<Tensor<Number,?>,[1]>;").unwrap_err();
-   tlc.check(Some(si), "let a:Number[1][]; a: Tensor<Tensor<Number,[1]>,?>;").unwrap_err();
-   tlc.check(Some(si), "let a:Number[][1]; a: Tensor<Tensor<Number,?>,[1]>;").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[]; a: I64[1];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[][]; a: I64[][1];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[][]; a: I64[1][];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[1][]; a: I64[1][1];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[][1]; a: I64[1][1];").unwrap_err();
 }
 
 #[test]
 fn check_tensor_invariants() {
    let mut tlc = TLC::new();
-   let si = tlc.import_file(None, "preludes/si.tlc").unwrap();
-
-   tlc.check(Some(si), "let a:Number[1]; a.length: [1];").unwrap();
-   tlc.check(Some(si), "let a:Number[2]; a.length: [2];").unwrap();
-   tlc.check(Some(si), "let a:Number[2]; a.length: [3];").unwrap_err();
-   tlc.check(Some(si), "let a:Number[1]; a.length==0: [True];").unwrap_err();
-   tlc.check(Some(si), "let a:Number[1]; a.length==0: [False];").unwrap();
-   tlc.check(Some(si), "let a:Number[1]; a.length==1: [True];").unwrap();
-   tlc.check(Some(si), "let a:Number[1]; a.length==1: [False];").unwrap_err();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(l1), "let a:I64[1]; a.length: [1];").unwrap();
+   tlc.check(Some(l1), "let a:I64[2]; a.length: [2];").unwrap();
+   tlc.check(Some(l1), "let a:I64[2]; a.length: [3];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[2]; a.length: I64;").unwrap();
+   tlc.check(Some(l1), "let a:I64[1]; a.length==0: [True];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[1]; a.length==0: [False];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1]; a.length==1: [True];").unwrap();
+   tlc.check(Some(l1), "let a:I64[1]; a.length==1: [False];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[1]; a.length==0 @reduce :[1];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[1]; a.length==0 @reduce :[0];").unwrap();
+}
+
+#[test]
+fn check_tensor_lengths() {
+   let mut tlc = TLC::new();
+   let l1 = tlc.import_file(None, "preludes/l1.tlc").unwrap();
+
+   tlc.check(Some(l1), "(1,2,3): I64[3];").unwrap();
+   tlc.check(Some(l1), "(1,2,3): I64[2];").unwrap_err();
+   tlc.check(Some(l1), "(1,2,3).length: [3];").unwrap();
+
+   tlc.check(Some(l1), "range(3): I64[3];").unwrap();
+   tlc.check(Some(l1), "range(3): I64[2];").unwrap_err();
+   tlc.check(Some(l1), "range(1,3): I64[2];").unwrap();
+   tlc.check(Some(l1), "range(1,8,3): I64[3];").unwrap();
+   tlc.check(Some(l1), "range(3).length @reduce :[3];").unwrap();
+
+   tlc.check(Some(l1), "let a:I64[2][3]; a.flatten: I64[6];").unwrap();
+   tlc.check(Some(l1), "let a:I64[2][3]; a.flatten: I64[5];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[2][]; a.flatten: I64[];").unwrap();
+   tlc.check(Some(l1), "let a:I64[2]; a.flatmap(fn(x: I64): I64[3] = (x,x,x)): I64[6];").unwrap();
+   tlc.check(Some(l1), "let a:I64[2]; (for x:I64 in a yield x): I64[2];").unwrap();
+   tlc.check(Some(l1), "(for x:I64 in range(4) yield x).length @reduce :[4];").unwrap();
+
+   tlc.check(Some(l1), "let a:I64[2]; a[1];").unwrap();
+   tlc.check(Some(l1), "let a:I64[2]; a[2];").unwrap_err();
+   tlc.check(Some(l1), "let a:I64[]; a[2];").unwrap();
+
+   //lengths that do not fit are left unknown
+   tlc.check(Some(l1), "range(0, 9223372036854775807, 2);").unwrap();
+   tlc.check(Some(l1), "let a:I64[4294967296][4294967296]; a.flatten;").unwrap();
+
+   //only the builtin operations carry lengths
+   tlc.check(Some(l1), "let range(to: I64): I64[] = (1,); range(5): I64[5];").unwrap_err();
+   tlc.check(Some(l1), "let range(to: I64): I64[] = (1,); range(5).length @reduce :[1];").unwrap();
 }
-*/
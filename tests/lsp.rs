@@ -0,0 +1,144 @@
+use std::io::{BufReader,Write};
+use std::process::{Command,Stdio};
+use serde_json::{json,Value};
+use lsts::lsp::{Server,read_message,write_message,MAX_CONTENT_LENGTH};
+
+fn session(requests: &[Value]) -> Vec<Value> {
+   let mut server = Command::new(env!("CARGO_BIN_EXE_lsts-lsp"))
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn().unwrap();
+   {
+      let mut stdin = server.stdin.take().unwrap();
+      for r in requests.iter() {
+         write_message(&mut stdin, r).unwrap();
+      }
+      stdin.flush().unwrap();
+   }
+   let mut stdout = BufReader::new(server.stdout.take().unwrap());
+   let mut responses = Vec::new();
+   while let Some(r) = read_message(&mut stdout).unwrap() {
+      responses.push(r);
+   }
+   assert!(server.wait().unwrap().success());
+   responses
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+   json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+}
+
+fn notify(method: &str, params: Value) -> Value {
+   json!({"jsonrpc": "2.0", "method": method, "params": params})
+}
+
+fn at(line: u64, character: u64) -> Value {
+   json!({"textDocument": {"uri": "file:///definitions.tlc"}, "position": {"line": line, "character": character}})
+}
+
+fn range(line: u64, start: u64, end: u64) -> Value {
+   json!({"start": {"line": line, "character": start}, "end": {"line": line, "character": end}})
+}
+
+#[test]
+fn lsp_session() {
+   let uri = "file:///definitions.tlc";
+   let rs = session(&[
+      request(1, "initialize", json!({"capabilities": {}})),
+      notify("initialized", json!({})),
+      notify("textDocument/didOpen", json!({"textDocument": {
+         "uri": uri, "languageId": "tlc", "version": 1,
+         "text": include_str!("lsp/definitions.tlc"),
+      }})),
+      request(2, "textDocument/hover", at(2,13)),
+      request(3, "textDocument/hover", at(1,15)),
+      request(4, "textDocument/definition", at(1,15)),
+      request(5, "textDocument/definition", at(5,8)),
+      request(6, "textDocument/definition", at(6,16)),
+      notify("textDocument/didChange", json!({
+         "textDocument": {"uri": uri, "version": 2},
+         "contentChanges": [{"text": "let z: U64 = 1;\n"}],
+      })),
+      request(7, "shutdown", Value::Null),
+      notify("exit", Value::Null),
+   ]);
+   assert_eq!(rs.len(), 9);
+   assert_eq!(rs[0]["result"]["capabilities"]["hoverProvider"], json!(true));
+
+   let diagnostics = &rs[1]["params"]["diagnostics"];
+   assert_eq!(rs[1]["method"], json!("textDocument/publishDiagnostics"));
+   assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+   assert_eq!(diagnostics[0]["code"], json!("E0003"));
+   assert_eq!(diagnostics[0]["range"], range(2,13,14));
+
+   assert_eq!(rs[2]["result"]["contents"]["value"], json!("y : I64"));
+   assert_eq!(rs[3]["result"]["contents"]["value"], json!("double : ((I64))->(I64)"));
+
+   //let bindings, typedefs, and named hints
+   assert_eq!(rs[4]["result"], json!([{"uri": uri, "range": range(0,0,3)}]));
+   assert_eq!(rs[5]["result"], json!([{"uri": uri, "range": range(4,0,4)}]));
+   assert_eq!(rs[6]["result"], json!([{"uri": uri, "range": range(3,0,6)}]));
+
+   assert_eq!(rs[7]["params"]["diagnostics"], json!([]));
+   assert_eq!(rs[8]["id"], json!(7));
+}
+
+#[test]
+fn lsp_scopes_and_imports() {
+   //documents are checked under the path of their URI, so imports are relative to it
+   let uri = format!("file://{}/tests/modules/user%20file.tlc", env!("CARGO_MANIFEST_DIR"));
+   let text = "import $\"preludes/l1.tlc\";\nimport $\"increment.tlc\";\nlet x: I64 = increment(1);\nlet f(x: I64): I64 = x + 1;\n";
+   let at = |line: u64, character: u64| json!({"textDocument": {"uri": uri}, "position": {"line": line, "character": character}});
+   let rs = session(&[
+      request(1, "initialize", json!({"capabilities": {}})),
+      notify("textDocument/didOpen", json!({"textDocument": {"uri": uri, "languageId": "tlc", "version": 1, "text": text}})),
+      request(2, "textDocument/definition", at(3,21)),
+      notify("textDocument/didOpen", json!({"textDocument": {
+         "uri": "file:///hint.tlc", "languageId": "tlc", "version": 1,
+         "text": "forall @h x:I64. I64 = (x: I64);\n(1: I64) @h;\n",
+      }})),
+      request(3, "textDocument/hover", at(2,13)),
+      request(4, "shutdown", Value::Null),
+      notify("exit", Value::Null),
+   ]);
+   assert_eq!(rs.len(), 6);
+   assert_eq!(rs[1]["params"]["diagnostics"], json!([]));
+
+   //a parameter shadows the global of the same name, and is defined at its name
+   assert_eq!(rs[2]["result"], json!([{"uri": uri, "range": range(3,6,7)}]));
+
+   //a document that fails to check does not stop the server
+   let diagnostics = &rs[3]["params"]["diagnostics"];
+   assert_eq!(rs[3]["method"], json!("textDocument/publishDiagnostics"));
+   assert_eq!(rs[3]["params"]["uri"], json!("file:///hint.tlc"));
+   assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+   assert_eq!(diagnostics[0]["code"], json!("E0003"));
+   assert_eq!(diagnostics[0]["range"], range(1,1,7));
+   assert_eq!(diagnostics[0]["message"], json!("Type Error, hint h can not yet match an ascription in term: (1:I64)"));
+   assert_eq!(rs[4]["result"]["contents"]["value"], json!("increment : ((I64))->(I64)"));
+   assert_eq!(rs[5]["id"], json!(4));
+}
+
+#[test]
+fn lsp_malformed_messages() {
+   //a message that is not JSON or is too long is answered with a parse error, and the server keeps serving
+   let mut input = Vec::new();
+   write!(input, "Content-Length: 9\r\n\r\n{{\"id\": 1,").unwrap();
+   write!(input, "Content-Length: {}\r\n\r\n{}", MAX_CONTENT_LENGTH+1, " ".repeat(MAX_CONTENT_LENGTH+1)).unwrap();
+   write_message(&mut input, &request(2, "shutdown", Value::Null)).unwrap();
+   write_message(&mut input, &notify("exit", Value::Null)).unwrap();
+   let mut output = Vec::new();
+   Server::new().serve(&mut input.as_slice(), &mut output).unwrap();
+
+   let mut output = output.as_slice();
+   let mut rs = Vec::new();
+   while let Some(r) = read_message(&mut output).unwrap() {
+      rs.push(r);
+   }
+   assert_eq!(rs.len(), 3);
+   assert_eq!(rs[0]["error"]["code"], json!(-32700));
+   assert_eq!(rs[0]["id"], Value::Null);
+   assert_eq!(rs[1]["error"]["code"], json!(-32700));
+   assert!(rs[1]["error"]["message"].as_str().unwrap().contains("is longer than"));
+   assert_eq!(rs[2]["id"], json!(2));
+}
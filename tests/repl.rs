@@ -0,0 +1,49 @@
+use lsts::repl::Repl;
+
+#[test]
+fn repl_statements() {
+   let mut repl = Repl::new();
+
+   assert_eq!(repl.feed("let a: I64 = 2;").unwrap().unwrap(), "a : I64\n");
+   assert!(repl.feed("let b: I64 =").is_none());
+   assert!(repl.is_continued());
+   assert_eq!(repl.feed("   a + 3;").unwrap().unwrap(), "b : I64\n");
+   assert_eq!(repl.feed("a + b;").unwrap().unwrap(), "7 : I64\n");
+
+   //errors do not reset accumulated definitions
+   repl.feed("let c: U64 = a;").unwrap().unwrap_err();
+   repl.feed("c;").unwrap().unwrap_err();
+   assert_eq!(repl.feed("b * 2;").unwrap().unwrap(), "10 : I64\n");
+}
+
+#[test]
+fn repl_commands() {
+   let mut repl = Repl::new();
+
+   repl.feed("let a: I64 = 2;").unwrap().unwrap();
+   assert_eq!(repl.feed(":type a + 1").unwrap().unwrap(), "I64\n");
+   assert!(repl.feed(":scope").unwrap().unwrap().contains("\ta: I64 with"));
+   assert!(repl.feed(":rules").unwrap().unwrap().contains("Odd:Integer::Term"));
+   assert_eq!(repl.feed(":load examples/adder.tlc").unwrap().unwrap(), "loaded examples/adder.tlc\n");
+   assert_eq!(repl.feed("add(a, 3);").unwrap().unwrap(), "5 : I64\n");
+   repl.feed(":load examples/missing.tlc").unwrap().unwrap_err();
+
+   //a file that fails to check is rolled back like a failed statement
+   let rules = repl.feed(":rules").unwrap().unwrap();
+   repl.feed(":load tests/packages/broken/src/bad.tlc").unwrap().unwrap_err();
+   assert_eq!(repl.feed(":rules").unwrap().unwrap(), rules);
+   repl.feed("bad(1);").unwrap().unwrap_err();
+   assert_eq!(repl.feed("add(a, 4);").unwrap().unwrap(), "6 : I64\n");
+}
+
+#[test]
+fn repl_session() {
+   let mut repl = Repl::new();
+   let mut output = Vec::new();
+   repl.run(&mut "let a: I64 = 2;\n(a +\n 1);\nlet c: U64 = a;\n:quit\na;\n".as_bytes(), &mut output).unwrap();
+   let output = String::from_utf8(output).unwrap();
+   assert!(output.starts_with("lsts> a : I64\nlsts> ....> 3 : I64\n"));
+   assert!(output.contains("error[E0003]"));
+   assert!(output.contains("1 | let c: U64 = a;\n  |              ^\n"));
+   assert!(output.ends_with("lsts> "));
+}